@@ -1,26 +1,61 @@
 use inquire::Text;
-use octocrab::Page;
 use serde::Serialize;
-use skootrs_lib::service::{project::ProjectService, source::LocalSourceService};
+use skootrs_lib::service::{
+    github::GithubClient,
+    pin::{ActionPin, ActionPins, ActionReference},
+    project::ProjectService,
+    source::LocalSourceService,
+};
 use skootrs_model::skootrs::{
-    facet::InitializedFacet, Config, EcosystemInitializeParams, FacetGetParams, FacetMapKey,
-    GithubRepoParams, GithubUser, GoParams, InitializedProject, ProjectArchiveParams,
-    ProjectCreateParams, ProjectGetParams, ProjectOutput, ProjectOutputGetParams,
-    ProjectOutputReference, ProjectOutputType, ProjectOutputsListParams, ProjectReleaseParam,
-    ProjectUpdateParams, RepoCreateParams, SkootError, SourceInitializeParams, SupportedEcosystems,
+    facet::{
+        BranchProtectionMode, BranchProtectionParams, CodeReviewConfig, FacetConfig, GUACEndpoint,
+        GUACForwardingConfig, InitializedFacet, WritePolicy,
+    },
+    state::ProjectReference,
+    Config, EcosystemInitializeParams, FacetGetParams, FacetMapKey, GithubRepoParams,
+    GithubRepoSettings, GithubUser, GoParams, InitializedProject, ProjectAdoptParams,
+    ProjectAdoptResult, ProjectArchiveParams, ProjectCreateParams, ProjectCreateProgress,
+    ProjectDiscoverParams, ProjectFacetRemoveParams, ProjectGetParams, ProjectOutput,
+    ProjectOutputGetParams, ProjectOutputReference, ProjectOutputType, ProjectOutputsListParams,
+    ProjectPullRequestParams, ProjectReleaseParam, ProjectUpdateParams, PullRequestParams,
+    RepoCreateParams, RepoVisibility, RollbackStrategy, SkootError, SourceInitializeParams,
+    SupportedEcosystems,
 };
 use std::{
-    collections::{HashMap, HashSet},
+    collections::HashMap,
     io::Write,
+    path::{Path, PathBuf},
     str::FromStr,
 };
 use strum::VariantNames;
 use tracing::debug;
 
 use skootrs_statestore::{
-    GitProjectStateStore, InMemoryProjectReferenceCache, ProjectReferenceCache, ProjectStateStore,
+    sqlite::SqliteProjectReferenceCache, GitProjectStateStore, ProjectReferenceCache,
+    ProjectStateStore,
 };
 
+/// The location of the JSON reference cache used by earlier versions of Skootrs.
+const LEGACY_CACHE_PATH: &str = "./skootcache";
+
+/// The suffix of the files the progress of creating a project is recorded in.
+const CREATION_PROGRESS_SUFFIX: &str = ".skootrs-create.json";
+
+/// Returns the file the progress of creating a project is recorded in until it's created. It's kept next to the
+/// clone rather than in it, so it isn't committed.
+fn creation_progress_path(parent_path: &str, name: &str) -> PathBuf {
+    Path::new(parent_path).join(format!(".{name}{CREATION_PROGRESS_SUFFIX}"))
+}
+
+/// Opens the local project reference cache, importing the legacy `./skootcache` file the first time.
+/// Projects read through the cache are verified with the state signing settings from `config`.
+fn open_reference_cache(config: &Config) -> Result<SqliteProjectReferenceCache, SkootError> {
+    let mut cache = SqliteProjectReferenceCache::open_default()?;
+    cache.signing = config.state_signing.clone();
+    cache.import_legacy_json(Path::new(LEGACY_CACHE_PATH))?;
+    Ok(cache)
+}
+
 /// Helper trait that lets me inline writing the result of a Skootrs function to a writer.
 pub trait HandleResponseOutput<T> {
     #[must_use]
@@ -60,63 +95,198 @@ impl Project {
     /// The project is created in Github, cloned down, and then initialized along with any other security supporting
     /// tasks. If the `project_params` is not provided, the user will be prompted for the project details.
     ///
+    /// The progress of the creation is recorded next to the clone so it can be resumed with `resume` if a step
+    /// fails. If a `rollback` strategy is given, a failed creation is rolled back instead.
+    ///
     /// # Errors
     ///
     /// Returns an error if the user is not authenticated with Github, or if the project can't be created
     /// for any other reason.
     pub async fn create<'a, T: ProjectService + ?Sized>(
         config: &Config,
+        github: &GithubClient,
         project_service: &'a T,
         project_params: Option<ProjectCreateParams>,
+        rollback: Option<RollbackStrategy>,
     ) -> Result<InitializedProject, SkootError> {
         let project_params = match project_params {
             Some(p) => p,
-            None => Project::prompt_create(config).await?,
+            None => Project::prompt_create(config, github).await?,
         };
 
-        let project = project_service.initialize(project_params).await?;
-        let git_state_store = GitProjectStateStore {
-            source: project.source.clone(),
-            source_service: LocalSourceService {},
+        Project::run_creation(
+            config,
+            project_service,
+            ProjectCreateProgress::new(project_params),
+            rollback,
+        )
+        .await
+    }
+
+    /// Resumes a project creation that failed part way through from the step that failed. If the
+    /// `project_params` aren't provided, the user is prompted for the creation to resume.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if there's no creation to resume, or if the project can't be created.
+    pub async fn resume<T: ProjectService + ?Sized>(
+        config: &Config,
+        project_service: &T,
+        project_params: Option<ProjectCreateParams>,
+        rollback: Option<RollbackStrategy>,
+    ) -> Result<InitializedProject, SkootError> {
+        let progress_path = match project_params {
+            Some(p) => creation_progress_path(&p.source_params.parent_path, &p.name),
+            None => Project::prompt_resume(config)?,
         };
+        let progress = std::fs::read_to_string(&progress_path).map_err(|error| {
+            format!(
+                "There's no project creation to resume at {}: {error}",
+                progress_path.display()
+            )
+        })?;
+        let progress: ProjectCreateProgress = serde_json::from_str(&progress)?;
+
+        Project::run_creation(config, project_service, progress, rollback).await
+    }
 
-        let mut local_cache = InMemoryProjectReferenceCache::load_or_create("./skootcache")?;
-        git_state_store.create(project.clone()).await?;
-        local_cache.set(project.repo.full_url()).await?;
-        Ok(project)
+    async fn run_creation<T: ProjectService + ?Sized>(
+        config: &Config,
+        project_service: &T,
+        progress: ProjectCreateProgress,
+        rollback: Option<RollbackStrategy>,
+    ) -> Result<InitializedProject, SkootError> {
+        let progress_path = creation_progress_path(
+            &progress.params.source_params.parent_path,
+            &progress.params.name,
+        );
+        let record = |progress: &ProjectCreateProgress| -> Result<(), SkootError> {
+            std::fs::write(&progress_path, serde_json::to_string_pretty(progress)?)?;
+            Ok(())
+        };
+        record(&progress)?;
+
+        let mut latest = progress.clone();
+        let created = async {
+            let project = project_service
+                .resume(progress, |progress| {
+                    latest = progress.clone();
+                    record(progress)
+                })
+                .await?;
+            // Writing the state and caching the project are steps of the creation too, so a failure in
+            // either can be resumed or rolled back like any other step.
+            if !latest.state_written {
+                let git_state_store = GitProjectStateStore {
+                    source: project.source.clone(),
+                    source_service: LocalSourceService {},
+                    signing: config.state_signing.clone(),
+                };
+                git_state_store.create(project.clone()).await?;
+                latest.state_written = true;
+                record(&latest)?;
+            }
+            if !latest.cached {
+                let mut local_cache = open_reference_cache(config)?;
+                local_cache.set_project(&project).await?;
+                latest.cached = true;
+                record(&latest)?;
+            }
+            Ok::<_, SkootError>(project)
+        }
+        .await;
+
+        match created {
+            Ok(project) => {
+                std::fs::remove_file(&progress_path)?;
+                Ok(project)
+            }
+            Err(error) => {
+                Err(Project::fail_creation(project_service, &progress_path, rollback, &error).await)
+            }
+        }
     }
 
-    async fn prompt_create(config: &Config) -> Result<ProjectCreateParams, SkootError> {
+    /// Rolls back a failed creation if a rollback strategy is given, otherwise points the user at resuming it.
+    async fn fail_creation<T: ProjectService + ?Sized>(
+        project_service: &T,
+        progress_path: &Path,
+        rollback: Option<RollbackStrategy>,
+        error: &SkootError,
+    ) -> SkootError {
+        let Some(strategy) = rollback else {
+            return format!(
+                "{error}. The progress is recorded in {}, run `skootrs project create --resume` to pick up from the failed step",
+                progress_path.display()
+            )
+            .into();
+        };
+
+        let rolled_back = async {
+            let progress: ProjectCreateProgress =
+                serde_json::from_str(&std::fs::read_to_string(progress_path)?)?;
+            project_service.rollback(&progress, strategy).await?;
+            std::fs::remove_file(progress_path)?;
+            Ok::<(), SkootError>(())
+        }
+        .await;
+        match rolled_back {
+            Ok(()) => format!("{error}. The creation was rolled back").into(),
+            Err(rollback_error) => format!(
+                "{error}. Rolling back the creation failed: {rollback_error}. The progress is recorded in {}",
+                progress_path.display()
+            )
+            .into(),
+        }
+    }
+
+    /// Prompts for the project creation to resume out of the ones that failed in the local project path.
+    fn prompt_resume(config: &Config) -> Result<PathBuf, SkootError> {
+        let mut names = std::fs::read_dir(&config.local_project_path)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                entry
+                    .file_name()
+                    .to_str()?
+                    .strip_prefix('.')?
+                    .strip_suffix(CREATION_PROGRESS_SUFFIX)
+                    .map(str::to_string)
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        let name = match names.len() {
+            0 => return Err("There's no project creation to resume".into()),
+            1 => names.remove(0),
+            _ => inquire::Select::new("Select a project creation to resume", names).prompt()?,
+        };
+        Ok(creation_progress_path(&config.local_project_path, &name))
+    }
+
+    async fn prompt_create(
+        config: &Config,
+        github: &GithubClient,
+    ) -> Result<ProjectCreateParams, SkootError> {
         let name = Text::new("The name of the repository").prompt()?;
         let description = Text::new("The description of the repository").prompt()?;
-        let user = octocrab::instance().current().user().await?.login;
-        let Page { items, .. } = octocrab::instance()
-            .current()
-            .list_org_memberships_for_authenticated_user()
-            .send()
-            .await?;
+        let accounts = github.accounts().await?;
         let organization = inquire::Select::new(
             "Select an organization",
-            items
-                .iter()
-                .map(|i| i.organization.login.as_str())
-                .chain(vec![user.as_str()])
-                .collect(),
+            accounts.iter().map(GithubUser::get_name).collect(),
         )
         .prompt()?;
         let language =
             inquire::Select::new("Select a language", SupportedEcosystems::VARIANTS.to_vec());
 
-        let gh_org = match organization {
-            x if x == user => GithubUser::User(x.to_string()),
-            x => GithubUser::Organization(x.to_string()),
-        };
+        let gh_org = accounts
+            .into_iter()
+            .find(|account| account.get_name() == organization)
+            .ok_or("The selected organization isn't available")?;
 
         let language_prompt = language.prompt()?;
         let ecosystem_params = match SupportedEcosystems::from_str(language_prompt)? {
             SupportedEcosystems::Go => EcosystemInitializeParams::Go(GoParams {
                 name: name.clone(),
-                host: format!("github.com/{organization}"),
+                host: format!("{}/{organization}", config.github_host),
             }),
             // TODO: Re-add Maven support.
             // TODO: Unclear if this is the right way to handle Maven group and artifact.
@@ -129,7 +299,9 @@ impl Project {
         let repo_params = RepoCreateParams::Github(GithubRepoParams {
             name: name.clone(),
             description,
+            settings: Project::prompt_repo_settings(&gh_org)?,
             organization: gh_org,
+            host: config.github_host.clone(),
         });
 
         Ok(ProjectCreateParams {
@@ -139,9 +311,222 @@ impl Project {
             source_params: SourceInitializeParams {
                 parent_path: config.local_project_path.clone(),
             },
+            facet_configs: Project::prompt_facet_configs()?,
+            excluded_facets: vec![],
+        })
+    }
+
+    /// Prompts for the settings to create the repository with. The less common ones, like a template repo or
+    /// the merge strategies, can be set by passing the project parameters as input instead.
+    fn prompt_repo_settings(organization: &GithubUser) -> Result<GithubRepoSettings, SkootError> {
+        // Only organization repos can be internal.
+        let visibilities = RepoVisibility::VARIANTS
+            .iter()
+            .copied()
+            .filter(|visibility| {
+                matches!(organization, GithubUser::Organization(_))
+                    || *visibility != RepoVisibility::Internal.to_string()
+            })
+            .collect();
+        let visibility = RepoVisibility::from_str(
+            inquire::Select::new("Select the repository visibility", visibilities).prompt()?,
+        )?;
+        let topics = Text::new("The topics of the repository, separated by spaces")
+            .prompt_skippable()?
+            .map(|topics| topics.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+        let enable_security_features =
+            inquire::Confirm::new("Enable secret scanning, push protection and Dependabot alerts?")
+                .with_default(true)
+                .prompt()?;
+        Ok(GithubRepoSettings {
+            visibility,
+            topics,
+            secret_scanning: enable_security_features,
+            secret_scanning_push_protection: enable_security_features,
+            dependabot_alerts: enable_security_features,
+            ..GithubRepoSettings::default()
+        })
+    }
+
+    /// Prompts for the configuration of the optional facets the user wants to enable.
+    fn prompt_facet_configs() -> Result<Vec<FacetConfig>, SkootError> {
+        let mut facet_configs = Vec::new();
+        let publish_packages = inquire::Confirm::new(
+            "Publish the project's packages when a release is published? (this needs registry credentials)",
+        )
+        .with_default(false)
+        .prompt()?;
+        if publish_packages {
+            facet_configs.push(FacetConfig::PublishPackages);
+        }
+        let forward_to_guac =
+            inquire::Confirm::new("Forward the SBOMs and attestations of releases to GUAC?")
+                .with_default(false)
+                .prompt()?;
+        if forward_to_guac {
+            let collect_sub = "GUAC collectsub service";
+            let blob = "S3 compatible bucket";
+            let endpoint = match inquire::Select::new(
+                "Where should they be forwarded to?",
+                vec![collect_sub, blob],
+            )
+            .prompt()?
+            {
+                x if x == collect_sub => GUACEndpoint::CollectSub {
+                    csub_address: Text::new("The address of the collectsub service").prompt()?,
+                    graphql_address: Text::new("The URL of the GUAC GraphQL API").prompt()?,
+                },
+                _ => GUACEndpoint::Blob {
+                    bucket_url: Text::new("The bucket URL, e.g. s3://bucket/prefix").prompt()?,
+                    endpoint_url: Text::new("The S3 compatible endpoint URL, if not using AWS")
+                        .prompt_skippable()?
+                        .filter(|url| !url.is_empty()),
+                },
+            };
+            facet_configs.push(FacetConfig::GUACForwarding(GUACForwardingConfig {
+                endpoint,
+            }));
+        }
+        let require_code_review = inquire::Confirm::new("Require code review of pull requests?")
+            .with_default(false)
+            .prompt()?;
+        if require_code_review {
+            let required_approving_review_count =
+                inquire::CustomType::<u8>::new("How many approvals are required?")
+                    .with_default(1)
+                    .prompt()?;
+            let dismiss_stale_reviews =
+                inquire::Confirm::new("Dismiss approvals when new commits are pushed?")
+                    .with_default(true)
+                    .prompt()?;
+            let code_owners = Text::new(
+                "The code owners, separated by spaces, e.g. @org/team @user (their review will be required)",
+            )
+            .prompt_skippable()?
+            .map(|owners| owners.split_whitespace().map(str::to_string).collect())
+            .unwrap_or_default();
+            facet_configs.push(FacetConfig::CodeReview(CodeReviewConfig {
+                required_approving_review_count,
+                dismiss_stale_reviews,
+                code_owners,
+            }));
+        }
+        let customize_branch_protection = inquire::Confirm::new("Customize the branch protection?")
+            .with_default(false)
+            .prompt()?;
+        if customize_branch_protection {
+            let classic = "Classic branch protection";
+            let ruleset = "Repository ruleset";
+            let mode = match inquire::Select::new(
+                "How should the branch be protected?",
+                vec![classic, ruleset],
+            )
+            .prompt()?
+            {
+                x if x == ruleset => BranchProtectionMode::Ruleset,
+                _ => BranchProtectionMode::Classic,
+            };
+            let branch = Text::new(
+                "The branch to protect, if not the default branch (rulesets also accept a pattern)",
+            )
+            .prompt_skippable()?
+            .filter(|branch| !branch.is_empty());
+            let require_signed_commits = inquire::Confirm::new("Require signed commits?")
+                .with_default(false)
+                .prompt()?;
+            facet_configs.push(FacetConfig::BranchProtection(BranchProtectionParams {
+                branch,
+                mode,
+                require_signed_commits,
+                ..BranchProtectionParams::default()
+            }));
+        }
+        Ok(facet_configs)
+    }
+
+    /// Adopts an existing repository as a Skootrs project. The missing default facets and the initial
+    /// state file are committed to a branch and proposed with a pull request. If the `project_params`
+    /// is not provided, the user will be prompted for the adoption details.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repository can't be adopted or the pull request can't be opened.
+    pub async fn adopt<T: ProjectService + ?Sized>(
+        config: &Config,
+        project_service: &T,
+        project_params: Option<ProjectAdoptParams>,
+    ) -> Result<ProjectAdoptResult, SkootError> {
+        let project_params = match project_params {
+            Some(p) => p,
+            None => Project::prompt_adopt(config)?,
+        };
+
+        let overwrite_existing = project_params.overwrite_existing;
+        let mut adoption = project_service.adopt(project_params).await?;
+        let git_state_store = GitProjectStateStore {
+            source: adoption.project.source.clone(),
+            source_service: LocalSourceService {},
+            signing: config.state_signing.clone(),
+        };
+        // The state of a project that was already adopted is only replaced when overwriting was asked for.
+        if overwrite_existing {
+            git_state_store.update(adoption.project.clone()).await?;
+        } else {
+            git_state_store.create(adoption.project.clone()).await?;
+        }
+
+        let pull_request_url = project_service
+            .open_pull_request(ProjectPullRequestParams {
+                initialized_project: adoption.project.clone(),
+                pull_request: PullRequestParams {
+                    head: adoption.branch.clone(),
+                    base: adoption.project.repo.default_branch().to_string(),
+                    title: "Adopt project with Skootrs".to_string(),
+                    body: Project::adoption_pull_request_body(&adoption),
+                },
+            })
+            .await?;
+        adoption.pull_request_url = Some(pull_request_url);
+
+        let mut local_cache = open_reference_cache(config)?;
+        local_cache.set_project(&adoption.project).await?;
+        Ok(adoption)
+    }
+
+    fn prompt_adopt(config: &Config) -> Result<ProjectAdoptParams, SkootError> {
+        let repo_url = Text::new("The URL of the repository to adopt").prompt()?;
+        let overwrite_existing =
+            inquire::Confirm::new("Overwrite files that already exist in the repository?")
+                .with_default(false)
+                .prompt()?;
+        Ok(ProjectAdoptParams {
+            repo_url,
+            source_params: SourceInitializeParams {
+                parent_path: config.local_project_path.clone(),
+            },
+            overwrite_existing,
         })
     }
 
+    fn adoption_pull_request_body(adoption: &ProjectAdoptResult) -> String {
+        let mut body = String::from("This adopts the project with Skootrs.\n\nApplied facets:\n");
+        for facet in &adoption.applied_facets {
+            body.push_str(&format!("- {facet}\n"));
+        }
+        if !adoption.conflicting_facets.is_empty() {
+            body.push_str("\nSkipped facets that conflict with existing files:\n");
+            for conflict in &adoption.conflicting_facets {
+                body.push_str(&format!(
+                    "- {}: {}\n",
+                    conflict.facet,
+                    conflict.existing_files.join(", ")
+                ));
+            }
+        }
+        body
+    }
+
     /// Fetches the contents of an `InitializedProject` along with an interactive prompt.
     ///
     /// # Errors
@@ -152,7 +537,7 @@ impl Project {
         _project_service: &'a T,
         project_get_params: Option<ProjectGetParams>,
     ) -> Result<InitializedProject, SkootError> {
-        let mut cache = InMemoryProjectReferenceCache::load_or_create("./skootcache")?;
+        let mut cache = open_reference_cache(config)?;
         let project_get_params = match project_get_params {
             Some(p) => p,
             None => Project::prompt_get(config).await?,
@@ -163,10 +548,13 @@ impl Project {
 
     async fn prompt_get(config: &Config) -> Result<ProjectGetParams, SkootError> {
         let projects = Project::list(config).await?;
-        let selected_project =
-            inquire::Select::new("Select a project", projects.iter().collect()).prompt()?;
+        let selected_project = inquire::Select::new(
+            "Select a project",
+            projects.iter().map(|p| p.repo_url.as_str()).collect(),
+        )
+        .prompt()?;
         Ok(ProjectGetParams {
-            project_url: selected_project.clone(),
+            project_url: selected_project.to_string(),
         })
     }
 
@@ -180,13 +568,20 @@ impl Project {
         project_service: &'a T,
         project_update_params: Option<ProjectUpdateParams>,
     ) -> Result<InitializedProject, SkootError> {
-        let mut cache = InMemoryProjectReferenceCache::load_or_create("./skootcache")?;
+        let mut cache = open_reference_cache(config)?;
         let project_update_params = match project_update_params {
             Some(p) => p,
             None => Project::prompt_update(config, project_service).await?,
         };
         let updated_project = project_service.update(project_update_params).await?;
-        cache.set(updated_project.repo.full_url()).await?;
+        // The state records which facets are applied, so removed facets stay removed.
+        let git_state_store = GitProjectStateStore {
+            source: updated_project.source.clone(),
+            source_service: LocalSourceService {},
+            signing: config.state_signing.clone(),
+        };
+        git_state_store.update(updated_project.clone()).await?;
+        cache.set_project(&updated_project).await?;
         Ok(updated_project)
     }
 
@@ -197,20 +592,61 @@ impl Project {
         let initialized_project = Project::get(config, project_service, None).await?;
         Ok(ProjectUpdateParams {
             initialized_project,
+            write_policy: WritePolicy::ThreeWayMerge,
+            facet_configs: None,
+            excluded_facets: None,
         })
     }
 
-    /// Returns the list of projects that are stored in the cache.
+    /// Returns the list of projects that are stored in the cache along with their metadata.
     ///
     /// # Errors
     ///
     /// Returns an error if the cache can't be loaded or if the list of projects can't be fetched.
-    pub async fn list(_config: &Config) -> Result<HashSet<String>, SkootError> {
-        let cache = InMemoryProjectReferenceCache::load_or_create("./skootcache")?;
-        let projects: HashSet<String> = cache.list().await?;
+    pub async fn list(config: &Config) -> Result<Vec<ProjectReference>, SkootError> {
+        let cache = open_reference_cache(config)?;
+        let projects = cache.list_references().await?;
         Ok(projects)
     }
 
+    /// Imports an existing project into the local cache after checking that its state file is valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the project's state can't be fetched or is invalid, or if the project can't be
+    /// added to the cache.
+    pub async fn import<T: ProjectService + ?Sized>(
+        config: &Config,
+        project_service: &T,
+        project_get_params: ProjectGetParams,
+    ) -> Result<InitializedProject, SkootError> {
+        let project = project_service.get(project_get_params).await?;
+        let mut cache = open_reference_cache(config)?;
+        cache.set_project(&project).await?;
+        Ok(project)
+    }
+
+    /// Discovers the existing projects that belong to an organization or user and imports them into the
+    /// local cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repos can't be scanned or if the projects can't be added to the cache.
+    pub async fn discover<T: ProjectService + ?Sized>(
+        config: &Config,
+        project_service: &T,
+        project_discover_params: ProjectDiscoverParams,
+    ) -> Result<Vec<ProjectReference>, SkootError> {
+        let projects = project_service.discover(project_discover_params).await?;
+        let mut cache = open_reference_cache(config)?;
+        let mut references = Vec::new();
+        for project in &projects {
+            cache.set_project(project).await?;
+            references.push(ProjectReference::from_project(project));
+        }
+        Ok(references)
+    }
+
     /// Archives a project by archiving the repository and removing it from the local cache.
     ///
     /// # Errors
@@ -229,9 +665,8 @@ impl Project {
         };
         let url = project_archive_params.initialized_project.repo.full_url();
         project_service.archive(project_archive_params).await?;
-        let mut local_cache = InMemoryProjectReferenceCache::load_or_create("./skootcache")?;
+        let mut local_cache = open_reference_cache(config)?;
         local_cache.delete(url).await?;
-        local_cache.save()?;
         Ok(())
     }
 }
@@ -299,6 +734,75 @@ impl Facet {
         let facet_map_keys = project_service.list_facets(project_get_params).await?;
         Ok(facet_map_keys)
     }
+
+    /// Removes a facet from a project and records the project's new state. This includes removing the facet's
+    /// files and reverting the settings it applied.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the facet can't be removed, or if the project's state can't be recorded.
+    pub async fn remove<T: ProjectService + ?Sized>(
+        config: &Config,
+        project_service: &T,
+        facet_remove_params: Option<ProjectFacetRemoveParams>,
+        force: bool,
+    ) -> Result<InitializedProject, SkootError> {
+        let mut facet_remove_params = if let Some(p) = facet_remove_params {
+            p
+        } else {
+            let project_get_params = Project::prompt_get(config).await?;
+            let facet_map_keys = project_service
+                .list_facets(project_get_params.clone())
+                .await?;
+            let facet_map_key = Facet::prompt_get(config, facet_map_keys)?;
+            if !inquire::Confirm::new(&format!(
+                "Remove the {facet_map_key} facet? Its files are removed and the settings it applied are reverted."
+            ))
+            .with_default(false)
+            .prompt()?
+            {
+                return Err("Facet removal cancelled".into());
+            }
+            ProjectFacetRemoveParams {
+                project_get_params,
+                facet_map_key,
+                force: false,
+            }
+        };
+        facet_remove_params.force |= force;
+
+        let message = format!("Removed the {} facet", facet_remove_params.facet_map_key);
+        let project = project_service.remove_facet(facet_remove_params).await?;
+        let git_state_store = GitProjectStateStore {
+            source: project.source.clone(),
+            source_service: LocalSourceService {},
+            signing: config.state_signing.clone(),
+        };
+        // The removed files are committed along with the new state so the two can't get out of sync.
+        git_state_store.write_state(project.clone(), message)?;
+        let mut cache = open_reference_cache(config)?;
+        cache.set_project(&project).await?;
+        Ok(project)
+    }
+
+    /// Resolves the GitHub Actions pins against GitHub, along with any additional `actions`, and caches
+    /// the result for the PinnedDependencies facet.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if an action reference is invalid, can't be resolved, or the cache can't be written.
+    pub async fn refresh_pins(
+        github: &GithubClient,
+        actions: &[String],
+    ) -> Result<Vec<ActionPin>, SkootError> {
+        let additional = actions
+            .iter()
+            .map(|action| action.parse::<ActionReference>())
+            .collect::<Result<Vec<_>, _>>()?;
+        ActionPins::load()
+            .refresh(&*github.octocrab().await?, &additional)
+            .await
+    }
 }
 
 pub struct Output;
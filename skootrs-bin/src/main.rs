@@ -30,15 +30,18 @@ pub mod helpers;
 
 use std::io::stdout;
 
-use clap::{Parser, Subcommand};
+use clap::{ArgGroup, Parser, Subcommand};
 use clio::Input;
 use skootrs_lib::service::ecosystem::LocalEcosystemService;
 use skootrs_lib::service::facet::LocalFacetService;
+use skootrs_lib::service::github::GithubClient;
 use skootrs_lib::service::output::LocalOutputService;
 use skootrs_lib::service::project::LocalProjectService;
 use skootrs_lib::service::repo::LocalRepoService;
 use skootrs_lib::service::source::LocalSourceService;
-use skootrs_model::skootrs::SkootError;
+use skootrs_model::skootrs::{
+    GithubUser, ProjectDiscoverParams, ProjectGetParams, RollbackStrategy, SkootError,
+};
 
 use helpers::{Facet, HandleResponseOutput, Output};
 use opentelemetry::global;
@@ -99,6 +102,21 @@ enum ProjectCommands {
         /// This is expected to be YAML or JSON. If it is not provided, the CLI will prompt the user for the input.
         #[clap(value_parser)]
         input: Option<Input>,
+        /// Resume a creation that failed part way through from the step that failed.
+        #[clap(long)]
+        resume: bool,
+        /// Roll back the creation if it fails, by removing the local clone and either deleting or archiving
+        /// the repo.
+        #[clap(long, value_name = "delete|archive")]
+        rollback: Option<RollbackStrategy>,
+    },
+    /// Adopt an existing repository as a project.
+    #[command(name = "adopt")]
+    Adopt {
+        /// This is an optional input parameter that can be used to pass in a file, pipe, url, or stdin.
+        /// This is expected to be YAML or JSON. If it is not provided, the CLI will prompt the user for the input.
+        #[clap(value_parser)]
+        input: Option<Input>,
     },
     /// Get the metadata for a particular project.
     #[command(name = "get")]
@@ -130,6 +148,25 @@ enum ProjectCommands {
     /// List all the projects known to the local Skootrs
     #[command(name = "list")]
     List,
+
+    /// Import an existing project so it's known to the local Skootrs.
+    #[command(name = "import")]
+    Import {
+        /// The URL of the repo of the project to import, e.g. `https://github.com/myorg/myproject`.
+        url: String,
+    },
+
+    /// Discover and import all the existing projects that belong to an organization or user.
+    #[command(name = "discover")]
+    #[clap(group(ArgGroup::new("owner").required(true)))]
+    Discover {
+        /// The organization whose repos should be scanned for projects.
+        #[clap(long, group = "owner")]
+        org: Option<String>,
+        /// The user whose repos should be scanned for projects.
+        #[clap(long, group = "owner")]
+        user: Option<String>,
+    },
 }
 
 /// This is the enum for what nouns the `facet` command can take.
@@ -151,6 +188,28 @@ enum FacetCommands {
         #[clap(value_parser)]
         input: Option<Input>,
     },
+    /// Remove a facet from a particular project.
+    ///
+    /// The facet's files are removed and the settings it applied are reverted, and it isn't applied again
+    /// when the project is updated.
+    #[command(name = "remove")]
+    Remove {
+        /// This is an optional input parameter that can be used to pass in a file, pipe, url, or stdin.
+        /// This is expected to be YAML or JSON. If it is not provided, the CLI will prompt the user for the input.
+        #[clap(value_parser)]
+        input: Option<Input>,
+        /// Remove the facet's files even if they were modified since Skootrs last wrote them.
+        #[clap(long)]
+        force: bool,
+    },
+    /// Refresh the commit SHAs the PinnedDependencies facet pins GitHub Actions to.
+    /// The refreshed pins are cached and used instead of the ones that ship with Skootrs.
+    #[command(name = "refresh-pins")]
+    RefreshPins {
+        /// Additional actions to resolve, e.g. `actions/cache@v4`.
+        #[clap(long = "action")]
+        actions: Vec<String>,
+    },
 }
 
 /// This is the enum for what nouns the `output` command can take.
@@ -211,7 +270,9 @@ fn init_tracing() {
 }
 
 /// TODO: This probably should be configurable in some way.
-fn init_project_service() -> LocalProjectService<
+fn init_project_service(
+    github: &GithubClient,
+) -> LocalProjectService<
     LocalRepoService,
     LocalEcosystemService,
     LocalSourceService,
@@ -219,11 +280,17 @@ fn init_project_service() -> LocalProjectService<
     LocalOutputService,
 > {
     LocalProjectService {
-        repo_service: LocalRepoService {},
+        repo_service: LocalRepoService {
+            github: github.clone(),
+        },
         ecosystem_service: LocalEcosystemService {},
         source_service: LocalSourceService {},
-        facet_service: LocalFacetService {},
-        output_service: LocalOutputService {},
+        facet_service: LocalFacetService {
+            github: github.clone(),
+        },
+        output_service: LocalOutputService {
+            github: github.clone(),
+        },
     }
 }
 
@@ -246,27 +313,49 @@ fn parse_optional_input<T: DeserializeOwned>(
 async fn main() -> std::result::Result<(), SkootError> {
     init_tracing();
     let cli = SkootrsCli::parse();
-    let o: octocrab::Octocrab = octocrab::Octocrab::builder()
-        .personal_token(
-            std::env::var("GITHUB_TOKEN").expect("GITHUB_TOKEN env var must be populated"),
-        )
-        .build()?;
-    octocrab::initialise(o);
+    let config = skootrs_model::skootrs::Config::from_env();
+    let github = GithubClient::for_host(&config.github_host);
 
-    let project_service = init_project_service();
-    // TODO: This should only default when it can't pull a valid config from the environment.
-    let config = skootrs_model::skootrs::Config::default();
+    let project_service = init_project_service(&github);
 
     match cli {
         SkootrsCli::Project { project } => match project {
-            ProjectCommands::Create { input } => {
+            ProjectCommands::Create {
+                input,
+                resume,
+                rollback,
+            } => {
                 let project_create_params = parse_optional_input(input)?;
+                let result = if resume {
+                    helpers::Project::resume(
+                        &config,
+                        &project_service,
+                        project_create_params,
+                        rollback,
+                    )
+                    .await
+                } else {
+                    helpers::Project::create(
+                        &config,
+                        &github,
+                        &project_service,
+                        project_create_params,
+                        rollback,
+                    )
+                    .await
+                };
+                if let Err(ref error) = result.handle_response_output(stdout()) {
+                    error!(error = error.as_ref(), "Failed to create project");
+                }
+            }
+            ProjectCommands::Adopt { input } => {
+                let project_adopt_params = parse_optional_input(input)?;
                 if let Err(ref error) =
-                    helpers::Project::create(&config, &project_service, project_create_params)
+                    helpers::Project::adopt(&config, &project_service, project_adopt_params)
                         .await
                         .handle_response_output(stdout())
                 {
-                    error!(error = error.as_ref(), "Failed to create project");
+                    error!(error = error.as_ref(), "Failed to adopt project");
                 }
             }
             ProjectCommands::Get { input } => {
@@ -297,6 +386,31 @@ async fn main() -> std::result::Result<(), SkootError> {
                     error!(error = error.as_ref(), "Failed to list projects");
                 }
             }
+            ProjectCommands::Import { url } => {
+                let project_get_params = ProjectGetParams { project_url: url };
+                if let Err(ref error) =
+                    helpers::Project::import(&config, &project_service, project_get_params)
+                        .await
+                        .handle_response_output(stdout())
+                {
+                    error!(error = error.as_ref(), "Failed to import project");
+                }
+            }
+            ProjectCommands::Discover { org, user } => {
+                let owner = match (org, user) {
+                    (Some(org), _) => GithubUser::Organization(org),
+                    (None, Some(user)) => GithubUser::User(user),
+                    (None, None) => unreachable!("clap requires either --org or --user"),
+                };
+                let project_discover_params = ProjectDiscoverParams { owner };
+                if let Err(ref error) =
+                    helpers::Project::discover(&config, &project_service, project_discover_params)
+                        .await
+                        .handle_response_output(stdout())
+                {
+                    error!(error = error.as_ref(), "Failed to discover projects");
+                }
+            }
             ProjectCommands::Archive { input } => {
                 let project_archive_params = parse_optional_input(input)?;
                 if let Err(ref error) =
@@ -326,6 +440,24 @@ async fn main() -> std::result::Result<(), SkootError> {
                     error!(error = error.as_ref(), "Failed to list facets for project");
                 }
             }
+            FacetCommands::Remove { input, force } => {
+                let facet_remove_params = parse_optional_input(input)?;
+                if let Err(ref error) =
+                    Facet::remove(&config, &project_service, facet_remove_params, force)
+                        .await
+                        .handle_response_output(stdout())
+                {
+                    error!(error = error.as_ref(), "Failed to remove facet");
+                }
+            }
+            FacetCommands::RefreshPins { actions } => {
+                if let Err(ref error) = Facet::refresh_pins(&github, &actions)
+                    .await
+                    .handle_response_output(stdout())
+                {
+                    error!(error = error.as_ref(), "Failed to refresh action pins");
+                }
+            }
         },
         SkootrsCli::Output { output } => match output {
             OutputCommands::Get { input } => {
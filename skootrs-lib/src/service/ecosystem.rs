@@ -1,6 +1,6 @@
 #![allow(clippy::module_name_repetitions)]
 
-use std::process::Command;
+use std::{fs, path::Path, process::Command};
 
 use tracing::info;
 
@@ -23,6 +23,13 @@ pub trait EcosystemService {
         params: EcosystemInitializeParams,
         source: InitializedSource,
     ) -> Result<InitializedEcosystem, SkootError>;
+
+    /// Detects the ecosystem of an existing project from its build files, e.g. `go.mod` for Go.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no ecosystem can be detected or the detected ecosystem isn't supported.
+    fn detect(&self, source: &InitializedSource) -> Result<InitializedEcosystem, SkootError>;
 }
 
 /// The `LocalEcosystemService` struct provides an implementation of the `EcosystemService` trait for initializing 
@@ -53,6 +60,23 @@ impl EcosystemService for LocalEcosystemService {
             }
         }
     }
+
+    fn detect(&self, source: &InitializedSource) -> Result<InitializedEcosystem, SkootError> {
+        let path = Path::new(&source.path);
+        if let Ok(go_mod) = fs::read_to_string(path.join("go.mod")) {
+            return Ok(InitializedEcosystem::Go(LocalGoEcosystemHandler::detect(&go_mod)?));
+        }
+        if let Ok(pom) = fs::read_to_string(path.join("pom.xml")) {
+            return Ok(InitializedEcosystem::Maven(LocalMavenEcosystemHandler::detect(&pom)?));
+        }
+        if path.join("Cargo.toml").exists() {
+            return Err("Detected a Cargo project, but the Cargo ecosystem isn't supported yet".into());
+        }
+        if path.join("package.json").exists() {
+            return Err("Detected an npm project, but the npm ecosystem isn't supported yet".into());
+        }
+        Err(format!("Failed to detect the ecosystem of the project in {}", source.path).into())
+    }
 }
 
 
@@ -82,6 +106,28 @@ impl LocalMavenEcosystemHandler {
             )))
         }
     }
+
+    /// Returns the group and artifact IDs of the project from the content of its `pom.xml`. The
+    /// `<parent>` section is skipped so its IDs aren't mistaken for the project's.
+    fn detect(pom: &str) -> Result<InitializedMaven, SkootError> {
+        let without_parent = match (pom.find("<parent>"), pom.find("</parent>")) {
+            (Some(start), Some(end)) if start < end => {
+                format!("{}{}", &pom[..start], &pom[end + "</parent>".len()..])
+            }
+            _ => pom.to_string(),
+        };
+        let element = |name: &str| {
+            let open = format!("<{name}>");
+            let close = format!("</{name}>");
+            let start = without_parent.find(&open)? + open.len();
+            let end = without_parent[start..].find(&close)? + start;
+            Some(without_parent[start..end].trim().to_string())
+        };
+        Ok(InitializedMaven {
+            group_id: element("groupId").ok_or("Failed to find the groupId in pom.xml")?,
+            artifact_id: element("artifactId").ok_or("Failed to find the artifactId in pom.xml")?,
+        })
+    }
 }
 
 /// The `LocalGoEcosystemHandler` struct represents a handler for initializing and managing a Go
@@ -115,6 +161,23 @@ impl LocalGoEcosystemHandler {
             )))
         }
     }
+
+    /// Returns the name and host of the module from the content of its `go.mod`, e.g. the module
+    /// `github.com/myorg/myproject` has the name `myproject` and the host `github.com/myorg`.
+    fn detect(go_mod: &str) -> Result<InitializedGo, SkootError> {
+        let module = go_mod
+            .lines()
+            .find_map(|line| line.trim().strip_prefix("module "))
+            .map(|module| module.trim().trim_matches('"'))
+            .ok_or("Failed to find the module directive in go.mod")?;
+        let (host, name) = module
+            .rsplit_once('/')
+            .ok_or_else(|| format!("Unsupported Go module path: {module}"))?;
+        Ok(InitializedGo {
+            name: name.to_string(),
+            host: host.to_string(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -179,4 +242,57 @@ mod tests {
 
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_detect_go() {
+        let temp_dir = TempDir::new("test").unwrap();
+        fs::write(
+            temp_dir.path().join("go.mod"),
+            "module github.com/myorg/my-project\n\ngo 1.21\n",
+        )
+        .unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+
+        let InitializedEcosystem::Go(go) = LocalEcosystemService {}.detect(&source).unwrap() else {
+            panic!("Wrong ecosystem type");
+        };
+
+        assert_eq!(go.name, "my-project");
+        assert_eq!(go.host, "github.com/myorg");
+    }
+
+    #[test]
+    fn test_detect_maven_skips_parent() {
+        let temp_dir = TempDir::new("test").unwrap();
+        fs::write(
+            temp_dir.path().join("pom.xml"),
+            "<project><parent><groupId>org.parent</groupId><artifactId>parent</artifactId></parent>\
+             <groupId>com.example</groupId><artifactId>my-project</artifactId></project>",
+        )
+        .unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+
+        let InitializedEcosystem::Maven(maven) = LocalEcosystemService {}.detect(&source).unwrap()
+        else {
+            panic!("Wrong ecosystem type");
+        };
+
+        assert_eq!(maven.group_id, "com.example");
+        assert_eq!(maven.artifact_id, "my-project");
+    }
+
+    #[test]
+    fn test_detect_unsupported() {
+        let temp_dir = TempDir::new("test").unwrap();
+        fs::write(temp_dir.path().join("Cargo.toml"), "[package]").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+
+        assert!(LocalEcosystemService {}.detect(&source).is_err());
+    }
 }
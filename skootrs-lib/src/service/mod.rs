@@ -15,7 +15,10 @@
 
 pub mod ecosystem;
 pub mod facet;
+pub mod github;
 pub mod output;
+pub mod pin;
 pub mod project;
+pub mod reconcile;
 pub mod repo;
 pub mod source;
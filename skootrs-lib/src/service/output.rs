@@ -15,11 +15,16 @@
 
 #![allow(clippy::module_name_repetitions)]
 
-use octocrab::models::repos::{Asset, Release};
+use octocrab::{
+    models::repos::{Asset, Release},
+    Octocrab,
+};
 use skootrs_model::skootrs::{
     label::Label, ProjectOutput, ProjectOutputGetParams, ProjectOutputReference, ProjectOutputType,
     ProjectOutputsListParams, SkootError,
 };
+
+use super::github::GithubClient;
 pub trait OutputService {
     fn list(
         &self,
@@ -32,7 +37,11 @@ pub trait OutputService {
     ) -> impl std::future::Future<Output = Result<ProjectOutput, SkootError>> + Send;
 }
 
-pub struct LocalOutputService;
+#[derive(Debug, Default)]
+pub struct LocalOutputService {
+    /// The client for calling the GitHub API.
+    pub github: GithubClient,
+}
 
 impl OutputService for LocalOutputService {
     fn list(
@@ -47,7 +56,11 @@ impl OutputService for LocalOutputService {
                     repo: g.name,
                     tag: params.release.tag(),
                 };
-                GithubReleaseHandler::outputs_list(github_params)
+                let github = self.github.clone();
+                async move {
+                    let client = github.octocrab_for(&github_params.owner).await?;
+                    GithubReleaseHandler::outputs_list(&client, github_params).await
+                }
             }
         }
     }
@@ -55,12 +68,16 @@ impl OutputService for LocalOutputService {
     async fn get(&self, params: ProjectOutputGetParams) -> Result<ProjectOutput, SkootError> {
         match params.initialized_project.repo {
             skootrs_model::skootrs::InitializedRepo::Github(g) => {
+                let client = self.github.octocrab_for(&g.organization.get_name()).await?;
                 let github_params = GithubOutputGetParams {
-                    release: GithubReleaseHandler::get_release(GithubReleaseParams {
-                        owner: g.organization.get_name(),
-                        repo: g.name.clone(),
-                        tag: params.release.tag(),
-                    })
+                    release: GithubReleaseHandler::get_release(
+                        &client,
+                        GithubReleaseParams {
+                            owner: g.organization.get_name(),
+                            repo: g.name.clone(),
+                            tag: params.release.tag(),
+                        },
+                    )
                     .await?,
                     name: params.project_output,
                 };
@@ -73,9 +90,10 @@ impl OutputService for LocalOutputService {
 struct GithubReleaseHandler;
 impl GithubReleaseHandler {
     async fn outputs_list(
+        client: &Octocrab,
         params: GithubReleaseParams,
     ) -> Result<Vec<ProjectOutputReference>, SkootError> {
-        let release = Self::get_release(params).await?;
+        let release = Self::get_release(client, params).await?;
 
         let assets = release.assets;
         let references = assets
@@ -90,17 +108,20 @@ impl GithubReleaseHandler {
         Ok(references)
     }
 
-    async fn get_release(params: GithubReleaseParams) -> Result<Release, octocrab::Error> {
+    async fn get_release(
+        client: &Octocrab,
+        params: GithubReleaseParams,
+    ) -> Result<Release, octocrab::Error> {
         match params.tag {
             Some(tag) => {
-                octocrab::instance()
+                client
                     .repos(params.owner, params.repo)
                     .releases()
                     .get_by_tag(tag.as_str())
                     .await
             }
             None => {
-                octocrab::instance()
+                client
                     .repos(params.owner, params.repo)
                     .releases()
                     .get_latest()
@@ -0,0 +1,468 @@
+//
+// Copyright 2024 The Skootrs Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `pin` module pins the GitHub Actions used in workflow files to full commit SHAs.
+//!
+//! Pins are resolved from a table that ships with Skootrs so pinning works offline. The table can be
+//! refreshed against GitHub, in which case the refreshed pins are cached under the user's data directory
+//! and take precedence over the built in ones.
+
+#![allow(clippy::module_name_repetitions)]
+
+use std::{collections::BTreeMap, fmt, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use skootrs_model::skootrs::SkootError;
+
+/// The built in pins as `(action repo, version, commit SHA)`. Major version aliases like `v4` resolve to
+/// the latest release Skootrs knows about so the version comment records the exact version.
+const BUILTIN_PINS: &[(&str, &str, &str)] = &[
+    (
+        "actions/checkout",
+        "v4.1.2",
+        "9bb56186c3b09b4f86b1c65136769dd318469633",
+    ),
+    (
+        "actions/checkout",
+        "v4",
+        "9bb56186c3b09b4f86b1c65136769dd318469633",
+    ),
+    (
+        "actions/setup-go",
+        "v5.0.0",
+        "0c52d547c9bc32b1aa3301fd7a9cb496313a4491",
+    ),
+    (
+        "actions/setup-go",
+        "v5",
+        "0c52d547c9bc32b1aa3301fd7a9cb496313a4491",
+    ),
+    (
+        "actions/upload-artifact",
+        "v4.3.1",
+        "5d5d22a31266ced268874388b861e4b58bb5c2f3",
+    ),
+    (
+        "actions/upload-artifact",
+        "v4.3.0",
+        "26f96dfa697d77e81fd5907df203aa23a56210a8",
+    ),
+    (
+        "actions/upload-artifact",
+        "v4",
+        "5d5d22a31266ced268874388b861e4b58bb5c2f3",
+    ),
+    (
+        "aquasecurity/trivy-action",
+        "0.19.0",
+        "d710430a6722f083d3b36b8339ff66b32f22ee55",
+    ),
+    (
+        "docker/login-action",
+        "v3.1.0",
+        "e92390c5fb421da1463c202d546fed0ec5c39f20",
+    ),
+    (
+        "docker/login-action",
+        "v3",
+        "e92390c5fb421da1463c202d546fed0ec5c39f20",
+    ),
+    (
+        "github/codeql-action",
+        "v3.25.0",
+        "df5a14dc28094dc936e103b37d749c6628682b60",
+    ),
+    (
+        "github/codeql-action",
+        "v3.23.2",
+        "b7bf0a3ed3ecfa44160715d7c442788f65f0f923",
+    ),
+    (
+        "github/codeql-action",
+        "v3",
+        "df5a14dc28094dc936e103b37d749c6628682b60",
+    ),
+    (
+        "google/oss-fuzz",
+        "master",
+        "5acb10b65c2265d3c20d7152478c44a214088221",
+    ),
+    (
+        "goreleaser/goreleaser-action",
+        "v5.0.0",
+        "7ec5c2b0c6cdda6e8bbb49444bc797dd33d74dd8",
+    ),
+    (
+        "goreleaser/goreleaser-action",
+        "v5",
+        "7ec5c2b0c6cdda6e8bbb49444bc797dd33d74dd8",
+    ),
+    (
+        "ossf/scorecard-action",
+        "v2.3.1",
+        "0864cf19026789058feabb7e87baa5f140aac736",
+    ),
+    (
+        "ossf/scorecard-action",
+        "v2",
+        "0864cf19026789058feabb7e87baa5f140aac736",
+    ),
+    (
+        "sigstore/cosign-installer",
+        "v3.5.0",
+        "59acb6260d9c0ba8f4a2f9d9b48431a222b68e20",
+    ),
+    (
+        "sigstore/cosign-installer",
+        "v3",
+        "59acb6260d9c0ba8f4a2f9d9b48431a222b68e20",
+    ),
+];
+
+/// Reusable workflows that have to be referenced by tag. The SLSA generator verifies its own version from
+/// the tag, and Scorecard's Pinned-Dependencies check makes an exception for it.
+const TAG_ONLY_ACTIONS: &[&str] = &["slsa-framework/slsa-github-generator"];
+
+/// The version alias used in the version comment for major version aliases, e.g. `v4` -> `v4.1.2`.
+fn exact_version(action: &str, version: &str, sha: &str) -> String {
+    BUILTIN_PINS
+        .iter()
+        .filter(|(a, v, s)| *a == action && *s == sha && is_within(v, version))
+        .map(|(_, v, _)| (*v).to_string())
+        .next()
+        .unwrap_or_else(|| version.to_string())
+}
+
+/// Whether `candidate` is a more exact version within the `version` alias, e.g. `v4.1.2` within `v4`.
+/// Versions are compared by whole segments so `v10.0.0` isn't within `v1`.
+fn is_within(candidate: &str, version: &str) -> bool {
+    candidate
+        .strip_prefix(version)
+        .is_some_and(|rest| rest.starts_with('.'))
+}
+
+/// A reference to a GitHub Action, e.g. `actions/checkout@v4`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ActionReference {
+    /// The action, e.g. `github/codeql-action/init`.
+    pub action: String,
+    /// The version the action is referenced by, e.g. a tag or branch.
+    pub version: String,
+}
+
+impl ActionReference {
+    /// The repo the action lives in, e.g. `github/codeql-action` for `github/codeql-action/init`.
+    #[must_use]
+    pub fn repo(&self) -> String {
+        self.action
+            .splitn(3, '/')
+            .take(2)
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+impl fmt::Display for ActionReference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}@{}", self.action, self.version)
+    }
+}
+
+impl std::str::FromStr for ActionReference {
+    type Err = SkootError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (action, version) = s
+            .split_once('@')
+            .ok_or_else(|| format!("{s} is not an action reference like owner/repo@version"))?;
+        if action.split('/').count() < 2 || version.is_empty() {
+            return Err(format!("{s} is not an action reference like owner/repo@version").into());
+        }
+        Ok(Self {
+            action: action.to_string(),
+            version: version.to_string(),
+        })
+    }
+}
+
+/// A GitHub Action version resolved to a commit SHA.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ActionPin {
+    /// The repo of the action, e.g. `actions/checkout`.
+    pub repo: String,
+    /// The version that was resolved, e.g. `v4.1.2`.
+    pub version: String,
+    /// The full commit SHA the version resolved to.
+    pub sha: String,
+}
+
+/// The result of pinning a workflow file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedWorkflow {
+    /// The content of the workflow with every resolvable action pinned.
+    pub content: String,
+    /// The actions that couldn't be resolved to a SHA and are left as is.
+    pub unresolved: Vec<ActionReference>,
+}
+
+/// The table used to resolve action versions to commit SHAs.
+#[derive(Debug, Clone, Default)]
+pub struct ActionPins {
+    pins: BTreeMap<(String, String), String>,
+}
+
+impl ActionPins {
+    /// Returns the pins that ship with Skootrs.
+    #[must_use]
+    pub fn offline() -> Self {
+        let pins = BUILTIN_PINS
+            .iter()
+            .map(|(repo, version, sha)| {
+                (
+                    ((*repo).to_string(), (*version).to_string()),
+                    (*sha).to_string(),
+                )
+            })
+            .collect();
+        Self { pins }
+    }
+
+    /// Returns the pins that ship with Skootrs along with any pins cached by a previous refresh. A cache
+    /// that can't be read is ignored so pinning keeps working offline.
+    #[must_use]
+    pub fn load() -> Self {
+        let mut pins = Self::offline();
+        let Ok(cache_path) = Self::cache_path() else {
+            return pins;
+        };
+        if !cache_path.exists() {
+            return pins;
+        }
+        match std::fs::read_to_string(&cache_path)
+            .map_err(SkootError::from)
+            .and_then(|content| Ok(serde_json::from_str::<Vec<ActionPin>>(&content)?))
+        {
+            Ok(cached) => pins.extend(cached),
+            Err(error) => warn!(
+                "Ignoring the action pin cache {}: {error}",
+                cache_path.display()
+            ),
+        }
+        pins
+    }
+
+    /// Returns the location of the cache of refreshed pins, e.g. `~/.local/share/skootrs/action-pins.json`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user's data directory can't be determined.
+    pub fn cache_path() -> Result<PathBuf, SkootError> {
+        let data_dir = dirs::data_dir().ok_or("Failed to find the user's data directory")?;
+        Ok(data_dir.join("skootrs").join("action-pins.json"))
+    }
+
+    /// Adds pins to the table, replacing the SHA of any version that's already known.
+    pub fn extend(&mut self, pins: impl IntoIterator<Item = ActionPin>) {
+        for pin in pins {
+            self.pins.insert((pin.repo, pin.version), pin.sha);
+        }
+    }
+
+    /// Returns the SHA a reference resolves to, if it's known.
+    #[must_use]
+    pub fn resolve(&self, reference: &ActionReference) -> Option<&str> {
+        self.pins
+            .get(&(reference.repo(), reference.version.clone()))
+            .map(String::as_str)
+    }
+
+    /// Returns every pin in the table.
+    #[must_use]
+    pub fn pins(&self) -> Vec<ActionPin> {
+        self.pins
+            .iter()
+            .map(|((repo, version), sha)| ActionPin {
+                repo: repo.clone(),
+                version: version.clone(),
+                sha: sha.clone(),
+            })
+            .collect()
+    }
+
+    /// Resolves every known version plus the `additional` references against GitHub and caches the result
+    /// so later runs pick up the refreshed pins. Returns the refreshed pins.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a version can't be resolved or the cache can't be written.
+    pub async fn refresh(
+        &mut self,
+        client: &octocrab::Octocrab,
+        additional: &[ActionReference],
+    ) -> Result<Vec<ActionPin>, SkootError> {
+        let mut targets: Vec<(String, String)> = self.pins.keys().cloned().collect();
+        targets.extend(
+            additional
+                .iter()
+                .map(|reference| (reference.repo(), reference.version.clone())),
+        );
+        targets.sort();
+        targets.dedup();
+
+        let mut refreshed = Vec::new();
+        for (repo, version) in targets {
+            let commit: serde_json::Value = client
+                .get(format!("/repos/{repo}/commits/{version}"), None::<&()>)
+                .await?;
+            let sha = commit["sha"]
+                .as_str()
+                .ok_or_else(|| format!("Failed to resolve {repo}@{version} to a commit"))?
+                .to_string();
+            info!("Resolved {repo}@{version} to {sha}");
+            refreshed.push(ActionPin { repo, version, sha });
+        }
+
+        self.extend(refreshed.clone());
+        let cache_path = Self::cache_path()?;
+        if let Some(parent) = cache_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&cache_path, serde_json::to_string_pretty(&self.pins())?)?;
+        Ok(refreshed)
+    }
+
+    /// Pins every `uses:` reference in a workflow file to a full commit SHA, with the version as a comment
+    /// so tools like dependabot can keep it updated. References that are already pinned, local actions,
+    /// docker images, and reusable workflows that require a tag are left as is.
+    #[must_use]
+    pub fn pin_workflow(&self, content: &str) -> PinnedWorkflow {
+        let mut unresolved = Vec::new();
+        let mut lines = Vec::new();
+        for line in content.split('\n') {
+            let pinned = parse_uses(line).and_then(|(prefix, reference)| {
+                if is_pinned(&reference) || TAG_ONLY_ACTIONS.contains(&reference.repo().as_str()) {
+                    return None;
+                }
+                if let Some(sha) = self.resolve(&reference) {
+                    let version = exact_version(&reference.repo(), &reference.version, sha);
+                    Some(format!("{prefix}{}@{sha} # {version}", reference.action))
+                } else {
+                    warn!("No pin is known for {reference}, leaving it as is");
+                    unresolved.push(reference);
+                    None
+                }
+            });
+            lines.push(pinned.unwrap_or_else(|| line.to_string()));
+        }
+        PinnedWorkflow {
+            content: lines.join("\n"),
+            unresolved,
+        }
+    }
+}
+
+/// Splits a `uses:` line into everything up to the reference and the reference itself.
+fn parse_uses(line: &str) -> Option<(String, ActionReference)> {
+    let start = line.find("uses:")?;
+    let before = line[..start].trim_start();
+    if !(before.is_empty() || before == "-" || before == "- ") {
+        return None;
+    }
+    let value_start = start + "uses:".len();
+    let value = line[value_start..].trim_start();
+    let value_start = line.len() - value.len();
+    let value = value.split('#').next().unwrap_or_default().trim();
+    let value = value.trim_matches(|c| c == '"' || c == '\'');
+    if value.starts_with("./") || value.starts_with("docker://") {
+        return None;
+    }
+    let reference = value.parse::<ActionReference>().ok()?;
+    Some((line[..value_start].to_string(), reference))
+}
+
+fn is_pinned(reference: &ActionReference) -> bool {
+    reference.version.len() == 40 && reference.version.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pin_workflow() {
+        let pins = ActionPins::offline();
+        let workflow = "steps:
+      - name: Checkout
+        uses: actions/checkout@v4
+      - uses: \"github/codeql-action/init@v3.25.0\" # init
+      - uses: ./local-action
+      - uses: docker://alpine:3.19
+      - uses: actions/setup-go@0c52d547c9bc32b1aa3301fd7a9cb496313a4491 # v5.0.0
+      - uses: someone/unknown@v1
+  provenance:
+    uses: slsa-framework/slsa-github-generator/.github/workflows/generator_generic_slsa3.yml@v1.10.0";
+
+        let pinned = pins.pin_workflow(workflow);
+
+        assert_eq!(
+            pinned.content,
+            "steps:
+      - name: Checkout
+        uses: actions/checkout@9bb56186c3b09b4f86b1c65136769dd318469633 # v4.1.2
+      - uses: github/codeql-action/init@df5a14dc28094dc936e103b37d749c6628682b60 # v3.25.0
+      - uses: ./local-action
+      - uses: docker://alpine:3.19
+      - uses: actions/setup-go@0c52d547c9bc32b1aa3301fd7a9cb496313a4491 # v5.0.0
+      - uses: someone/unknown@v1
+  provenance:
+    uses: slsa-framework/slsa-github-generator/.github/workflows/generator_generic_slsa3.yml@v1.10.0"
+        );
+        assert_eq!(
+            pinned.unresolved,
+            vec![ActionReference {
+                action: "someone/unknown".to_string(),
+                version: "v1".to_string(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_is_within() {
+        assert!(is_within("v4.1.2", "v4"));
+        assert!(is_within("v4.1.2", "v4.1"));
+        assert!(!is_within("v4", "v4"));
+        assert!(!is_within("v10.0.0", "v1"));
+        assert!(!is_within("v1.10.0", "v1.1"));
+    }
+
+    #[test]
+    fn test_extend_overrides_builtin_pins() {
+        let mut pins = ActionPins::offline();
+        let reference: ActionReference = "actions/checkout@v4".parse().unwrap();
+        pins.extend(vec![ActionPin {
+            repo: "actions/checkout".to_string(),
+            version: "v4".to_string(),
+            sha: "b4ffde65f46336ab88eb53be808477a3936bae11".to_string(),
+        }]);
+
+        assert_eq!(
+            pins.resolve(&reference),
+            Some("b4ffde65f46336ab88eb53be808477a3936bae11")
+        );
+        assert!("actions@v4".parse::<ActionReference>().is_err());
+    }
+}
@@ -15,21 +15,34 @@
 
 #![allow(clippy::module_name_repetitions)]
 
-use std::collections::HashMap;
+use std::{collections::HashMap, path::Path};
 
-use crate::service::facet::{FacetSetParamsGenerator, RootFacetService};
+use crate::service::{
+    facet::{FacetSetParamsGenerator, RootFacetService},
+    reconcile::{exclude_facets, FacetReconciler},
+};
 
 use skootrs_model::skootrs::{
-    facet::{CommonFacetCreateParams, InitializedFacet, SourceFile},
-    FacetGetParams, FacetMapKey, InitializedProject, InitializedSource, ProjectArchiveParams,
-    ProjectCreateParams, ProjectGetParams, ProjectOutput, ProjectOutputGetParams,
-    ProjectOutputReference, ProjectOutputsListParams, ProjectUpdateParams, SkootError,
+    facet::{
+        CommonFacetCreateParams, FacetCreateParams, FacetRemoveParams, FacetSetCreateParams,
+        InitializedFacet, SourceFile, SupportedFacetType, WritePolicy,
+    },
+    state::{ProjectState, STATE_FILE_NAME},
+    FacetConflict, FacetGetParams, FacetMapKey, InitializedProject, InitializedRepo,
+    InitializedRepoGetParams, ProjectAdoptParams, ProjectAdoptResult, ProjectArchiveParams,
+    ProjectCreateParams, ProjectCreateProgress, ProjectDiscoverParams, ProjectFacetRemoveParams,
+    ProjectGetParams, ProjectOutput, ProjectOutputGetParams, ProjectOutputReference,
+    ProjectOutputsListParams, ProjectPullRequestParams, ProjectUpdateParams, RollbackStrategy,
+    SkootError,
 };
 
+/// The branch that the facets applied when adopting an existing repo are committed to.
+pub const ADOPT_BRANCH: &str = "skootrs/adopt";
+
 use super::{
     ecosystem::EcosystemService, output::OutputService, repo::RepoService, source::SourceService,
 };
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 /// The `ProjectService` trait provides an interface for initializing and managing a Skootrs project.
 pub trait ProjectService {
@@ -43,6 +56,33 @@ pub trait ProjectService {
         params: ProjectCreateParams,
     ) -> impl std::future::Future<Output = Result<InitializedProject, SkootError>> + Send;
 
+    /// Runs the steps of creating a project that aren't done yet according to `progress`, so a creation that
+    /// failed part way through can pick up from the step that failed. `record` is called with the progress
+    /// after each step.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a step fails, or the progress can't be recorded.
+    fn resume<F>(
+        &self,
+        progress: ProjectCreateProgress,
+        record: F,
+    ) -> impl std::future::Future<Output = Result<InitializedProject, SkootError>> + Send
+    where
+        F: FnMut(&ProjectCreateProgress) -> Result<(), SkootError> + Send;
+
+    /// Undoes what a failed project creation did: the local clone is removed and the repo is deleted or
+    /// archived.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the clone can't be removed, or the repo can't be deleted or archived.
+    fn rollback(
+        &self,
+        progress: &ProjectCreateProgress,
+        strategy: RollbackStrategy,
+    ) -> impl std::future::Future<Output = Result<(), SkootError>> + Send;
+
     /// Gets an initialized project.
     ///
     /// # Errors
@@ -53,6 +93,45 @@ pub trait ProjectService {
         params: ProjectGetParams,
     ) -> impl std::future::Future<Output = Result<InitializedProject, SkootError>> + Send;
 
+    /// Adopts an existing repo as a Skootrs project. The repo is cloned and its ecosystem is detected from
+    /// its build files. The default source facets whose files don't exist yet in the repo are committed to
+    /// the `skootrs/adopt` branch so they can be proposed with a pull request. Facets whose files already
+    /// exist are reported as conflicts and left alone unless `overwrite_existing` is set.
+    ///
+    /// API bundle facets like branch protection aren't applied since they can't be proposed in a pull
+    /// request. They're applied by the next update once the adoption is merged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repo can't be cloned, its ecosystem can't be detected, or the facets can't
+    /// be applied.
+    fn adopt(
+        &self,
+        params: ProjectAdoptParams,
+    ) -> impl std::future::Future<Output = Result<ProjectAdoptResult, SkootError>> + Send;
+
+    /// Opens a pull request against the repo of a project and returns its URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pull request can't be opened.
+    fn open_pull_request(
+        &self,
+        params: ProjectPullRequestParams,
+    ) -> impl std::future::Future<Output = Result<String, SkootError>> + Send;
+
+    /// Discovers the existing Skootrs projects that belong to a user or organization by scanning each of
+    /// their repos for a valid state file. Repos without a state file, or with one that can't be read,
+    /// are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repos of the user or organization can't be listed.
+    fn discover(
+        &self,
+        params: ProjectDiscoverParams,
+    ) -> impl std::future::Future<Output = Result<Vec<InitializedProject>, SkootError>> + Send;
+
     /// Gets a facet along with its content from an initialized project.
     ///
     /// # Errors
@@ -63,6 +142,20 @@ pub trait ProjectService {
         params: FacetGetParams,
     ) -> impl std::future::Future<Output = Result<InitializedFacet, SkootError>> + Send;
 
+    /// Removes a facet from an initialized project. The facet's files are removed from the source, and the
+    /// settings it applied are reverted. The facet is excluded from the returned project so it isn't applied
+    /// again when the project is updated. The removed files aren't committed, so they can be committed along
+    /// with the project's new state.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the facet can't be found or removed, e.g. because its files were modified since
+    /// they were recorded and the removal isn't forced.
+    fn remove_facet(
+        &self,
+        params: ProjectFacetRemoveParams,
+    ) -> impl std::future::Future<Output = Result<InitializedProject, SkootError>> + Send;
+
     /// Lists the facets of an initialized project.
     ///
     /// # Errors
@@ -88,6 +181,13 @@ pub trait ProjectService {
         _params: ProjectOutputGetParams,
     ) -> impl std::future::Future<Output = Result<ProjectOutput, SkootError>> + Send;
 
+    /// Updates an initialized project to include any updated facets. Facet files that already exist are
+    /// written according to the write policy of the params, and the files that conflict are reported in
+    /// the `conflicts` of their facet in the returned project.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the project can't be updated.
     fn update(
         &self,
         params: ProjectUpdateParams,
@@ -133,19 +233,58 @@ where
         &self,
         params: ProjectCreateParams,
     ) -> Result<InitializedProject, SkootError> {
-        debug!("Starting repo initialization");
-        let initialized_repo = self
-            .repo_service
-            .initialize(params.repo_params.clone())
-            .await?;
-        debug!("Starting source initialization");
-        let initialized_source: InitializedSource = self
-            .source_service
-            .initialize(params.source_params.clone(), initialized_repo.clone())?;
-        debug!("Starting ecosystem initialization");
-        let initialized_ecosystem = self
-            .ecosystem_service
-            .initialize(params.ecosystem_params.clone(), initialized_source.clone())?;
+        self.resume(ProjectCreateProgress::new(params), |_| Ok(()))
+            .await
+    }
+
+    async fn resume<F>(
+        &self,
+        mut progress: ProjectCreateProgress,
+        mut record: F,
+    ) -> Result<InitializedProject, SkootError>
+    where
+        F: FnMut(&ProjectCreateProgress) -> Result<(), SkootError> + Send,
+    {
+        let params = progress.params.clone();
+        let initialized_repo = if let Some(repo) = &progress.repo {
+            repo.clone()
+        } else {
+            debug!("Starting repo initialization");
+            let repo = self
+                .repo_service
+                .initialize(params.repo_params.clone())
+                .await?;
+            progress.repo = Some(repo.clone());
+            record(&progress)?;
+            repo
+        };
+        let initialized_source = if let Some(source) = &progress.source {
+            source.clone()
+        } else {
+            debug!("Starting source initialization");
+            let InitializedRepo::Github(github_repo) = &initialized_repo;
+            let existed = Path::new(&params.source_params.parent_path)
+                .join(&github_repo.name)
+                .exists();
+            let source = self
+                .source_service
+                .initialize(params.source_params.clone(), initialized_repo.clone())?;
+            progress.source = Some(source.clone());
+            progress.source_created = !existed;
+            record(&progress)?;
+            source
+        };
+        let initialized_ecosystem = if let Some(ecosystem) = &progress.ecosystem {
+            ecosystem.clone()
+        } else {
+            debug!("Starting ecosystem initialization");
+            let ecosystem = self
+                .ecosystem_service
+                .initialize(params.ecosystem_params.clone(), initialized_source.clone())?;
+            progress.ecosystem = Some(ecosystem.clone());
+            record(&progress)?;
+            ecosystem
+        };
         debug!("Starting facet initialization");
         // TODO: This is ugly and this should probably be configured somewhere better, preferably outside of code.
         let facet_set_params_generator = FacetSetParamsGenerator {};
@@ -155,23 +294,49 @@ where
             repo: initialized_repo.clone(),
             ecosystem: initialized_ecosystem.clone(),
         };
-        let source_facet_set_params = facet_set_params_generator
-            .generate_default_source_bundle_facet_params(&common_params)?;
-        let api_facet_set_params =
-            facet_set_params_generator.generate_default_api_bundle(&common_params)?;
-        let initialized_source_facets = self
-            .facet_service
-            .initialize_all(source_facet_set_params)
-            .await?;
+        // Nothing is applied yet, so every facet is created.
+        let reconciler = FacetReconciler {
+            facet_service: &self.facet_service,
+            common: &common_params,
+            write_policy: WritePolicy::default(),
+        };
+        let initialized_source_facets = if let Some(facets) = &progress.source_facets {
+            facets.clone()
+        } else {
+            let source_facet_set_params = exclude_facets(
+                facet_set_params_generator
+                    .generate_source_bundle_facet_params(&common_params, &params.facet_configs)?,
+                &params.excluded_facets,
+            );
+            let facets = reconciler
+                .reconcile(source_facet_set_params, vec![])
+                .await?;
+            progress.source_facets = Some(facets.clone());
+            record(&progress)?;
+            facets
+        };
         // TODO: Figure out how to better order commits and pushes
-        self.source_service.commit_and_push_changes(
-            initialized_source.clone(),
-            "Initialized project".to_string(),
-        )?;
-        let initialized_api_facets = self
-            .facet_service
-            .initialize_all(api_facet_set_params)
-            .await?;
+        if !progress.pushed {
+            self.source_service.commit_and_push_changes(
+                initialized_source.clone(),
+                "Initialized project".to_string(),
+            )?;
+            progress.pushed = true;
+            record(&progress)?;
+        }
+        let initialized_api_facets = if let Some(facets) = &progress.api_facets {
+            facets.clone()
+        } else {
+            let api_facet_set_params = exclude_facets(
+                facet_set_params_generator
+                    .generate_api_bundle_facet_params(&common_params, &params.facet_configs)?,
+                &params.excluded_facets,
+            );
+            let facets = reconciler.reconcile(api_facet_set_params, vec![]).await?;
+            progress.api_facets = Some(facets.clone());
+            record(&progress)?;
+            facets
+        };
         // FIXME: Also add facet by name as well
         let initialized_facets = [initialized_source_facets, initialized_api_facets]
             .concat()
@@ -186,24 +351,203 @@ where
             ecosystem: initialized_ecosystem,
             source: initialized_source,
             facets: initialized_facets,
-            name: params.name.clone(),
+            name: params.name,
+            excluded_facets: params.excluded_facets,
         })
     }
 
+    async fn rollback(
+        &self,
+        progress: &ProjectCreateProgress,
+        strategy: RollbackStrategy,
+    ) -> Result<(), SkootError> {
+        // A directory that was already there before the creation isn't the creation's to remove.
+        if let (Some(source), true) = (&progress.source, progress.source_created) {
+            if Path::new(&source.path).exists() {
+                info!("Removing the local clone at {}", source.path);
+                std::fs::remove_dir_all(&source.path)?;
+            }
+        }
+        if let Some(repo) = &progress.repo {
+            let url = match strategy {
+                RollbackStrategy::Delete => self.repo_service.delete(repo.clone()).await?,
+                RollbackStrategy::Archive => self.repo_service.archive(repo.clone()).await?,
+            };
+            info!("Rolled back the creation of {url} ({strategy})");
+        }
+        Ok(())
+    }
+
     async fn get(&self, params: ProjectGetParams) -> Result<InitializedProject, SkootError> {
         let get_repo_params = skootrs_model::skootrs::InitializedRepoGetParams {
             repo_url: params.project_url.clone(),
         };
         debug!("Getting repo: {get_repo_params:?}");
         let repo = self.repo_service.get(get_repo_params).await?;
-        // TODO: Skootrs file path should be kept as a global constant somewhere.
         let skootrs_file = self
             .repo_service
-            .fetch_file_content(&repo, ".skootrs")
+            .fetch_file_content(&repo, STATE_FILE_NAME)
             .await?;
         debug!("Skootrs file: {skootrs_file}");
-        let initialized_project: InitializedProject = serde_json::from_str(&skootrs_file)?;
-        Ok(initialized_project)
+        let mut project = ProjectState::from_json(&skootrs_file)?.project;
+        // The default branch can be changed after the project is created, so the detected one is used.
+        let (InitializedRepo::Github(state_repo), InitializedRepo::Github(repo)) =
+            (&mut project.repo, repo);
+        state_repo.default_branch = repo.default_branch;
+        Ok(project)
+    }
+
+    async fn adopt(&self, params: ProjectAdoptParams) -> Result<ProjectAdoptResult, SkootError> {
+        let initialized_repo = self
+            .repo_service
+            .get(InitializedRepoGetParams {
+                repo_url: params.repo_url.clone(),
+            })
+            .await?;
+        let InitializedRepo::Github(github_repo) = &initialized_repo;
+        let project_name = github_repo.name.clone();
+        // Cloning is a no-op if the repo was already cloned, in which case the latest changes are pulled.
+        let initialized_source = self
+            .source_service
+            .initialize(params.source_params.clone(), initialized_repo.clone())?;
+        self.source_service
+            .pull_updates(initialized_source.clone())?;
+        // Adopting a project again would replace the state recorded for it, e.g. its excluded facets.
+        if !params.overwrite_existing
+            && self
+                .source_service
+                .file_exists(&initialized_source, "./", STATE_FILE_NAME)?
+        {
+            return Err(format!(
+                "{} is already managed by Skootrs, adopt it with overwrite_existing to replace its state",
+                initialized_repo.full_url()
+            )
+            .into());
+        }
+        let initialized_ecosystem = self.ecosystem_service.detect(&initialized_source)?;
+        info!(
+            "Detected {initialized_ecosystem} ecosystem for {}",
+            initialized_repo.full_url()
+        );
+
+        let common_params = CommonFacetCreateParams {
+            project_name: project_name.clone(),
+            source: initialized_source.clone(),
+            repo: initialized_repo.clone(),
+            ecosystem: initialized_ecosystem.clone(),
+        };
+        let source_facet_set_params = FacetSetParamsGenerator {}
+            .generate_default_source_bundle_facet_params(&common_params)?;
+
+        let mut facets_to_apply = Vec::new();
+        let mut applied_facets = Vec::new();
+        let mut conflicting_facets = Vec::new();
+        for facet_params in source_facet_set_params.facets_params {
+            let FacetCreateParams::SourceBundle(ref source_bundle_params) = facet_params else {
+                continue;
+            };
+            // The repo already has its own code, so the placeholder source code is never adopted.
+            if source_bundle_params.facet_type == SupportedFacetType::DefaultSourceCode {
+                continue;
+            }
+            let facet = FacetMapKey::Type(source_bundle_params.facet_type.clone());
+            let mut existing_files = Vec::new();
+            for file in self
+                .facet_service
+                .generate_source_files_content(&facet_params)?
+            {
+                if self
+                    .source_service
+                    .file_exists(&initialized_source, &file.path, &file.name)?
+                {
+                    let path = std::path::Path::new(&file.path).join(&file.name);
+                    let relative_path = path.strip_prefix("./").unwrap_or(&path);
+                    existing_files.push(relative_path.display().to_string());
+                }
+            }
+
+            if existing_files.is_empty() || params.overwrite_existing {
+                if !existing_files.is_empty() {
+                    warn!("Overwriting {existing_files:?} with the {facet} facet");
+                }
+                applied_facets.push(facet);
+                facets_to_apply.push(facet_params);
+            } else {
+                warn!("Skipping the {facet} facet since {existing_files:?} already exist");
+                conflicting_facets.push(FacetConflict {
+                    facet,
+                    existing_files,
+                });
+            }
+        }
+
+        self.source_service
+            .create_branch(&initialized_source, ADOPT_BRANCH)?;
+        let initialized_facets = self
+            .facet_service
+            .initialize_all(FacetSetCreateParams {
+                facets_params: facets_to_apply,
+            })
+            .await?
+            .into_iter()
+            .map(|f| (FacetMapKey::Type(f.facet_type()), f))
+            .collect::<HashMap<FacetMapKey, InitializedFacet>>();
+        self.source_service.commit_and_push_changes(
+            initialized_source.clone(),
+            "Adopted project with Skootrs".to_string(),
+        )?;
+
+        info!("Completed adoption of {}", initialized_repo.full_url());
+
+        Ok(ProjectAdoptResult {
+            project: InitializedProject {
+                repo: initialized_repo,
+                ecosystem: initialized_ecosystem,
+                source: initialized_source,
+                facets: initialized_facets,
+                name: project_name,
+                excluded_facets: vec![],
+            },
+            branch: ADOPT_BRANCH.to_string(),
+            applied_facets,
+            conflicting_facets,
+            pull_request_url: None,
+        })
+    }
+
+    async fn open_pull_request(
+        &self,
+        params: ProjectPullRequestParams,
+    ) -> Result<String, SkootError> {
+        self.repo_service
+            .create_pull_request(&params.initialized_project.repo, params.pull_request)
+            .await
+    }
+
+    async fn discover(
+        &self,
+        params: ProjectDiscoverParams,
+    ) -> Result<Vec<InitializedProject>, SkootError> {
+        let repos = self.repo_service.list(params.owner).await?;
+        let mut projects = Vec::new();
+        for repo in repos {
+            let Some(skootrs_file) = self
+                .repo_service
+                .fetch_optional_file_content(&repo, STATE_FILE_NAME)
+                .await?
+            else {
+                debug!("No Skootrs state file found in {}", repo.full_url());
+                continue;
+            };
+            match ProjectState::from_json(&skootrs_file) {
+                Ok(project_state) => {
+                    info!("Discovered Skootrs project: {}", repo.full_url());
+                    projects.push(project_state.project);
+                }
+                Err(e) => warn!("Skipping {}: {e}", repo.full_url()),
+            }
+        }
+        Ok(projects)
     }
 
     async fn get_facet_with_content(
@@ -252,6 +596,8 @@ where
                             source_files: None,
                             source_files_content: Some(source_files_content_map),
                             labels: s.labels.clone(),
+                            conflicts: s.conflicts.clone(),
+                            config: s.config.clone(),
                         },
                     ))
                 } else {
@@ -262,15 +608,14 @@ where
         }
     }
 
-    // TODO: A lot of this code is copied from the initialize function. This should be refactored to avoid code duplication.
     async fn update(&self, params: ProjectUpdateParams) -> Result<InitializedProject, SkootError> {
-        let initialized_project = params.initialized_project.clone();
-        let initialized_repo = initialized_project.repo;
+        let initialized_project = params.initialized_project;
+        let initialized_repo = initialized_project.repo.clone();
         let initialized_source = self.repo_service.clone_local_or_pull(
             initialized_repo.clone(),
             initialized_project.source.path.clone(),
         )?;
-        let initialized_ecosystem = initialized_project.ecosystem;
+        let initialized_ecosystem = initialized_project.ecosystem.clone();
 
         let facet_set_params_generator = FacetSetParamsGenerator {};
         let common_params = CommonFacetCreateParams {
@@ -279,22 +624,54 @@ where
             repo: initialized_repo.clone(),
             ecosystem: initialized_ecosystem.clone(),
         };
-        let source_facet_set_params = facet_set_params_generator
-            .generate_default_source_bundle_facet_params(&common_params)?;
-        let api_facet_set_params =
-            facet_set_params_generator.generate_default_api_bundle(&common_params)?;
-        let initialized_source_facets = self
-            .facet_service
-            .initialize_all(source_facet_set_params)
+        // Optional facets stay enabled with the config they were created with unless new ones are given.
+        let facet_configs = params
+            .facet_configs
+            .unwrap_or_else(|| initialized_project.facet_configs());
+        let excluded_facets = params
+            .excluded_facets
+            .unwrap_or_else(|| initialized_project.excluded_facets.clone());
+        let (current_source_facets, current_api_facets): (Vec<_>, Vec<_>) = initialized_project
+            .facets
+            .into_values()
+            .partition(|facet| matches!(facet, InitializedFacet::SourceBundle(_)));
+        // Existing files are written according to the write policy, based on what was recorded for them.
+        let reconciler = FacetReconciler {
+            facet_service: &self.facet_service,
+            common: &common_params,
+            write_policy: params.write_policy,
+        };
+
+        let source_facet_set_params = exclude_facets(
+            facet_set_params_generator
+                .generate_source_bundle_facet_params(&common_params, &facet_configs)?,
+            &excluded_facets,
+        );
+        let initialized_source_facets = reconciler
+            .reconcile(source_facet_set_params, current_source_facets)
             .await?;
+        for facet in &initialized_source_facets {
+            if let InitializedFacet::SourceBundle(source_bundle_facet) = facet {
+                for conflict in &source_bundle_facet.conflicts {
+                    warn!(
+                        "The {} facet wasn't updated in {}: {:?}",
+                        source_bundle_facet.facet_type, conflict.path, conflict.reason
+                    );
+                }
+            }
+        }
         // TODO: Figure out how to better order commits and pushes
         self.source_service.commit_and_push_changes(
             initialized_source.clone(),
             "Updated facets for project".to_string(),
         )?;
-        let initialized_api_facets = self
-            .facet_service
-            .initialize_all(api_facet_set_params)
+        let api_facet_set_params = exclude_facets(
+            facet_set_params_generator
+                .generate_api_bundle_facet_params(&common_params, &facet_configs)?,
+            &excluded_facets,
+        );
+        let initialized_api_facets = reconciler
+            .reconcile(api_facet_set_params, current_api_facets)
             .await?;
         // FIXME: Also add facet by name as well
         let initialized_facets = [initialized_source_facets, initialized_api_facets]
@@ -308,10 +685,47 @@ where
             ecosystem: initialized_ecosystem,
             source: initialized_source,
             facets: initialized_facets,
-            name: initialized_project.name.clone(),
+            name: initialized_project.name,
+            excluded_facets,
         })
     }
 
+    async fn remove_facet(
+        &self,
+        params: ProjectFacetRemoveParams,
+    ) -> Result<InitializedProject, SkootError> {
+        let mut initialized_project = self.get(params.project_get_params).await?;
+        let facet = initialized_project
+            .facets
+            .remove(&params.facet_map_key)
+            .ok_or(SkootError::from("Facet not found"))?;
+        let facet_type = facet.facet_type();
+        let initialized_source = self.repo_service.clone_local_or_pull(
+            initialized_project.repo.clone(),
+            initialized_project.source.path.clone(),
+        )?;
+        let common_params = CommonFacetCreateParams {
+            project_name: initialized_project.name.clone(),
+            source: initialized_source.clone(),
+            repo: initialized_project.repo.clone(),
+            ecosystem: initialized_project.ecosystem.clone(),
+        };
+        self.facet_service
+            .remove(FacetRemoveParams {
+                common: common_params,
+                facet,
+                force: params.force,
+            })
+            .await?;
+        info!("Removed the {facet_type} facet");
+
+        if !initialized_project.excluded_facets.contains(&facet_type) {
+            initialized_project.excluded_facets.push(facet_type);
+        }
+        initialized_project.source = initialized_source;
+        Ok(initialized_project)
+    }
+
     async fn outputs_list(
         &self,
         params: ProjectOutputsListParams,
@@ -338,25 +752,24 @@ where
 }
 
 #[cfg(test)]
-mod tests {
-    use std::path::Path;
+pub(crate) mod tests {
+    use std::{collections::HashMap, path::Path};
 
     use skootrs_model::skootrs::{
-        facet::{
-            APIBundleFacet, APIContent, FacetCreateParams, FacetSetCreateParams, SourceBundleFacet,
-            SupportedFacetType,
-        },
+        facet::{APIBundleFacet, APIContent, SourceBundleFacet, SourceFileContent, WriteOutcome},
         label::Label,
-        EcosystemInitializeParams, GithubRepoParams, GithubUser, GoParams, InitializedEcosystem,
-        InitializedGithubRepo, InitializedGo, InitializedMaven, InitializedRepo, ProjectOutputType,
-        RepoCreateParams, SourceInitializeParams,
+        EcosystemInitializeParams, GithubRepoParams, GithubRepoSettings, GithubUser, GoParams,
+        InitializedEcosystem, InitializedGithubRepo, InitializedGo, InitializedMaven,
+        InitializedSource, ProjectOutputType, PullRequestParams, RepoCreateParams,
+        SourceInitializeParams,
     };
 
     use super::*;
     struct MockRepoService;
     struct MockEcosystemService;
     struct MockSourceService;
-    struct MockFacetService;
+    /// Also used by the `reconcile` tests.
+    pub(crate) struct MockFacetService;
     struct MockOutputService;
 
     impl RepoService for MockRepoService {
@@ -374,6 +787,9 @@ mod tests {
             let initialized_repo = InitializedRepo::Github(InitializedGithubRepo {
                 name: inner_params.name,
                 organization: inner_params.organization,
+                host: inner_params.host,
+                default_branch: inner_params.settings.default_branch.clone(),
+                settings: Some(inner_params.settings),
             });
 
             Ok(initialized_repo)
@@ -413,10 +829,19 @@ mod tests {
             if repo_url == "error" {
                 return Err("Error".into());
             }
+            // Special case for testing projects with a state file
+            let name = if repo_url.ends_with("/skootrs-project") {
+                "skootrs-project"
+            } else {
+                "test"
+            };
 
             let initialized_repo = InitializedRepo::Github(InitializedGithubRepo {
-                name: "test".to_string(),
+                name: name.to_string(),
                 organization: GithubUser::User("testuser".to_string()),
+                host: "github.com".to_string(),
+                default_branch: "main".to_string(),
+                settings: None,
             });
 
             Ok(initialized_repo)
@@ -424,19 +849,101 @@ mod tests {
 
         async fn fetch_file_content<P: AsRef<std::path::Path> + Send>(
             &self,
-            _initialized_repo: &InitializedRepo,
+            initialized_repo: &InitializedRepo,
             path: P,
         ) -> Result<String, SkootError> {
             if path.as_ref().to_str().unwrap() == "error" {
                 return Err("Error".into());
             }
 
-            Ok("Worked".to_string())
+            let InitializedRepo::Github(inner_repo) = initialized_repo;
+            match inner_repo.name.as_str() {
+                // Special cases for testing project discovery
+                "no-state" => Err("Not Found".into()),
+                "skootrs-project" => Ok(serde_json::to_string(&ProjectState::new(
+                    InitializedProject {
+                        repo: initialized_repo.clone(),
+                        ecosystem: InitializedEcosystem::Go(InitializedGo {
+                            name: "skootrs-project".to_string(),
+                            host: "github.com/testorg".to_string(),
+                        }),
+                        source: InitializedSource {
+                            path: "test".to_string(),
+                        },
+                        facets: HashMap::from([(
+                            FacetMapKey::Type(SupportedFacetType::Readme),
+                            InitializedFacet::SourceBundle(SourceBundleFacet {
+                                source_files: Some(vec![]),
+                                facet_type: SupportedFacetType::Readme,
+                                source_files_content: None,
+                                labels: vec![],
+                                conflicts: vec![],
+                                config: None,
+                            }),
+                        )]),
+                        name: "skootrs-project".to_string(),
+                        excluded_facets: vec![],
+                    },
+                    "test".to_string(),
+                ))?),
+                _ => Ok("Worked".to_string()),
+            }
+        }
+
+        async fn fetch_optional_file_content<P: AsRef<std::path::Path> + Send>(
+            &self,
+            initialized_repo: &InitializedRepo,
+            path: P,
+        ) -> Result<Option<String>, SkootError> {
+            let InitializedRepo::Github(inner_repo) = initialized_repo;
+            match (
+                inner_repo.organization.get_name().as_str(),
+                inner_repo.name.as_str(),
+            ) {
+                // Special cases for testing project discovery
+                ("unreachable", _) => Err("API rate limit exceeded".into()),
+                (_, "no-state") => Ok(None),
+                _ => self
+                    .fetch_file_content(initialized_repo, path)
+                    .await
+                    .map(Some),
+            }
         }
 
         async fn archive(&self, initialized_repo: InitializedRepo) -> Result<String, SkootError> {
             Ok(initialized_repo.full_url())
         }
+
+        async fn delete(&self, initialized_repo: InitializedRepo) -> Result<String, SkootError> {
+            Ok(initialized_repo.full_url())
+        }
+
+        async fn list(&self, owner: GithubUser) -> Result<Vec<InitializedRepo>, SkootError> {
+            Ok(["skootrs-project", "no-state", "invalid-state"]
+                .iter()
+                .map(|name| {
+                    InitializedRepo::Github(InitializedGithubRepo {
+                        name: (*name).to_string(),
+                        organization: owner.clone(),
+                        host: "github.com".to_string(),
+                        default_branch: "main".to_string(),
+                        settings: None,
+                    })
+                })
+                .collect())
+        }
+
+        async fn create_pull_request(
+            &self,
+            initialized_repo: &InitializedRepo,
+            params: PullRequestParams,
+        ) -> Result<String, SkootError> {
+            if params.head == "error" {
+                return Err("Error".into());
+            }
+
+            Ok(format!("{}/pull/1", initialized_repo.full_url()))
+        }
     }
 
     impl EcosystemService for MockEcosystemService {
@@ -468,6 +975,17 @@ mod tests {
 
             Ok(initialized_ecosystem)
         }
+
+        fn detect(&self, source: &InitializedSource) -> Result<InitializedEcosystem, SkootError> {
+            if source.path == "error" {
+                return Err("Error".into());
+            }
+
+            Ok(InitializedEcosystem::Go(InitializedGo {
+                name: "test".to_string(),
+                host: "github.com/testuser".to_string(),
+            }))
+        }
     }
 
     impl SourceService for MockSourceService {
@@ -517,6 +1035,22 @@ mod tests {
             Ok(())
         }
 
+        fn write_file_with_policy<P: AsRef<std::path::Path>, C: AsRef<[u8]>>(
+            &self,
+            _source: &InitializedSource,
+            _path: P,
+            name: String,
+            _contents: C,
+            _policy: &WritePolicy,
+            _recorded: Option<&SourceFile>,
+        ) -> Result<WriteOutcome, SkootError> {
+            if name == "error" {
+                return Err("Error".into());
+            }
+
+            Ok(WriteOutcome::Written)
+        }
+
         fn read_file<P: AsRef<std::path::Path>>(
             &self,
             _source: &InitializedSource,
@@ -534,15 +1068,46 @@ mod tests {
             &self,
             _source: &InitializedSource,
             path: P,
-            _name: String,
+            name: String,
         ) -> Result<String, SkootError> {
-            if path.as_ref().to_str().unwrap() == "error" {
+            // Special case for testing files that don't exist
+            if path.as_ref().to_str().unwrap() == "error" || name == "missing" {
                 return Err("Error".into());
             }
 
             Ok("fakehash".to_string())
         }
 
+        fn file_exists<P: AsRef<Path>>(
+            &self,
+            source: &InitializedSource,
+            path: P,
+            name: &str,
+        ) -> Result<bool, SkootError> {
+            if path.as_ref().to_str().unwrap() == "error" {
+                return Err("Error".into());
+            }
+            // Special case for testing adopting a project that's already managed by Skootrs
+            if name == STATE_FILE_NAME {
+                return Ok(source.path.starts_with("adopted/"));
+            }
+
+            Ok(name != "missing")
+        }
+
+        fn remove_file<P: AsRef<Path>>(
+            &self,
+            _source: &InitializedSource,
+            path: P,
+            _name: String,
+        ) -> Result<(), SkootError> {
+            if path.as_ref().to_str().unwrap() == "error" {
+                return Err("Error".into());
+            }
+
+            Ok(())
+        }
+
         fn pull_updates(&self, source: InitializedSource) -> Result<(), SkootError> {
             if source.path == "error" {
                 return Err("Error".into());
@@ -550,6 +1115,18 @@ mod tests {
 
             Ok(())
         }
+
+        fn create_branch(
+            &self,
+            _source: &InitializedSource,
+            branch: &str,
+        ) -> Result<(), SkootError> {
+            if branch == "error" {
+                return Err("Error".into());
+            }
+
+            Ok(())
+        }
     }
 
     impl RootFacetService for MockFacetService {
@@ -571,6 +1148,8 @@ mod tests {
                         facet_type: SupportedFacetType::Readme,
                         source_files_content: None,
                         labels: vec![Label::Custom("test".to_string())],
+                        conflicts: vec![],
+                        config: None,
                     };
 
                     Ok(InitializedFacet::SourceBundle(source_bundle_facet))
@@ -587,6 +1166,7 @@ mod tests {
                         }],
                         facet_type: SupportedFacetType::BranchProtection,
                         labels: vec![Label::Custom("test".to_string())],
+                        config: None,
                     };
 
                     Ok(InitializedFacet::APIBundle(api_bundle_facet))
@@ -606,6 +1186,33 @@ mod tests {
 
             Ok(initialized_facets)
         }
+
+        fn generate_source_files_content(
+            &self,
+            params: &FacetCreateParams,
+        ) -> Result<Vec<SourceFileContent>, SkootError> {
+            match params {
+                // Only the README already exists in the mock source.
+                FacetCreateParams::SourceBundle(s) => Ok(vec![SourceFileContent {
+                    name: if s.facet_type == SupportedFacetType::Readme {
+                        "README.md".to_string()
+                    } else {
+                        "missing".to_string()
+                    },
+                    path: "./".to_string(),
+                    content: "test".to_string(),
+                }]),
+                FacetCreateParams::APIBundle(_) => Ok(vec![]),
+            }
+        }
+
+        async fn remove(&self, params: FacetRemoveParams) -> Result<(), SkootError> {
+            if params.common.project_name == "error" {
+                return Err("Error".into());
+            }
+
+            Ok(())
+        }
     }
 
     impl OutputService for MockOutputService {
@@ -643,6 +1250,8 @@ mod tests {
                 name: "test".to_string(),
                 description: "foobar".to_string(),
                 organization: GithubUser::User("testuser".to_string()),
+                host: "github.com".to_string(),
+                settings: GithubRepoSettings::default(),
             }),
             ecosystem_params: EcosystemInitializeParams::Go(GoParams {
                 name: "test".to_string(),
@@ -651,6 +1260,8 @@ mod tests {
             source_params: SourceInitializeParams {
                 parent_path: "test".to_string(),
             },
+            facet_configs: vec![],
+            excluded_facets: vec![],
         };
 
         let local_project_service = LocalProjectService {
@@ -680,4 +1291,304 @@ mod tests {
         // of handling that.
         assert_eq!(initialized_project.facets.len(), 2);
     }
+
+    fn create_params(name: &str, parent_path: &str) -> ProjectCreateParams {
+        ProjectCreateParams {
+            name: name.to_string(),
+            repo_params: RepoCreateParams::Github(GithubRepoParams {
+                name: name.to_string(),
+                description: "foobar".to_string(),
+                organization: GithubUser::User("testuser".to_string()),
+                host: "github.com".to_string(),
+                settings: GithubRepoSettings::default(),
+            }),
+            ecosystem_params: EcosystemInitializeParams::Go(GoParams {
+                name: name.to_string(),
+                host: "github.com".to_string(),
+            }),
+            source_params: SourceInitializeParams {
+                parent_path: parent_path.to_string(),
+            },
+            facet_configs: vec![],
+            excluded_facets: vec![],
+        }
+    }
+
+    fn mock_project_service() -> LocalProjectService<
+        MockRepoService,
+        MockEcosystemService,
+        MockSourceService,
+        MockFacetService,
+        MockOutputService,
+    > {
+        LocalProjectService {
+            repo_service: MockRepoService,
+            ecosystem_service: MockEcosystemService,
+            source_service: MockSourceService,
+            facet_service: MockFacetService,
+            output_service: MockOutputService,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_initialize_records_progress_until_failure() {
+        let mut recorded = Vec::new();
+
+        // The mock source service fails to clone into the `error` path.
+        let result = mock_project_service()
+            .resume(
+                ProjectCreateProgress::new(create_params("test", "error")),
+                |progress| {
+                    recorded.push(progress.clone());
+                    Ok(())
+                },
+            )
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(recorded.len(), 1);
+        let progress = &recorded[0];
+        assert_eq!(
+            progress.repo.as_ref().unwrap().full_url(),
+            "https://github.com/testuser/test"
+        );
+        assert!(progress.source.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_resume_skips_completed_steps() {
+        // The mock repo service fails to create a repo named `error`, so this only succeeds if the repo
+        // recorded in the progress is used.
+        let mut params = create_params("test", "test");
+        let RepoCreateParams::Github(repo_params) = &mut params.repo_params;
+        repo_params.name = "error".to_string();
+        let mut progress = ProjectCreateProgress::new(params);
+        progress.repo = Some(InitializedRepo::Github(InitializedGithubRepo {
+            name: "test".to_string(),
+            organization: GithubUser::User("testuser".to_string()),
+            host: "github.com".to_string(),
+            default_branch: "main".to_string(),
+            settings: None,
+        }));
+        progress.source = Some(InitializedSource {
+            path: "test/test".to_string(),
+        });
+        let mut steps = 0;
+
+        let project = mock_project_service()
+            .resume(progress, |_| {
+                steps += 1;
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(project.source.path, "test/test");
+        // The ecosystem, source facets, push and API facets are left.
+        assert_eq!(steps, 4);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_removes_clone() {
+        let temp_dir = tempdir::TempDir::new("test").unwrap();
+        let clone_path = temp_dir.path().join("test");
+        std::fs::create_dir(&clone_path).unwrap();
+        let mut progress =
+            ProjectCreateProgress::new(create_params("test", temp_dir.path().to_str().unwrap()));
+        progress.source = Some(InitializedSource {
+            path: clone_path.to_string_lossy().to_string(),
+        });
+        progress.repo = Some(InitializedRepo::Github(InitializedGithubRepo {
+            name: "test".to_string(),
+            organization: GithubUser::User("testuser".to_string()),
+            host: "github.com".to_string(),
+            default_branch: "main".to_string(),
+            settings: None,
+        }));
+
+        progress.source_created = false;
+        mock_project_service()
+            .rollback(&progress, RollbackStrategy::Delete)
+            .await
+            .unwrap();
+        assert!(clone_path.exists());
+
+        progress.source_created = true;
+        mock_project_service()
+            .rollback(&progress, RollbackStrategy::Delete)
+            .await
+            .unwrap();
+        assert!(!clone_path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_resume_records_existing_directory() {
+        let temp_dir = tempdir::TempDir::new("test").unwrap();
+        std::fs::create_dir(temp_dir.path().join("test")).unwrap();
+        let progress =
+            ProjectCreateProgress::new(create_params("test", temp_dir.path().to_str().unwrap()));
+        let mut source_created = None;
+
+        mock_project_service()
+            .resume(progress, |progress| {
+                if progress.source.is_some() {
+                    source_created.get_or_insert(progress.source_created);
+                }
+                Ok(())
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(source_created, Some(false));
+    }
+
+    #[tokio::test]
+    async fn test_remove_facet() {
+        let local_project_service = mock_project_service();
+        let params = |facet_type: SupportedFacetType| ProjectFacetRemoveParams {
+            project_get_params: ProjectGetParams {
+                project_url: "https://github.com/testorg/skootrs-project".to_string(),
+            },
+            facet_map_key: FacetMapKey::Type(facet_type),
+            force: false,
+        };
+
+        let project = local_project_service
+            .remove_facet(params(SupportedFacetType::Readme))
+            .await
+            .unwrap();
+
+        assert!(project.facets.is_empty());
+        // The removed facet isn't applied again when the project is updated.
+        assert_eq!(project.excluded_facets, vec![SupportedFacetType::Readme]);
+        assert!(local_project_service
+            .remove_facet(params(SupportedFacetType::Fuzzing))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_discover_projects() {
+        let local_project_service = LocalProjectService {
+            repo_service: MockRepoService,
+            ecosystem_service: MockEcosystemService,
+            source_service: MockSourceService,
+            facet_service: MockFacetService,
+            output_service: MockOutputService,
+        };
+
+        let projects = local_project_service
+            .discover(ProjectDiscoverParams {
+                owner: GithubUser::Organization("testorg".to_string()),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(projects.len(), 1);
+        assert_eq!(
+            projects[0].repo.full_url(),
+            "https://github.com/testorg/skootrs-project"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_discover_projects_fails_on_fetch_errors() {
+        let local_project_service = LocalProjectService {
+            repo_service: MockRepoService,
+            ecosystem_service: MockEcosystemService,
+            source_service: MockSourceService,
+            facet_service: MockFacetService,
+            output_service: MockOutputService,
+        };
+
+        let result = local_project_service
+            .discover(ProjectDiscoverParams {
+                owner: GithubUser::Organization("unreachable".to_string()),
+            })
+            .await;
+
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_adopt_project_skips_conflicting_facets() {
+        let local_project_service = LocalProjectService {
+            repo_service: MockRepoService,
+            ecosystem_service: MockEcosystemService,
+            source_service: MockSourceService,
+            facet_service: MockFacetService,
+            output_service: MockOutputService,
+        };
+
+        let result = local_project_service
+            .adopt(ProjectAdoptParams {
+                repo_url: "https://github.com/testuser/test".to_string(),
+                source_params: SourceInitializeParams {
+                    parent_path: "test".to_string(),
+                },
+                overwrite_existing: false,
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(result.branch, ADOPT_BRANCH);
+        assert_eq!(
+            result.conflicting_facets,
+            vec![FacetConflict {
+                facet: FacetMapKey::Type(SupportedFacetType::Readme),
+                existing_files: vec!["README.md".to_string()],
+            }]
+        );
+        assert!(!result
+            .applied_facets
+            .contains(&FacetMapKey::Type(SupportedFacetType::Readme)));
+        assert!(!result
+            .applied_facets
+            .contains(&FacetMapKey::Type(SupportedFacetType::DefaultSourceCode)));
+        assert!(result
+            .applied_facets
+            .contains(&FacetMapKey::Type(SupportedFacetType::License)));
+    }
+
+    #[tokio::test]
+    async fn test_adopt_project_overwrites_when_asked() {
+        let local_project_service = LocalProjectService {
+            repo_service: MockRepoService,
+            ecosystem_service: MockEcosystemService,
+            source_service: MockSourceService,
+            facet_service: MockFacetService,
+            output_service: MockOutputService,
+        };
+
+        let result = local_project_service
+            .adopt(ProjectAdoptParams {
+                repo_url: "https://github.com/testuser/test".to_string(),
+                source_params: SourceInitializeParams {
+                    parent_path: "test".to_string(),
+                },
+                overwrite_existing: true,
+            })
+            .await
+            .unwrap();
+
+        assert!(result.conflicting_facets.is_empty());
+        assert!(result
+            .applied_facets
+            .contains(&FacetMapKey::Type(SupportedFacetType::Readme)));
+    }
+
+    #[tokio::test]
+    async fn test_adopt_project_refuses_existing_state() {
+        let local_project_service = mock_project_service();
+        let params = |overwrite_existing: bool| ProjectAdoptParams {
+            repo_url: "https://github.com/testuser/test".to_string(),
+            source_params: SourceInitializeParams {
+                parent_path: "adopted".to_string(),
+            },
+            overwrite_existing,
+        };
+
+        assert!(local_project_service.adopt(params(false)).await.is_err());
+        assert!(local_project_service.adopt(params(true)).await.is_ok());
+    }
 }
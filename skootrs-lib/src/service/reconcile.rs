@@ -0,0 +1,482 @@
+//
+// Copyright 2024 The Skootrs Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `reconcile` module brings the facets applied to a project in line with the facets the project
+//! should have. For each facet it works out whether the facet has to be created, updated, removed or
+//! left as is, and then carries that out. Both creating and updating a project go through it.
+
+#![allow(clippy::module_name_repetitions)]
+
+use sha2::Digest;
+use tracing::info;
+
+use skootrs_model::skootrs::{
+    facet::{
+        CommonFacetCreateParams, FacetAction, FacetCreateParams, FacetRemoveParams,
+        FacetSetCreateParams, InitializedFacet, SupportedFacetType, WritePolicy,
+    },
+    SkootError,
+};
+
+use super::{facet::RootFacetService, source::resolve_source_path};
+
+/// A change to one of a project's facets.
+#[derive(Clone, Debug)]
+pub struct FacetChange {
+    /// The type of the facet.
+    pub facet_type: SupportedFacetType,
+    /// What has to be done to the facet.
+    pub action: FacetAction,
+    /// The params the facet is created or updated with. Facets that are removed don't have any.
+    pub params: Option<FacetCreateParams>,
+    /// The facet as it's currently applied, if it is.
+    pub current: Option<InitializedFacet>,
+}
+
+/// The `FacetReconciler` struct works out and applies the changes needed to get from the facets applied to
+/// a project to the facets it should have.
+pub struct FacetReconciler<'a, FS: RootFacetService> {
+    /// The service the facets are created, updated and removed with.
+    pub facet_service: &'a FS,
+    /// The context of the project the facets belong to.
+    pub common: &'a CommonFacetCreateParams,
+    /// The policy for writing the files of source bundle facets that already exist in the source.
+    pub write_policy: WritePolicy,
+}
+
+impl<FS: RootFacetService + Sync> FacetReconciler<'_, FS> {
+    /// Works out the change needed for each facet. The facets in `desired` are created if they aren't in
+    /// `current`, and updated or left as is if they are. The facets in `current` that aren't in `desired`
+    /// are removed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content of a source bundle facet can't be generated to check whether it's
+    /// up to date.
+    pub fn plan(
+        &self,
+        desired: FacetSetCreateParams,
+        mut current: Vec<InitializedFacet>,
+    ) -> Result<Vec<FacetChange>, SkootError> {
+        let mut changes = Vec::new();
+        for mut params in desired.facets_params {
+            let facet_type = params.facet_type();
+            let current_facet = current
+                .iter()
+                .position(|facet| facet.facet_type() == facet_type)
+                .map(|position| current.remove(position));
+            if let FacetCreateParams::SourceBundle(source_bundle_params) = &mut params {
+                source_bundle_params.write_policy = self.write_policy.clone();
+                if let Some(InitializedFacet::SourceBundle(recorded)) = &current_facet {
+                    source_bundle_params.recorded_source_files =
+                        recorded.source_files.clone().unwrap_or_default();
+                }
+            }
+            let action = match &current_facet {
+                None => FacetAction::Create,
+                Some(facet) if self.is_up_to_date(&params, facet)? => FacetAction::Noop,
+                Some(_) => FacetAction::Update,
+            };
+            changes.push(FacetChange {
+                facet_type,
+                action,
+                params: Some(params),
+                current: current_facet,
+            });
+        }
+        // Whatever is left is applied but isn't wanted anymore.
+        changes.extend(current.into_iter().map(|facet| FacetChange {
+            facet_type: facet.facet_type(),
+            action: FacetAction::Remove,
+            params: None,
+            current: Some(facet),
+        }));
+
+        Ok(changes)
+    }
+
+    /// Applies the changes and returns the facets the project ends up with. Facets are removed first so
+    /// the facets that are created or updated have the final say over the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the facets can't be created, updated or removed.
+    pub async fn apply(
+        &self,
+        changes: Vec<FacetChange>,
+    ) -> Result<Vec<InitializedFacet>, SkootError> {
+        for change in &changes {
+            info!("{} the {} facet", change.action, change.facet_type);
+        }
+        for change in &changes {
+            if let (FacetAction::Remove, Some(facet)) = (&change.action, &change.current) {
+                self.facet_service
+                    .remove(FacetRemoveParams {
+                        common: self.common.clone(),
+                        facet: facet.clone(),
+                        force: false,
+                    })
+                    .await?;
+            }
+        }
+        let facets_params = changes
+            .iter()
+            .filter(|change| matches!(change.action, FacetAction::Create | FacetAction::Update))
+            .filter_map(|change| change.params.clone())
+            .collect();
+        let mut applied = self
+            .facet_service
+            .initialize_all(FacetSetCreateParams { facets_params })
+            .await?
+            .into_iter();
+
+        // The facets are returned in the order they're desired in.
+        Ok(changes
+            .into_iter()
+            .filter_map(|change| match change.action {
+                FacetAction::Create | FacetAction::Update => applied.next(),
+                FacetAction::Noop => change.current,
+                FacetAction::Remove => None,
+            })
+            .collect())
+    }
+
+    /// Plans and applies the changes in one go.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the changes can't be planned or applied.
+    pub async fn reconcile(
+        &self,
+        desired: FacetSetCreateParams,
+        current: Vec<InitializedFacet>,
+    ) -> Result<Vec<InitializedFacet>, SkootError> {
+        let changes = self.plan(desired, current)?;
+        self.apply(changes).await
+    }
+
+    /// A source bundle facet is up to date when the files it would generate are exactly the ones recorded
+    /// for it and they're all still in the source. API bundle facets are always applied again since their
+    /// settings can be changed outside of Skootrs.
+    fn is_up_to_date(
+        &self,
+        params: &FacetCreateParams,
+        current: &InitializedFacet,
+    ) -> Result<bool, SkootError> {
+        let (
+            FacetCreateParams::SourceBundle(source_bundle_params),
+            InitializedFacet::SourceBundle(recorded),
+        ) = (params, current)
+        else {
+            return Ok(false);
+        };
+        if !recorded.conflicts.is_empty() || recorded.config != source_bundle_params.config {
+            return Ok(false);
+        }
+        let recorded_files = recorded.source_files.clone().unwrap_or_default();
+        let generated = self.facet_service.generate_source_files_content(params)?;
+        Ok(generated.len() == recorded_files.len()
+            && generated.iter().all(|content| {
+                let hash = format!("{:x}", sha2::Sha256::digest(&content.content));
+                recorded_files.iter().any(|recorded| {
+                    recorded.name == content.name
+                        && recorded.path == content.path
+                        && recorded.hash == hash
+                }) && resolve_source_path(&self.common.source, &content.path, &content.name)
+                    .is_ok_and(|path| path.exists())
+            }))
+    }
+}
+
+/// Leaves the excluded facets out of a set of facet params, including from the facets each source bundle
+/// facet is told are enabled.
+#[must_use]
+pub fn exclude_facets(
+    desired: FacetSetCreateParams,
+    excluded: &[SupportedFacetType],
+) -> FacetSetCreateParams {
+    let facets_params = desired
+        .facets_params
+        .into_iter()
+        .filter(|params| !excluded.contains(&params.facet_type()))
+        .map(|mut params| {
+            if let FacetCreateParams::SourceBundle(source_bundle_params) = &mut params {
+                source_bundle_params
+                    .enabled_facets
+                    .retain(|facet_type| !excluded.contains(facet_type));
+            }
+            params
+        })
+        .collect();
+
+    FacetSetCreateParams { facets_params }
+}
+
+#[cfg(test)]
+mod tests {
+    use skootrs_model::skootrs::{
+        facet::{
+            APIBundleFacetParams, SourceBundleFacet, SourceBundleFacetCreateParams, SourceFile,
+        },
+        GithubUser, InitializedEcosystem, InitializedGithubRepo, InitializedMaven, InitializedRepo,
+        InitializedSource,
+    };
+    use tempdir::TempDir;
+
+    use super::*;
+    use crate::service::{facet::LocalFacetService, project::tests::MockFacetService};
+
+    fn common(source: &InitializedSource) -> CommonFacetCreateParams {
+        CommonFacetCreateParams {
+            project_name: "test".to_string(),
+            source: source.clone(),
+            repo: InitializedRepo::Github(InitializedGithubRepo {
+                name: "test".to_string(),
+                organization: GithubUser::User("testuser".to_string()),
+                host: "github.com".to_string(),
+                default_branch: "main".to_string(),
+                settings: None,
+            }),
+            ecosystem: InitializedEcosystem::Maven(InitializedMaven {
+                group_id: "com.example".to_string(),
+                artifact_id: "test".to_string(),
+            }),
+        }
+    }
+
+    fn source_params(
+        common: &CommonFacetCreateParams,
+        facet_type: SupportedFacetType,
+    ) -> FacetCreateParams {
+        FacetCreateParams::SourceBundle(SourceBundleFacetCreateParams {
+            common: common.clone(),
+            facet_type,
+            labels: vec![],
+            write_policy: WritePolicy::default(),
+            recorded_source_files: vec![],
+            enabled_facets: vec![],
+            config: None,
+        })
+    }
+
+    /// A source bundle facet recorded with a single file, which is written to the source with the given
+    /// content. The mock generates `README.md` for the Readme facet and `missing` for the others, with
+    /// `test` as their content.
+    fn recorded(
+        source: &InitializedSource,
+        facet_type: SupportedFacetType,
+        name: &str,
+        content: &str,
+    ) -> InitializedFacet {
+        std::fs::write(std::path::Path::new(&source.path).join(name), content).unwrap();
+        InitializedFacet::SourceBundle(SourceBundleFacet {
+            source_files: Some(vec![SourceFile {
+                name: name.to_string(),
+                path: "./".to_string(),
+                hash: format!("{:x}", sha2::Sha256::digest(content)),
+            }]),
+            facet_type,
+            source_files_content: None,
+            labels: vec![],
+            conflicts: vec![],
+            config: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_reconcile_facets() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let common = common(&source);
+        let facet_service = MockFacetService;
+        let reconciler = FacetReconciler {
+            facet_service: &facet_service,
+            common: &common,
+            write_policy: WritePolicy::ThreeWayMerge,
+        };
+        let desired = FacetSetCreateParams {
+            facets_params: vec![
+                source_params(&common, SupportedFacetType::Readme),
+                source_params(&common, SupportedFacetType::License),
+                source_params(&common, SupportedFacetType::Scorecard),
+                FacetCreateParams::APIBundle(APIBundleFacetParams {
+                    common: common.clone(),
+                    facet_type: SupportedFacetType::BranchProtection,
+                    config: None,
+                }),
+            ],
+        };
+        let current = vec![
+            // Generates the same content that was recorded.
+            recorded(&source, SupportedFacetType::Readme, "README.md", "test"),
+            // Generates different content than what was recorded.
+            recorded(
+                &source,
+                SupportedFacetType::License,
+                "missing",
+                "Old license",
+            ),
+            recorded(
+                &source,
+                SupportedFacetType::Fuzzing,
+                "Fuzzing.md",
+                "Fuzzing",
+            ),
+        ];
+
+        let changes = reconciler.plan(desired, current).unwrap();
+        let actions = changes
+            .iter()
+            .map(|change| (change.facet_type.clone(), change.action.clone()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            actions,
+            vec![
+                (SupportedFacetType::Readme, FacetAction::Noop),
+                (SupportedFacetType::License, FacetAction::Update),
+                (SupportedFacetType::Scorecard, FacetAction::Create),
+                (SupportedFacetType::BranchProtection, FacetAction::Create),
+                (SupportedFacetType::Fuzzing, FacetAction::Remove),
+            ]
+        );
+        // Updated files are written according to the write policy, based on what was recorded for them.
+        let Some(FacetCreateParams::SourceBundle(license_params)) = &changes[1].params else {
+            panic!("Expected source bundle params for the License facet");
+        };
+        assert_eq!(license_params.write_policy, WritePolicy::ThreeWayMerge);
+        assert_eq!(license_params.recorded_source_files.len(), 1);
+
+        // The facet that's up to date is kept as is and the removed one is left out. The mock creates
+        // every source bundle facet as a Readme facet.
+        let facets = reconciler.apply(changes).await.unwrap();
+        assert_eq!(facets.len(), 4);
+        let InitializedFacet::SourceBundle(readme) = &facets[0] else {
+            panic!("Expected the Readme source bundle facet");
+        };
+        assert_eq!(
+            readme.source_files.as_ref().unwrap()[0].hash,
+            format!("{:x}", sha2::Sha256::digest("test"))
+        );
+        assert_eq!(facets[3].facet_type(), SupportedFacetType::BranchProtection);
+    }
+
+    #[test]
+    fn test_missing_files_are_written_again() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let common = common(&source);
+        let facet_service = MockFacetService;
+        let reconciler = FacetReconciler {
+            facet_service: &facet_service,
+            common: &common,
+            write_policy: WritePolicy::default(),
+        };
+        let current = vec![recorded(
+            &source,
+            SupportedFacetType::Readme,
+            "README.md",
+            "test",
+        )];
+        std::fs::remove_file(temp_dir.path().join("README.md")).unwrap();
+
+        let changes = reconciler
+            .plan(
+                FacetSetCreateParams {
+                    facets_params: vec![source_params(&common, SupportedFacetType::Readme)],
+                },
+                current,
+            )
+            .unwrap();
+        assert_eq!(changes[0].action, FacetAction::Update);
+    }
+
+    #[tokio::test]
+    async fn test_modified_files_are_not_removed() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let common = common(&source);
+        // The files are only checked for modifications by the real facet service.
+        let facet_service = LocalFacetService::default();
+        let reconciler = FacetReconciler {
+            facet_service: &facet_service,
+            common: &common,
+            write_policy: WritePolicy::default(),
+        };
+        let nothing = || FacetSetCreateParams {
+            facets_params: vec![],
+        };
+        let current = vec![
+            recorded(&source, SupportedFacetType::Readme, "README.md", "Readme"),
+            recorded(
+                &source,
+                SupportedFacetType::Fuzzing,
+                "Fuzzing.md",
+                "Fuzzing",
+            ),
+        ];
+        std::fs::write(temp_dir.path().join("Fuzzing.md"), "Modified").unwrap();
+
+        let err = reconciler
+            .reconcile(nothing(), current.clone())
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("The Fuzzing facet can't be removed since these files were modified since they were recorded: Fuzzing.md"));
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("Fuzzing.md")).unwrap(),
+            "Modified"
+        );
+
+        // The unmodified files are removed once the modified facet is no longer in the way.
+        let facets = reconciler
+            .reconcile(nothing(), vec![current[0].clone()])
+            .await
+            .unwrap();
+        assert!(facets.is_empty());
+        assert!(!temp_dir.path().join("README.md").exists());
+    }
+
+    #[test]
+    fn test_exclude_facets() {
+        let source = InitializedSource {
+            path: "/tmp/test".to_string(),
+        };
+        let common = common(&source);
+        let mut readme = source_params(&common, SupportedFacetType::Readme);
+        if let FacetCreateParams::SourceBundle(params) = &mut readme {
+            params.enabled_facets = vec![SupportedFacetType::Readme, SupportedFacetType::Scorecard];
+        }
+        let desired = FacetSetCreateParams {
+            facets_params: vec![
+                readme,
+                source_params(&common, SupportedFacetType::Scorecard),
+            ],
+        };
+
+        let desired = exclude_facets(desired, &[SupportedFacetType::Scorecard]);
+        assert_eq!(desired.facets_params.len(), 1);
+        let FacetCreateParams::SourceBundle(readme) = &desired.facets_params[0] else {
+            panic!("Expected source bundle params for the Readme facet");
+        };
+        assert_eq!(readme.enabled_facets, vec![SupportedFacetType::Readme]);
+    }
+}
@@ -22,7 +22,9 @@ use chrono::Utc;
 use octocrab::Octocrab;
 use tracing::{info, debug};
 
-use skootrs_model::{cd_events::repo_created::{RepositoryCreatedEvent, RepositoryCreatedEventContext, RepositoryCreatedEventContextId, RepositoryCreatedEventContextVersion, RepositoryCreatedEventSubject, RepositoryCreatedEventSubjectContent, RepositoryCreatedEventSubjectContentName, RepositoryCreatedEventSubjectContentUrl, RepositoryCreatedEventSubjectId}, skootrs::{InitializedRepoGetParams, GithubRepoParams, GithubUser, InitializedGithubRepo, InitializedRepo, InitializedSource, RepoCreateParams, SkootError}};
+use super::github::GithubClient;
+
+use skootrs_model::{cd_events::repo_created::{RepositoryCreatedEvent, RepositoryCreatedEventContext, RepositoryCreatedEventContextId, RepositoryCreatedEventContextVersion, RepositoryCreatedEventSubject, RepositoryCreatedEventSubjectContent, RepositoryCreatedEventSubjectContentName, RepositoryCreatedEventSubjectContentUrl, RepositoryCreatedEventSubjectId}, skootrs::{InitializedRepoGetParams, GithubRepoParams, GithubRepoSettings, GithubUser, RepoVisibility, InitializedGithubRepo, InitializedRepo, InitializedSource, PullRequestParams, RepoCreateParams, SkootError}};
 
 /// The `RepoService` trait provides an interface for initializing and managing a project's source code
 /// repository. This repo is usually something like Github or Gitlab.
@@ -64,28 +66,67 @@ pub trait RepoService {
     /// Returns an error if the file can't be fetched from the repository for any reason.
     fn fetch_file_content<P: AsRef<std::path::Path> + Send>(&self, initialized_repo: &InitializedRepo, path: P) -> impl std::future::Future<Output = Result<String, SkootError>> + std::marker::Send;
 
+    /// Fetches an arbitrary file from the repository like `fetch_file_content`, but returns `None` if the
+    /// file doesn't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be fetched for any reason other than it not existing, e.g. the
+    /// credentials being rejected or the API being rate limited.
+    fn fetch_optional_file_content<P: AsRef<std::path::Path> + Send>(&self, initialized_repo: &InitializedRepo, path: P) -> impl std::future::Future<Output = Result<Option<String>, SkootError>> + std::marker::Send;
+
     fn archive(&self, initialized_repo: InitializedRepo) -> impl std::future::Future<Output = Result<String, SkootError>> + Send;
+
+    /// Deletes a repository and returns its URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repository can't be deleted.
+    fn delete(&self, initialized_repo: InitializedRepo) -> impl std::future::Future<Output = Result<String, SkootError>> + Send;
+
+    /// Lists all the repositories that belong to a user or organization.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the repositories can't be listed.
+    fn list(&self, owner: GithubUser) -> impl std::future::Future<Output = Result<Vec<InitializedRepo>, SkootError>> + Send;
+
+    /// Opens a pull request against a repository and returns its URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the pull request can't be opened.
+    fn create_pull_request(&self, initialized_repo: &InitializedRepo, params: PullRequestParams) -> impl std::future::Future<Output = Result<String, SkootError>> + Send;
 }
 
 /// The `LocalRepoService` struct provides an implementation of the `RepoService` trait for initializing
 /// and managing a project's source code repository from the local machine. This doesn't mean the repo is
 /// local, but that the operations like API calls are run from the local machine.
-#[derive(Debug)]
-pub struct LocalRepoService {}
+#[derive(Debug, Default)]
+pub struct LocalRepoService {
+    /// The client for calling the GitHub API.
+    pub github: GithubClient,
+}
+
+impl LocalRepoService {
+    /// Checks a repo is on the GitHub instance the client calls, since its API can't reach repos on other
+    /// instances.
+    fn check_host(&self, host: &str) -> Result<(), SkootError> {
+        if host == self.github.host() {
+            Ok(())
+        } else {
+            Err(format!("Unsupported repo host {host}, the GitHub client is configured for {}", self.github.host()).into())
+        }
+    }
+}
 
 impl RepoService for LocalRepoService {
     async fn initialize(&self, params: RepoCreateParams) -> Result<InitializedRepo, SkootError> {
-        // TODO: The octocrab initialization should be done in a better place and be parameterized
-        let o: octocrab::Octocrab = octocrab::Octocrab::builder()
-            .personal_token(
-                    std::env::var("GITHUB_TOKEN").expect("GITHUB_TOKEN env var must be populated"),
-            )
-            .build()?;
-        octocrab::initialise(o);
         match params {
             RepoCreateParams::Github(g) => {
+                self.check_host(&g.host)?;
                 let github_repo_handler = GithubRepoHandler {
-                    client: octocrab::instance(),
+                    client: self.github.octocrab_for(&g.organization.get_name()).await?,
                 };
                 Ok(InitializedRepo::Github(github_repo_handler.create(g).await?))
             },
@@ -123,41 +164,35 @@ impl RepoService for LocalRepoService {
     }
     
     async fn get(&self, params: InitializedRepoGetParams) -> Result<InitializedRepo, SkootError> {
-        let parsed_url = url::Url::parse(&params.repo_url)?;
-        match parsed_url.host_str() {
-            Some("github.com") => {
-                let path = parsed_url.path();
-                let parts: Vec<&str> = path.split('/').collect();
-                let organization = parts[1];    
-                let name = parts[2];
-                let exists = octocrab::instance().repos(organization, name).get().await.is_ok();
-                if !exists {
+        // FIXME: This will probably break in weird ways since repos from a user and organization are handled
+        // slightly different in the Github API. I am not sure yet what the best way to determine if a repo
+        // belongs to a user or organization is.
+        let mut initialized_repo = InitializedRepo::try_from(params.repo_url)?;
+        match &mut initialized_repo {
+            InitializedRepo::Github(g) => {
+                self.check_host(&g.host)?;
+                let organization = g.organization.get_name();
+                let Ok(repo) = self.github.octocrab_for(&organization).await?.repos(&organization, &g.name).get().await else {
                     return Err("Repo does not exist".into());
+                };
+                if let Some(default_branch) = repo.default_branch {
+                    g.default_branch = default_branch;
                 }
-                Ok(InitializedRepo::Github(InitializedGithubRepo {
-                    name: name.to_string(),
-                    // FIXME: This will probably break in weird ways since repos from a user and organization are handled
-                    // slightly different in the Github API. I am not sure yet what the best way to determine if a repo
-                    // belongs to a user or organization is.
-                    organization: GithubUser::User(organization.to_string()),
-                }))
             },
-            Some(_) => Err("Unsupported repo host".into()),
-            _ => Err("Invalid repo URL".into()),
         }
+        Ok(initialized_repo)
     }
 
     async fn fetch_file_content<P: AsRef<std::path::Path> + Send>(&self, initialized_repo: &InitializedRepo, path: P) -> Result<String, SkootError> {
         match &initialized_repo {
             InitializedRepo::Github(g) => {
                 let path_str = path.as_ref().to_str().ok_or_else(|| SkootError::from("Failed to convert path to string"))?;
-                let content_items = octocrab::instance().repos(
+                let content_items = self.github.octocrab_for(&g.organization.get_name()).await?.repos(
                     g.organization.get_name(), g.name.clone()
                 )
                 .get_content()
                 .path(path_str)
-                // TODO: Should this support multiple branches?
-                .r#ref("main")
+                .r#ref(&g.default_branch)
                 .send()
                 .await?;
 
@@ -175,6 +210,14 @@ impl RepoService for LocalRepoService {
         }
     }
 
+    async fn fetch_optional_file_content<P: AsRef<std::path::Path> + Send>(&self, initialized_repo: &InitializedRepo, path: P) -> Result<Option<String>, SkootError> {
+        match self.fetch_file_content(initialized_repo, path).await {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if is_not_found(&e) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
     async fn archive(&self, initialized_repo: InitializedRepo) -> Result<String, SkootError> {
         match initialized_repo {
             InitializedRepo::Github(g) => {
@@ -190,43 +233,160 @@ impl RepoService for LocalRepoService {
 
                 info!("Archiving {owner}/{repo}");
 
-                // FIXME: This should work with `Octocrabe::instance()` but for some reason it doesn't pick up the token/session
-                let token = std::env::var("GITHUB_TOKEN").expect("GITHUB_TOKEN env variable is required");
-                let octocrab = Octocrab::builder().personal_token(token).build()?;
-                let archived_response: serde_json::Value = octocrab.patch(format!("/repos/{owner}/{repo}"), Some(&body)).await?;
+                let archived_response: serde_json::Value = self.github.octocrab_for(&owner).await?.patch(format!("/repos/{owner}/{repo}"), Some(&body)).await?;
                 info!("Archived: {archived_response}");
 
                 Ok(g.full_url())
             }
         }
     }
+
+    async fn delete(&self, initialized_repo: InitializedRepo) -> Result<String, SkootError> {
+        match initialized_repo {
+            InitializedRepo::Github(g) => {
+                let owner = g.organization.get_name();
+                let repo = g.name.clone();
+                info!("Deleting {owner}/{repo}");
+                // Note: This call just returns a status with no JSON output.
+                octocrab::map_github_error(
+                    self.github.octocrab_for(&owner).await?._delete(format!("/repos/{owner}/{repo}"), None::<&()>).await?,
+                )
+                .await?;
+                info!("Deleted {owner}/{repo}");
+
+                Ok(g.full_url())
+            }
+        }
+    }
+
+    async fn list(&self, owner: GithubUser) -> Result<Vec<InitializedRepo>, SkootError> {
+        let client = self.github.octocrab_for(&owner.get_name()).await?;
+        let first_page = match &owner {
+            GithubUser::User(name) => client.users(name).repos().per_page(100).send().await?,
+            GithubUser::Organization(name) => client.orgs(name).list_repos().per_page(100).send().await?,
+        };
+        let repos = client.all_pages(first_page).await?;
+        debug!("Found {} repos for {}", repos.len(), owner.get_name());
+
+        Ok(repos
+            .into_iter()
+            .map(|repo| InitializedRepo::Github(InitializedGithubRepo {
+                name: repo.name,
+                organization: owner.clone(),
+                host: self.github.host().to_string(),
+                default_branch: repo.default_branch.unwrap_or_else(|| "main".to_string()),
+                settings: None,
+            }))
+            .collect())
+    }
+
+    async fn create_pull_request(&self, initialized_repo: &InitializedRepo, params: PullRequestParams) -> Result<String, SkootError> {
+        match initialized_repo {
+            InitializedRepo::Github(g) => {
+                let pull_request = self.github.octocrab_for(&g.organization.get_name()).await?
+                    .pulls(g.organization.get_name(), g.name.clone())
+                    .create(params.title, params.head, params.base)
+                    .body(params.body)
+                    .send()
+                    .await?;
+                let url = pull_request
+                    .html_url
+                    .map_or_else(|| pull_request.url.clone(), |u| u.to_string());
+                info!("Opened pull request: {url}");
+                Ok(url)
+            }
+        }
+    }
 }
 
 /// The `GithubRepoHandler` struct represents a handler for initializing and managing Github repos.
 #[derive(Debug)]
 struct GithubRepoHandler {
-    client: Arc<octocrab::Octocrab>,
+    client: Arc<Octocrab>,
 }
 
 impl GithubRepoHandler {
     async fn create(&self, github_params: GithubRepoParams) -> Result<InitializedGithubRepo, SkootError> {
-        let new_repo = NewGithubRepoParams {
-            name: github_params.name.clone(),
-            description: github_params.description.clone(),
-            private: false,
-            has_issues: true,
-            has_projects: true,
-            has_wiki: true,
+        let owner = github_params.organization.get_name();
+        let name = github_params.name.clone();
+        let settings = &github_params.settings;
+        let is_organization = matches!(github_params.organization, GithubUser::Organization(_));
+        if settings.visibility == RepoVisibility::Internal && !is_organization {
+            return Err("Internal visibility is only available for organization repos".into());
+        }
+
+        let template_default_branch = if let Some(template) = &settings.template {
+            let (template_owner, template_name) = template
+                .split_once('/')
+                .ok_or_else(|| SkootError::from(format!("Invalid template repo {template}, expected owner/name")))?;
+            let generate_repo = GenerateGithubRepoParams {
+                owner: owner.clone(),
+                name: name.clone(),
+                description: github_params.description.clone(),
+                private: settings.visibility != RepoVisibility::Public,
+                include_all_branches: false,
+            };
+            let generated: serde_json::Value = self.client
+                .post(format!("/repos/{template_owner}/{template_name}/generate"), Some(&generate_repo))
+                .await?;
+            generated["default_branch"].as_str().map(str::to_string)
+        } else {
+            let new_repo = NewGithubRepoParams {
+                name: name.clone(),
+                description: github_params.description.clone(),
+                private: settings.visibility != RepoVisibility::Public,
+                // Only organization repos can set the visibility, the user repo endpoint just takes `private`.
+                visibility: is_organization.then(|| visibility_name(settings.visibility)),
+                has_issues: true,
+                has_projects: true,
+                has_wiki: true,
+            };
+            let _response: serde_json::Value = if is_organization {
+                self.client.post(format!("/orgs/{owner}/repos"), Some(&new_repo)).await?
+            } else {
+                self.client.post("/user/repos", Some(&new_repo)).await?
+            };
+            None
         };
 
-        let _response: serde_json::Value = match github_params.organization.clone() {
-            GithubUser::User(_) => octocrab::instance().post("/user/repos", Some(&new_repo)).await?,
-            GithubUser::Organization(name) => {
-                self.client
-                    .post(format!("/orgs/{name}/repos"), Some(&new_repo))
-                    .await?
-            }
+        // Repos generated from a template can only be made private, so internal visibility is set afterwards.
+        let settings_body = GithubRepoSettingsBody {
+            visibility: (settings.template.is_some() && settings.visibility == RepoVisibility::Internal)
+                .then(|| visibility_name(settings.visibility)),
+            allow_merge_commit: settings.allow_merge_commit,
+            allow_squash_merge: settings.allow_squash_merge,
+            allow_rebase_merge: settings.allow_rebase_merge,
+            delete_branch_on_merge: settings.delete_branch_on_merge,
+            security_and_analysis: SecurityAndAnalysis::new(settings),
         };
+        let _response: serde_json::Value = self.client
+            .patch(format!("/repos/{owner}/{name}"), Some(&settings_body))
+            .await?;
+
+        // An empty repo gets its default branch from the first push, see `clone_local`, but a generated repo
+        // already has the template's.
+        if let Some(template_default_branch) = template_default_branch.filter(|branch| *branch != settings.default_branch) {
+            let _response: serde_json::Value = self.client
+                .post(
+                    format!("/repos/{owner}/{name}/branches/{template_default_branch}/rename"),
+                    Some(&serde_json::json!({ "new_name": settings.default_branch })),
+                )
+                .await?;
+        }
+
+        if !settings.topics.is_empty() {
+            let _response: serde_json::Value = self.client
+                .put(format!("/repos/{owner}/{name}/topics"), Some(&serde_json::json!({ "names": settings.topics })))
+                .await?;
+        }
+
+        if settings.dependabot_alerts {
+            // Note: This call just returns a status with no JSON output.
+            octocrab::map_github_error(
+                self.client._put(format!("/repos/{owner}/{name}/vulnerability-alerts"), None::<&()>).await?,
+            )
+            .await?;
+        }
 
         info!("Github Repo Created: {}", github_params.name);
         let rce = RepositoryCreatedEvent {
@@ -258,6 +418,9 @@ impl GithubRepoHandler {
         Ok(InitializedGithubRepo {
             name: github_params.name.clone(),
             organization: github_params.organization.clone(),
+            host: github_params.host.clone(),
+            default_branch: github_params.settings.default_branch.clone(),
+            settings: Some(github_params.settings.clone()),
         })
     }
 
@@ -270,10 +433,45 @@ impl GithubRepoHandler {
             .current_dir(path)
             .output()?;
 
+        let source_path = format!("{}/{}", path, initialized_github_repo.name);
+        if let Some(settings) = &initialized_github_repo.settings {
+            Self::set_unborn_branch(&source_path, &settings.default_branch)?;
+        }
+
         Ok(InitializedSource{
-            path: format!("{}/{}", path, initialized_github_repo.name),
+            path: source_path,
         })
     }
+
+    /// Points a freshly created, empty repo at its default branch so the first push creates it, which makes
+    /// it the default branch on Github.
+    fn set_unborn_branch(source_path: &str, branch: &str) -> Result<(), SkootError> {
+        let has_commits = Command::new("git")
+            .args(["rev-parse", "--verify", "--quiet", "HEAD"])
+            .current_dir(source_path)
+            .output()?
+            .status
+            .success();
+        if has_commits {
+            return Ok(());
+        }
+
+        for args in [
+            vec!["symbolic-ref".to_string(), "HEAD".to_string(), format!("refs/heads/{branch}")],
+            vec!["config".to_string(), format!("branch.{branch}.remote"), "origin".to_string()],
+            vec!["config".to_string(), format!("branch.{branch}.merge"), format!("refs/heads/{branch}")],
+        ] {
+            let output = Command::new("git").args(&args).current_dir(source_path).output()?;
+            if !output.status.success() {
+                return Err(format!(
+                    "Failed to set the default branch of {source_path}: {}",
+                    String::from_utf8_lossy(&output.stderr)
+                )
+                .into());
+            }
+        }
+        Ok(())
+    }
 }
 
 /// This is needed to easily send over Github new repo parameters to the post.
@@ -283,16 +481,88 @@ struct NewGithubRepoParams {
     name: String,
     description: String,
     private: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visibility: Option<&'static str>,
     has_issues: bool,
     has_projects: bool,
     has_wiki: bool,
 }
 
+/// The parameters for generating a repo from a template repo.
+#[derive(serde::Serialize)]
+struct GenerateGithubRepoParams {
+    owner: String,
+    name: String,
+    description: String,
+    private: bool,
+    include_all_branches: bool,
+}
+
+/// The settings that are updated once the repo exists.
+#[allow(clippy::struct_excessive_bools)] // Clippy doesn't like the Github API
+#[derive(serde::Serialize)]
+struct GithubRepoSettingsBody {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    visibility: Option<&'static str>,
+    allow_merge_commit: bool,
+    allow_squash_merge: bool,
+    allow_rebase_merge: bool,
+    delete_branch_on_merge: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    security_and_analysis: Option<SecurityAndAnalysis>,
+}
+
+/// The security features to enable. Features that aren't enabled are left out, since Github rejects
+/// changing them on repos that don't have Advanced Security.
+#[derive(serde::Serialize)]
+struct SecurityAndAnalysis {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret_scanning: Option<SecurityFeatureStatus>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    secret_scanning_push_protection: Option<SecurityFeatureStatus>,
+}
+
+impl SecurityAndAnalysis {
+    fn new(settings: &GithubRepoSettings) -> Option<Self> {
+        let enabled = |enable: bool| enable.then_some(SecurityFeatureStatus { status: "enabled" });
+        (settings.secret_scanning || settings.secret_scanning_push_protection).then(|| Self {
+            secret_scanning: enabled(settings.secret_scanning),
+            secret_scanning_push_protection: enabled(settings.secret_scanning_push_protection),
+        })
+    }
+}
+
+#[derive(serde::Serialize)]
+struct SecurityFeatureStatus {
+    status: &'static str,
+}
+
+const fn visibility_name(visibility: RepoVisibility) -> &'static str {
+    match visibility {
+        RepoVisibility::Public => "public",
+        RepoVisibility::Private => "private",
+        RepoVisibility::Internal => "internal",
+    }
+}
+
+/// Returns whether an error is the GitHub API reporting that something doesn't exist.
+fn is_not_found(error: &SkootError) -> bool {
+    matches!(
+        error.downcast_ref::<octocrab::Error>(),
+        Some(octocrab::Error::GitHub { source, .. }) if source.message == "Not Found"
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use tempdir::TempDir;
+    use wiremock::{
+        matchers::{body_json, body_partial_json, method, path},
+        Mock, MockServer, ResponseTemplate,
+    };
 
     use super::*;
+    use crate::service::github::{GithubCredentials, StaticCredentialProvider};
 
     // TODO: Mock out, or create test to create a repo/delete a repo
 
@@ -301,6 +571,9 @@ mod tests {
         let initialized_github_repo = InitializedGithubRepo {
             name: "skootrs".to_string(),
             organization: GithubUser::Organization("kusaridev".to_string()),
+            host: "github.com".to_string(),
+            default_branch: "main".to_string(),
+            settings: None,
         };
 
         let temp_dir = TempDir::new("test").unwrap();
@@ -314,4 +587,284 @@ mod tests {
             format!("{}/{}", path, initialized_github_repo.name)
         );
     }
+    #[tokio::test]
+    async fn test_get_rejects_repos_on_other_hosts() {
+        let repo_service = LocalRepoService {
+            github: GithubClient::default().with_host("github.example.com"),
+        };
+
+        let error = repo_service
+            .get(InitializedRepoGetParams {
+                repo_url: "https://github.com/kusaridev/skootrs".to_string(),
+            })
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Unsupported repo host github.com, the GitHub client is configured for github.example.com"
+        );
+    }
+
+    fn repo_params(organization: GithubUser, settings: GithubRepoSettings) -> GithubRepoParams {
+        GithubRepoParams {
+            name: "test".to_string(),
+            description: "foobar".to_string(),
+            organization,
+            host: "github.com".to_string(),
+            settings,
+        }
+    }
+
+    async fn mock_handler(server: &MockServer) -> GithubRepoHandler {
+        GithubRepoHandler {
+            client: Arc::new(
+                Octocrab::builder()
+                    .base_uri(server.uri())
+                    .unwrap()
+                    .build()
+                    .unwrap(),
+            ),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_applies_repo_settings() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/orgs/testorg/repos"))
+            .and(body_partial_json(serde_json::json!({
+                "name": "test",
+                "private": true,
+                "visibility": "internal",
+            })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/repos/testorg/test"))
+            .and(body_json(serde_json::json!({
+                "allow_merge_commit": false,
+                "allow_squash_merge": true,
+                "allow_rebase_merge": true,
+                "delete_branch_on_merge": true,
+                "security_and_analysis": {
+                    "secret_scanning": { "status": "enabled" },
+                },
+            })))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/repos/testorg/test/topics"))
+            .and(body_json(
+                serde_json::json!({ "names": ["security", "rust"] }),
+            ))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/repos/testorg/test/vulnerability-alerts"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let settings = GithubRepoSettings {
+            visibility: RepoVisibility::Internal,
+            topics: vec!["security".to_string(), "rust".to_string()],
+            allow_merge_commit: false,
+            delete_branch_on_merge: true,
+            secret_scanning: true,
+            dependabot_alerts: true,
+            ..GithubRepoSettings::default()
+        };
+        let repo = mock_handler(&server)
+            .await
+            .create(repo_params(
+                GithubUser::Organization("testorg".to_string()),
+                settings.clone(),
+            ))
+            .await
+            .unwrap();
+
+        assert_eq!(repo.settings, Some(settings));
+    }
+
+    #[tokio::test]
+    async fn test_create_from_template_renames_default_branch() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/repos/kusaridev/template/generate"))
+            .and(body_partial_json(serde_json::json!({
+                "owner": "testuser",
+                "name": "test",
+                "private": false,
+            })))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .set_body_json(serde_json::json!({ "default_branch": "master" })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("PATCH"))
+            .and(path("/repos/testuser/test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/repos/testuser/test/branches/master/rename"))
+            .and(body_json(serde_json::json!({ "new_name": "trunk" })))
+            .respond_with(ResponseTemplate::new(201).set_body_json(serde_json::json!({})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let settings = GithubRepoSettings {
+            template: Some("kusaridev/template".to_string()),
+            default_branch: "trunk".to_string(),
+            ..GithubRepoSettings::default()
+        };
+        mock_handler(&server)
+            .await
+            .create(repo_params(
+                GithubUser::User("testuser".to_string()),
+                settings,
+            ))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_create_rejects_internal_user_repos() {
+        let server = MockServer::start().await;
+        let settings = GithubRepoSettings {
+            visibility: RepoVisibility::Internal,
+            ..GithubRepoSettings::default()
+        };
+
+        let error = mock_handler(&server)
+            .await
+            .create(repo_params(
+                GithubUser::User("testuser".to_string()),
+                settings,
+            ))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error.to_string(),
+            "Internal visibility is only available for organization repos"
+        );
+    }
+
+    #[test]
+    fn test_set_unborn_branch() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let path = temp_dir.path().to_str().unwrap();
+        Command::new("git")
+            .arg("init")
+            .current_dir(path)
+            .output()
+            .unwrap();
+
+        GithubRepoHandler::set_unborn_branch(path, "trunk").unwrap();
+
+        let head = Command::new("git")
+            .args(["symbolic-ref", "HEAD"])
+            .current_dir(path)
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&head.stdout).trim(),
+            "refs/heads/trunk"
+        );
+        let merge = Command::new("git")
+            .args(["config", "branch.trunk.merge"])
+            .current_dir(path)
+            .output()
+            .unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&merge.stdout).trim(),
+            "refs/heads/trunk"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_detects_default_branch() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/testuser/test"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "id": 1,
+                "name": "test",
+                "url": "https://api.github.com/repos/testuser/test",
+                "default_branch": "trunk",
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+        let repo_service = LocalRepoService {
+            github: GithubClient::new(StaticCredentialProvider(GithubCredentials::Token(
+                "test-token".to_string(),
+            )))
+            .with_base_url(server.uri()),
+        };
+
+        let repo = repo_service
+            .get(InitializedRepoGetParams {
+                repo_url: "https://github.com/testuser/test".to_string(),
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(repo.default_branch(), "trunk");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_optional_file_content_only_skips_missing_files() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/repos/testuser/test/contents/.skootrs"))
+            .respond_with(
+                ResponseTemplate::new(404).set_body_json(serde_json::json!({ "message": "Not Found" })),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/repos/testuser/test/contents/.skootrs.sig"))
+            .respond_with(ResponseTemplate::new(403).set_body_json(
+                serde_json::json!({ "message": "API rate limit exceeded" }),
+            ))
+            .mount(&server)
+            .await;
+        let repo_service = LocalRepoService {
+            github: GithubClient::new(StaticCredentialProvider(GithubCredentials::Token(
+                "test-token".to_string(),
+            )))
+            .with_base_url(server.uri()),
+        };
+        let repo = InitializedRepo::Github(InitializedGithubRepo {
+            name: "test".to_string(),
+            organization: GithubUser::User("testuser".to_string()),
+            host: "github.com".to_string(),
+            default_branch: "main".to_string(),
+            settings: None,
+        });
+
+        assert!(repo_service
+            .fetch_optional_file_content(&repo, ".skootrs")
+            .await
+            .unwrap()
+            .is_none());
+        assert!(repo_service
+            .fetch_optional_file_content(&repo, ".skootrs.sig")
+            .await
+            .is_err());
+    }
 }
@@ -15,12 +15,18 @@
 
 #![allow(clippy::module_name_repetitions)]
 
-use std::{fs, path::Path, process::Command};
+use std::{
+    error::Error,
+    fmt, fs,
+    path::{Component, Path, PathBuf},
+    process::Command,
+};
 
 use sha2::Digest;
 use tracing::{debug, info};
 
 use skootrs_model::skootrs::{
+    facet::{SourceFile, WriteConflictReason, WriteOutcome, WritePolicy},
     InitializedRepo, InitializedSource, SkootError, SourceInitializeParams,
 };
 
@@ -65,6 +71,24 @@ pub trait SourceService {
         contents: C,
     ) -> Result<(), SkootError>;
 
+    /// Writes a file to the source code directory according to a `WritePolicy` for when the file already
+    /// exists. `recorded` is the source file recorded the last time the file was written, if any, which is
+    /// used to tell whether the existing file was changed since.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be written. Conflicts with the existing file aren't errors and
+    /// are returned as a `WriteOutcome::Conflict`.
+    fn write_file_with_policy<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        source: &InitializedSource,
+        path: P,
+        name: String,
+        contents: C,
+        policy: &WritePolicy,
+        recorded: Option<&SourceFile>,
+    ) -> Result<WriteOutcome, SkootError>;
+
     /// Reads a file from the source code directory.
     ///
     /// # Errors
@@ -89,12 +113,45 @@ pub trait SourceService {
         name: String,
     ) -> Result<String, SkootError>;
 
+    /// Returns whether a file exists in the source code directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path of the file escapes the source code directory.
+    fn file_exists<P: AsRef<Path>>(
+        &self,
+        source: &InitializedSource,
+        path: P,
+        name: &str,
+    ) -> Result<bool, SkootError>;
+
+    /// Removes a file from the source code directory. A file that doesn't exist is left as is.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be removed from the source code directory.
+    fn remove_file<P: AsRef<Path>>(
+        &self,
+        source: &InitializedSource,
+        path: P,
+        name: String,
+    ) -> Result<(), SkootError>;
+
     /// Pulls updates from the remote repo.
     ///
     /// # Errors
     ///
     /// Returns an error if the updates can't be pulled from the remote repo.
     fn pull_updates(&self, source: InitializedSource) -> Result<(), SkootError>;
+
+    /// Creates a branch from the current commit, checks it out, and pushes it to the remote so later
+    /// pushes go to the branch. A branch that already exists on the remote, e.g. from an earlier attempt,
+    /// is replaced as long as it's where it was last fetched from.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the branch can't be created or pushed to the remote.
+    fn create_branch(&self, source: &InitializedSource, branch: &str) -> Result<(), SkootError>;
 }
 
 /// The `LocalSourceService` struct provides an implementation of the `SourceService` trait for initializing
@@ -110,7 +167,7 @@ impl SourceService for LocalSourceService {
         params: SourceInitializeParams,
         initialized_repo: InitializedRepo,
     ) -> Result<InitializedSource, SkootError> {
-        let repo_service = LocalRepoService {};
+        let repo_service = LocalRepoService::default();
         repo_service.clone_local(initialized_repo, params.parent_path)
     }
 
@@ -150,23 +207,80 @@ impl SourceService for LocalSourceService {
         name: String,
         contents: C,
     ) -> Result<(), SkootError> {
-        let full_path = Path::new(&source.path).join(&path);
+        let complete_path = resolve_source_path(&source, &path, &name)?;
+        let full_path = complete_path
+            .parent()
+            .ok_or_else(|| SkootError::from(format!("Invalid file path {complete_path:?}")))?;
         // Ensure path exists
         info!("Creating path {:?}", &full_path);
-        fs::create_dir_all(&full_path)?;
-        let complete_path = full_path.join(name);
-        fs::write(complete_path, contents)?;
+        fs::create_dir_all(full_path)?;
+        fs::write(&complete_path, contents)?;
         debug!("{:?} file written", &full_path);
         Ok(())
     }
 
+    fn write_file_with_policy<P: AsRef<Path>, C: AsRef<[u8]>>(
+        &self,
+        source: &InitializedSource,
+        path: P,
+        name: String,
+        contents: C,
+        policy: &WritePolicy,
+        recorded: Option<&SourceFile>,
+    ) -> Result<WriteOutcome, SkootError> {
+        let full_path = resolve_source_path(source, &path, &name)?;
+        if !full_path.exists() {
+            self.write_file(source.clone(), path, name, contents)?;
+            return Ok(WriteOutcome::Written);
+        }
+
+        let unchanged = || {
+            recorded.is_some_and(|recorded| {
+                self.hash_file(source, &path, name.clone())
+                    .is_ok_and(|hash| hash == recorded.hash)
+            })
+        };
+        match policy {
+            WritePolicy::Overwrite => {
+                self.write_file(source.clone(), path, name, contents)?;
+                Ok(WriteOutcome::Written)
+            }
+            WritePolicy::FailIfExists => {
+                Ok(WriteOutcome::Conflict(WriteConflictReason::AlreadyExists))
+            }
+            WritePolicy::SkipIfExists => Ok(WriteOutcome::Skipped),
+            WritePolicy::OverwriteIfUnchanged | WritePolicy::ThreeWayMerge if unchanged() => {
+                self.write_file(source.clone(), path, name, contents)?;
+                Ok(WriteOutcome::Written)
+            }
+            WritePolicy::OverwriteIfUnchanged => Ok(WriteOutcome::Conflict(
+                WriteConflictReason::ModifiedSinceRecorded,
+            )),
+            WritePolicy::ThreeWayMerge => {
+                let relative_path = relative_path(path.as_ref(), &name);
+                let Some(base) = recorded.and_then(|recorded| {
+                    Self::recorded_content(source, &relative_path, &recorded.hash)
+                }) else {
+                    return Ok(WriteOutcome::Conflict(WriteConflictReason::MissingBase));
+                };
+                match Self::merge(source, &full_path, &base, contents.as_ref())? {
+                    Some(merged) => {
+                        self.write_file(source.clone(), path, name, merged)?;
+                        Ok(WriteOutcome::Merged)
+                    }
+                    None => Ok(WriteOutcome::Conflict(WriteConflictReason::MergeConflict)),
+                }
+            }
+        }
+    }
+
     fn read_file<P: AsRef<Path>>(
         &self,
         source: &InitializedSource,
         path: P,
         name: String,
     ) -> Result<String, SkootError> {
-        let full_path = Path::new(&source.path).join(&path).join(name);
+        let full_path = resolve_source_path(source, &path, &name)?;
         let contents = fs::read_to_string(full_path)?;
         Ok(contents)
     }
@@ -177,7 +291,7 @@ impl SourceService for LocalSourceService {
         path: P,
         name: String,
     ) -> Result<String, SkootError> {
-        let full_path: std::path::PathBuf = Path::new(&source.path).join(&path).join(name);
+        let full_path = resolve_source_path(source, &path, &name)?;
         debug!("Hashing file {:?}", &full_path);
         let mut contents = fs::File::open(&full_path)?;
         let mut hasher = sha2::Sha256::new();
@@ -189,6 +303,29 @@ impl SourceService for LocalSourceService {
         Ok(format!("{hash:x}"))
     }
 
+    fn file_exists<P: AsRef<Path>>(
+        &self,
+        source: &InitializedSource,
+        path: P,
+        name: &str,
+    ) -> Result<bool, SkootError> {
+        Ok(resolve_source_path(source, path, name)?.exists())
+    }
+
+    fn remove_file<P: AsRef<Path>>(
+        &self,
+        source: &InitializedSource,
+        path: P,
+        name: String,
+    ) -> Result<(), SkootError> {
+        let full_path = resolve_source_path(source, &path, &name)?;
+        if full_path.exists() {
+            fs::remove_file(&full_path)?;
+            info!("Removed file {:?}", &full_path);
+        }
+        Ok(())
+    }
+
     fn pull_updates(&self, source: InitializedSource) -> Result<(), SkootError> {
         let _output = Command::new("git")
             .arg("pull")
@@ -197,6 +334,211 @@ impl SourceService for LocalSourceService {
         info!("Pulled updates for {}", source.path);
         Ok(())
     }
+
+    fn create_branch(&self, source: &InitializedSource, branch: &str) -> Result<(), SkootError> {
+        let output = Command::new("git")
+            .arg("checkout")
+            .arg("-B")
+            .arg(branch)
+            .current_dir(&source.path)
+            .output()?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to create branch {branch}: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )
+            .into());
+        }
+
+        // The branch was reset to the current commit, so an existing remote branch can't be fast-forwarded.
+        // The lease keeps commits pushed to it since it was last fetched from being overwritten.
+        let output = Command::new("git")
+            .arg("push")
+            .arg("--force-with-lease")
+            .arg("--set-upstream")
+            .arg("origin")
+            .arg(branch)
+            .current_dir(&source.path)
+            .output()?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to push branch {branch}: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )
+            .into());
+        }
+        info!("Created branch {branch} for {}", source.path);
+        Ok(())
+    }
+}
+
+impl LocalSourceService {
+    /// Finds the content a file had when it was recorded with the given hash by searching the git history
+    /// of the file, most recent first.
+    fn recorded_content(
+        source: &InitializedSource,
+        relative_path: &str,
+        hash: &str,
+    ) -> Option<Vec<u8>> {
+        let output = Command::new("git")
+            .args(["log", "--format=%H", "--", relative_path])
+            .current_dir(&source.path)
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter_map(|commit| {
+                Command::new("git")
+                    .arg("show")
+                    .arg(format!("{commit}:{relative_path}"))
+                    .current_dir(&source.path)
+                    .output()
+                    .ok()
+                    .filter(|output| output.status.success())
+            })
+            .map(|output| output.stdout)
+            .find(|content| format!("{:x}", sha2::Sha256::digest(content)) == hash)
+    }
+
+    /// Merges the changes between `base` and the file at `current_path` into `generated` with
+    /// `git merge-file`. Returns `None` if the changes conflict.
+    fn merge(
+        source: &InitializedSource,
+        current_path: &Path,
+        base: &[u8],
+        generated: &[u8],
+    ) -> Result<Option<Vec<u8>>, SkootError> {
+        // The inputs are kept in the git directory so they never show up as changes in the source.
+        let output = Command::new("git")
+            .args(["rev-parse", "--absolute-git-dir"])
+            .current_dir(&source.path)
+            .output()?;
+        let git_dir = PathBuf::from(String::from_utf8(output.stdout)?.trim());
+        let base_path = git_dir.join("SKOOTRS_MERGE_BASE");
+        let generated_path = git_dir.join("SKOOTRS_MERGE_GENERATED");
+        fs::write(&base_path, base)?;
+        fs::write(&generated_path, generated)?;
+
+        let output = Command::new("git")
+            .args([
+                "merge-file",
+                "-p",
+                "-L",
+                "current",
+                "-L",
+                "base",
+                "-L",
+                "generated",
+            ])
+            .arg(current_path)
+            .arg(&base_path)
+            .arg(&generated_path)
+            .current_dir(&source.path)
+            .output()?;
+        fs::remove_file(&base_path)?;
+        fs::remove_file(&generated_path)?;
+
+        match output.status.code() {
+            Some(0) => Ok(Some(output.stdout)),
+            Some(code) if code > 0 && code < 128 => {
+                debug!("{code} conflicts merging {current_path:?}");
+                Ok(None)
+            }
+            _ => Err(format!(
+                "Failed to merge {current_path:?}: {}",
+                String::from_utf8_lossy(&output.stderr)
+            )
+            .into()),
+        }
+    }
+}
+
+/// Resolves the path of a file within a source directory, making sure it can't escape the directory. The
+/// `path` and `name` can come from untrusted input like a `.skootrs` file fetched from a repo, so absolute
+/// paths, `..` components that climb out of the source, and symlinks that point outside of it are rejected.
+///
+/// # Errors
+///
+/// Returns a `SourcePathError` if the path would escape the source directory.
+pub fn resolve_source_path<P: AsRef<Path>>(
+    source: &InitializedSource,
+    path: P,
+    name: &str,
+) -> Result<PathBuf, SourcePathError> {
+    let requested = path.as_ref().join(name);
+    let display = requested.display().to_string();
+    let root = fs::canonicalize(&source.path)
+        .map_err(|e| SourcePathError::InvalidSource(format!("{}: {e}", source.path)))?;
+
+    let mut relative = PathBuf::new();
+    for component in requested.components() {
+        match component {
+            Component::CurDir => {}
+            Component::Normal(part) => relative.push(part),
+            Component::ParentDir => {
+                if !relative.pop() {
+                    return Err(SourcePathError::Escapes(display));
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => {
+                return Err(SourcePathError::Absolute(display));
+            }
+        }
+    }
+    if relative.as_os_str().is_empty() {
+        return Err(SourcePathError::Escapes(display));
+    }
+
+    // The part of the path that already exists might go through symlinks, so it's resolved to make
+    // sure it's still inside of the source. The rest of the path can't contain `..` at this point.
+    let resolved = root.join(&relative);
+    let mut existing = resolved.as_path();
+    while fs::symlink_metadata(existing).is_err() {
+        existing = existing
+            .parent()
+            .ok_or_else(|| SourcePathError::Escapes(display.clone()))?;
+    }
+    let canonical_existing =
+        fs::canonicalize(existing).map_err(|_| SourcePathError::Escapes(display.clone()))?;
+    if !canonical_existing.starts_with(&root) {
+        return Err(SourcePathError::Escapes(display));
+    }
+
+    Ok(resolved)
+}
+
+/// The reasons a path in a source directory can be rejected.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SourcePathError {
+    /// The path is absolute.
+    Absolute(String),
+    /// The path escapes the source directory, e.g. through `..` or a symlink.
+    Escapes(String),
+    /// The source directory itself can't be resolved.
+    InvalidSource(String),
+}
+
+impl fmt::Display for SourcePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Absolute(path) => write!(f, "Path {path} must be relative to the source"),
+            Self::Escapes(path) => write!(f, "Path {path} escapes the source"),
+            Self::InvalidSource(e) => write!(f, "Invalid source directory {e}"),
+        }
+    }
+}
+
+impl Error for SourcePathError {}
+
+/// Returns the path of a file relative to the root of the source, e.g. `./` and `README.md` become
+/// `README.md`.
+fn relative_path(path: &Path, name: &str) -> String {
+    let full_path = path.join(name);
+    full_path
+        .strip_prefix("./")
+        .unwrap_or(&full_path)
+        .display()
+        .to_string()
 }
 
 #[cfg(test)]
@@ -217,6 +559,9 @@ mod tests {
         let initialized_repo = InitializedRepo::Github(InitializedGithubRepo {
             name: "skootrs".to_string(),
             organization: GithubUser::Organization("kusaridev".to_string()),
+            host: "github.com".to_string(),
+            default_branch: "main".to_string(),
+            settings: None,
         });
         let result = source_service.initialize(params, initialized_repo);
         assert!(result.is_ok());
@@ -267,4 +612,350 @@ mod tests {
             .unwrap();
         assert_eq!(file_contents, "File contents");
     }
+
+    #[test]
+    fn test_remove_file() {
+        let source_service = LocalSourceService {};
+        let temp_dir = TempDir::new("test").unwrap();
+        let initialized_source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let path = "subdirectory";
+        let name = "file.txt".to_string();
+        source_service
+            .write_file(
+                initialized_source.clone(),
+                path,
+                name.clone(),
+                "File contents",
+            )
+            .unwrap();
+        source_service
+            .remove_file(&initialized_source, path, name.clone())
+            .unwrap();
+        assert!(!temp_dir.path().join(path).join(&name).exists());
+        // Removing a file that's already gone is fine.
+        assert!(source_service
+            .remove_file(&initialized_source, path, name)
+            .is_ok());
+        assert!(source_service
+            .remove_file(&initialized_source, "..", "file.txt".to_string())
+            .is_err());
+    }
+
+    #[test]
+    fn test_file_exists() {
+        let source_service = LocalSourceService {};
+        let temp_dir = TempDir::new("test").unwrap();
+        let initialized_source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        source_service
+            .write_file(
+                initialized_source.clone(),
+                "./",
+                "file.txt".to_string(),
+                "File contents",
+            )
+            .unwrap();
+        assert!(source_service
+            .file_exists(&initialized_source, "./", "file.txt")
+            .unwrap());
+        assert!(!source_service
+            .file_exists(&initialized_source, "./", "missing.txt")
+            .unwrap());
+        // Paths that escape the source are an error rather than a missing file.
+        assert!(source_service
+            .file_exists(&initialized_source, "..", "file.txt")
+            .is_err());
+    }
+
+    fn git(dir: &Path, args: &[&str]) -> String {
+        let output = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(dir)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "git {args:?} failed");
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Creates a git repo with a README that was written and recorded by Skootrs.
+    fn recorded_readme(temp_dir: &TempDir, content: &str) -> (InitializedSource, SourceFile) {
+        git(temp_dir.path(), &["init"]);
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let source_service = LocalSourceService {};
+        source_service
+            .write_file(source.clone(), "./", "README.md".to_string(), content)
+            .unwrap();
+        git(temp_dir.path(), &["add", "."]);
+        git(temp_dir.path(), &["commit", "-m", "Initialized project"]);
+        let recorded = SourceFile {
+            name: "README.md".to_string(),
+            path: "./".to_string(),
+            hash: source_service
+                .hash_file(&source, "./", "README.md".to_string())
+                .unwrap(),
+        };
+        (source, recorded)
+    }
+
+    #[test]
+    fn test_write_file_with_policy_existing_file() {
+        let source_service = LocalSourceService {};
+        let temp_dir = TempDir::new("test").unwrap();
+        let (source, recorded) = recorded_readme(&temp_dir, "generated\n");
+        fs::write(temp_dir.path().join("README.md"), "hand written\n").unwrap();
+
+        let write = |policy: WritePolicy| {
+            source_service
+                .write_file_with_policy(
+                    &source,
+                    "./",
+                    "README.md".to_string(),
+                    "regenerated\n",
+                    &policy,
+                    Some(&recorded),
+                )
+                .unwrap()
+        };
+
+        assert_eq!(
+            write(WritePolicy::FailIfExists),
+            WriteOutcome::Conflict(WriteConflictReason::AlreadyExists)
+        );
+        assert_eq!(write(WritePolicy::SkipIfExists), WriteOutcome::Skipped);
+        assert_eq!(
+            write(WritePolicy::OverwriteIfUnchanged),
+            WriteOutcome::Conflict(WriteConflictReason::ModifiedSinceRecorded)
+        );
+        assert_eq!(
+            write(WritePolicy::ThreeWayMerge),
+            WriteOutcome::Conflict(WriteConflictReason::MergeConflict)
+        );
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("README.md")).unwrap(),
+            "hand written\n"
+        );
+        assert_eq!(write(WritePolicy::Overwrite), WriteOutcome::Written);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("README.md")).unwrap(),
+            "regenerated\n"
+        );
+    }
+
+    #[test]
+    fn test_write_file_with_policy_unchanged_file() {
+        let source_service = LocalSourceService {};
+        let temp_dir = TempDir::new("test").unwrap();
+        let (source, recorded) = recorded_readme(&temp_dir, "generated\n");
+
+        let outcome = source_service
+            .write_file_with_policy(
+                &source,
+                "./",
+                "README.md".to_string(),
+                "regenerated\n",
+                &WritePolicy::OverwriteIfUnchanged,
+                Some(&recorded),
+            )
+            .unwrap();
+
+        assert_eq!(outcome, WriteOutcome::Written);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("README.md")).unwrap(),
+            "regenerated\n"
+        );
+    }
+
+    #[test]
+    fn test_write_file_with_policy_three_way_merge() {
+        let source_service = LocalSourceService {};
+        let temp_dir = TempDir::new("test").unwrap();
+        let (source, recorded) = recorded_readme(&temp_dir, "one\ntwo\nthree\nfour\nfive\n");
+        // A later commit that isn't the recorded one, so the base has to be found in the history.
+        fs::write(
+            temp_dir.path().join("README.md"),
+            "ONE\ntwo\nthree\nfour\nfive\n",
+        )
+        .unwrap();
+        git(temp_dir.path(), &["commit", "-am", "Hand edit"]);
+
+        let outcome = source_service
+            .write_file_with_policy(
+                &source,
+                "./",
+                "README.md".to_string(),
+                "one\ntwo\nthree\nfour\nFIVE\n",
+                &WritePolicy::ThreeWayMerge,
+                Some(&recorded),
+            )
+            .unwrap();
+
+        assert_eq!(outcome, WriteOutcome::Merged);
+        assert_eq!(
+            fs::read_to_string(temp_dir.path().join("README.md")).unwrap(),
+            "ONE\ntwo\nthree\nfour\nFIVE\n"
+        );
+        assert!(git(temp_dir.path(), &["status", "--porcelain"])
+            .lines()
+            .all(|line| line.ends_with("README.md")));
+    }
+
+    #[test]
+    fn test_write_file_with_policy_missing_base() {
+        let source_service = LocalSourceService {};
+        let temp_dir = TempDir::new("test").unwrap();
+        let (source, mut recorded) = recorded_readme(&temp_dir, "generated\n");
+        fs::write(temp_dir.path().join("README.md"), "hand written\n").unwrap();
+        recorded.hash = "unknown".to_string();
+
+        let outcome = source_service
+            .write_file_with_policy(
+                &source,
+                "./",
+                "README.md".to_string(),
+                "regenerated\n",
+                &WritePolicy::ThreeWayMerge,
+                Some(&recorded),
+            )
+            .unwrap();
+
+        assert_eq!(
+            outcome,
+            WriteOutcome::Conflict(WriteConflictReason::MissingBase)
+        );
+    }
+
+    #[test]
+    fn test_create_branch() {
+        let source_service = LocalSourceService {};
+        let temp_dir = TempDir::new("test").unwrap();
+        let remote = temp_dir.path().join("remote.git");
+        git(
+            temp_dir.path(),
+            &["init", "--bare", remote.to_str().unwrap()],
+        );
+        git(
+            temp_dir.path(),
+            &["clone", remote.to_str().unwrap(), "local"],
+        );
+        let local = temp_dir.path().join("local");
+        git(&local, &["commit", "--allow-empty", "-m", "initial"]);
+        let initialized_source = InitializedSource {
+            path: local.to_str().unwrap().to_string(),
+        };
+
+        source_service
+            .create_branch(&initialized_source, "skootrs/adopt")
+            .unwrap();
+
+        assert_eq!(
+            git(&local, &["branch", "--show-current"]).trim(),
+            "skootrs/adopt"
+        );
+        assert!(git(&remote, &["branch", "--list", "skootrs/adopt"]).contains("skootrs/adopt"));
+
+        // Creating the branch again replaces what an earlier attempt pushed to it.
+        git(
+            &local,
+            &["commit", "--allow-empty", "-m", "earlier attempt"],
+        );
+        git(&local, &["push"]);
+        git(&local, &["checkout", "-"]);
+        source_service
+            .create_branch(&initialized_source, "skootrs/adopt")
+            .unwrap();
+
+        assert_eq!(
+            git(&remote, &["rev-parse", "skootrs/adopt"]),
+            git(&local, &["rev-parse", "HEAD"])
+        );
+    }
+
+    #[test]
+    fn test_resolve_source_path() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source_dir = temp_dir.path().join("source");
+        fs::create_dir(&source_dir).unwrap();
+        let initialized_source = InitializedSource {
+            path: source_dir.to_str().unwrap().to_string(),
+        };
+
+        let resolved =
+            resolve_source_path(&initialized_source, ".github/workflows", "ci.yml").unwrap();
+        assert_eq!(
+            resolved,
+            fs::canonicalize(&source_dir)
+                .unwrap()
+                .join(".github/workflows/ci.yml")
+        );
+        assert!(resolve_source_path(&initialized_source, "docs/../", "README.md").is_ok());
+        assert!(matches!(
+            resolve_source_path(&initialized_source, "../", "outside.txt"),
+            Err(SourcePathError::Escapes(_))
+        ));
+        assert!(matches!(
+            resolve_source_path(&initialized_source, "docs/../../", "outside.txt"),
+            Err(SourcePathError::Escapes(_))
+        ));
+        assert!(matches!(
+            resolve_source_path(&initialized_source, "/etc", "passwd"),
+            Err(SourcePathError::Absolute(_))
+        ));
+    }
+
+    #[test]
+    fn test_resolve_source_path_rejects_symlink_escape() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source_dir = temp_dir.path().join("source");
+        let outside_dir = temp_dir.path().join("outside");
+        fs::create_dir(&source_dir).unwrap();
+        fs::create_dir(&outside_dir).unwrap();
+        std::os::unix::fs::symlink(&outside_dir, source_dir.join("link")).unwrap();
+        let initialized_source = InitializedSource {
+            path: source_dir.to_str().unwrap().to_string(),
+        };
+
+        assert!(matches!(
+            resolve_source_path(&initialized_source, "link", "file.txt"),
+            Err(SourcePathError::Escapes(_))
+        ));
+        assert!(matches!(
+            resolve_source_path(&initialized_source, "./", "link"),
+            Err(SourcePathError::Escapes(_))
+        ));
+    }
+
+    #[test]
+    fn test_file_operations_reject_traversal() {
+        let source_service = LocalSourceService {};
+        let temp_dir = TempDir::new("test").unwrap();
+        let source_dir = temp_dir.path().join("source");
+        fs::create_dir(&source_dir).unwrap();
+        let outside = temp_dir.path().join("outside.txt");
+        fs::write(&outside, "keep me").unwrap();
+        let initialized_source = InitializedSource {
+            path: source_dir.to_str().unwrap().to_string(),
+        };
+
+        assert!(source_service
+            .write_file(
+                initialized_source.clone(),
+                "../",
+                "outside.txt".to_string(),
+                "overwritten"
+            )
+            .is_err());
+        assert!(source_service
+            .read_file(&initialized_source, "../", "outside.txt".to_string())
+            .is_err());
+        assert!(source_service
+            .hash_file(&initialized_source, "../", "outside.txt".to_string())
+            .is_err());
+        assert_eq!(fs::read_to_string(outside).unwrap(), "keep me");
+    }
 }
@@ -22,6 +22,7 @@
 
 use std::{collections::HashMap, fmt};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use strum::VariantNames;
 #[cfg(feature = "openapi")]
@@ -31,12 +32,12 @@ use super::{
     label::{Label, Labeled},
     InitializedEcosystem, InitializedRepo, InitializedSource,
 };
-use strum::EnumString;
+use strum::{Display, EnumString};
 
 /// Represents a facet that has been initialized. This is an enum of
 /// the various supported facets like API based, and Source file bundle
 /// based.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub enum InitializedFacet {
     /// A facet that is based on a bundle of source files.
@@ -76,6 +77,31 @@ pub enum FacetCreateParams {
     APIBundle(APIBundleFacetParams),
 }
 
+impl FacetCreateParams {
+    /// Helper function to get the type of the facet the params are for.
+    #[must_use]
+    pub fn facet_type(&self) -> SupportedFacetType {
+        match self {
+            Self::SourceBundle(params) => params.facet_type.clone(),
+            Self::APIBundle(params) => params.facet_type.clone(),
+        }
+    }
+}
+
+/// The action needed to bring a facet of a project in line with the facets the project should have.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, Display)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum FacetAction {
+    /// The facet doesn't exist yet and is created.
+    Create,
+    /// The facet exists and is applied again.
+    Update,
+    /// The facet exists but the project shouldn't have it anymore, so it's removed.
+    Remove,
+    /// The facet is already up to date.
+    Noop,
+}
+
 /// This is required to create an ordering of what facets get applied.
 /// There could be issues like a security feature being enabled before
 /// some other feature, which could lead to it being blocked.
@@ -88,6 +114,20 @@ pub struct FacetSetCreateParams {
     pub facets_params: Vec<FacetCreateParams>,
 }
 
+/// Represents the parameters for removing a facet from a project.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct FacetRemoveParams {
+    /// The common parameters for the facet being removed.
+    pub common: CommonFacetCreateParams,
+    /// The facet being removed, as it was recorded when it was last applied.
+    pub facet: InitializedFacet,
+    /// Whether the files of the facet that were modified since they were recorded are removed anyway.
+    /// Otherwise the facet isn't removed if any of them were.
+    #[serde(default)]
+    pub force: bool,
+}
+
 /// Represents the common parameters that are shared across all facets.
 /// This is mostly the context of the project, like the project name,
 /// source, repo, and ecosystem.
@@ -157,19 +197,27 @@ impl From<SourceFile> for String {
 /// on a bundle of source files. This can be a single file like a
 /// README, or a collection of related files like several yaml files
 /// for a set of actions in a github workflow.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct SourceBundleFacet {
     /// The source files that make up the facet.
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<Vec<String>>")]
     pub source_files: Option<Vec<SourceFile>>,
     /// The type of facet this is.
     pub facet_type: SupportedFacetType,
     /// The content of the source files that make up the facet. This is a map of the source file to the content of the source file.
     #[serde(skip_serializing_if = "Option::is_none")]
+    #[schemars(with = "Option<HashMap<String, String>>")]
     pub source_files_content: Option<HashMap<SourceFile, String>>,
     /// The labels for the facet.
     pub labels: Vec<Label>,
+    /// The files of the facet that couldn't be written because of a conflict with the existing file.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub conflicts: Vec<SourceFileConflict>,
+    /// The configuration the facet was created with, for facets that need one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<FacetConfig>,
 }
 
 /// Represents the parameters for creating a source bundle facet.
@@ -182,11 +230,241 @@ pub struct SourceBundleFacetCreateParams {
     pub facet_type: SupportedFacetType,
     /// The labels for the facet.
     pub labels: Vec<Label>,
+    /// The policy for writing the facet's files when they already exist in the source.
+    #[serde(default)]
+    pub write_policy: WritePolicy,
+    /// The source files recorded for the facet the last time it was written, if any.
+    #[serde(default)]
+    pub recorded_source_files: Vec<SourceFile>,
+    /// All the facets enabled for the project, for facets whose content depends on the other facets.
+    #[serde(default)]
+    pub enabled_facets: Vec<SupportedFacetType>,
+    /// The configuration for the facet, for facets that need one.
+    #[serde(default)]
+    pub config: Option<FacetConfig>,
+}
+
+/// The configuration for facets that need more than the common parameters to be created. This is set per
+/// project and recorded with the facet so it's reused when the facet is updated.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum FacetConfig {
+    /// The configuration for the `GUACForwardingConfig` facet.
+    GUACForwarding(GUACForwardingConfig),
+    /// The configuration for the `CodeReview` facet and its `CodeOwners` file.
+    CodeReview(CodeReviewConfig),
+    /// The configuration for the `BranchProtection` facet. The default protection is used without it.
+    BranchProtection(BranchProtectionParams),
+    /// Enables the `PublishPackages` facet. It's opt-in since publishing needs registry credentials, or for
+    /// Maven a `release` profile, that a new project doesn't have.
+    PublishPackages,
+}
+
+impl FacetConfig {
+    /// Returns the type of the source bundle facet the configuration is for, if it has one. The
+    /// configuration is recorded with this facet.
+    #[must_use]
+    pub const fn facet_type(&self) -> Option<SupportedFacetType> {
+        match self {
+            Self::GUACForwarding(_) => Some(SupportedFacetType::GUACForwardingConfig),
+            Self::CodeReview(_) => Some(SupportedFacetType::CodeOwners),
+            Self::PublishPackages => Some(SupportedFacetType::PublishPackages),
+            Self::BranchProtection(_) => None,
+        }
+    }
+
+    /// Returns the type of the API bundle facet the configuration is for, if it has one. Configurations
+    /// without a source bundle facet are recorded with this facet.
+    #[must_use]
+    pub const fn api_facet_type(&self) -> Option<SupportedFacetType> {
+        match self {
+            Self::GUACForwarding(_) | Self::PublishPackages => None,
+            Self::CodeReview(_) => Some(SupportedFacetType::CodeReview),
+            Self::BranchProtection(_) => Some(SupportedFacetType::BranchProtection),
+        }
+    }
+}
+
+/// The configuration for requiring code review on the project's pull requests.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct CodeReviewConfig {
+    /// The number of approving reviews required to merge a pull request.
+    #[serde(default = "CodeReviewConfig::default_required_approving_review_count")]
+    pub required_approving_review_count: u8,
+    /// Whether approvals are dismissed when new commits are pushed.
+    #[serde(default)]
+    pub dismiss_stale_reviews: bool,
+    /// The owners of the project's code, e.g. `@org/team` or `@user`. They're written to the CODEOWNERS file
+    /// and a review from one of them is required when there are any.
+    #[serde(default)]
+    pub code_owners: Vec<String>,
+}
+
+impl CodeReviewConfig {
+    const fn default_required_approving_review_count() -> u8 {
+        1
+    }
+
+    /// Whether a review from a code owner is required, which is only the case when there are code owners.
+    #[must_use]
+    pub const fn require_code_owner_reviews(&self) -> bool {
+        !self.code_owners.is_empty()
+    }
+}
+
+/// The parameters for protecting a project's branches.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct BranchProtectionParams {
+    /// The branch to protect. Rulesets also accept a pattern, e.g. `release/*`. Defaults to the repo's
+    /// default branch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub branch: Option<String>,
+    /// How the protection is applied to the repo.
+    #[serde(default)]
+    pub mode: BranchProtectionMode,
+    /// The status checks required to pass before merging, on top of the ones derived from the pull
+    /// request workflows generated for the project.
+    #[serde(default)]
+    pub required_status_checks: Vec<String>,
+    /// Whether commits have to be signed.
+    #[serde(default)]
+    pub require_signed_commits: bool,
+    /// Whether merge commits are prevented.
+    #[serde(default = "BranchProtectionParams::default_true")]
+    pub require_linear_history: bool,
+    /// Whether the protection also applies to the repo's admins.
+    #[serde(default = "BranchProtectionParams::default_true")]
+    pub enforce_admins: bool,
+    /// The reviews required by the `CodeReview` facet. These are set from the `CodeReview` configuration
+    /// and applied in the same request as the rest of the protection, so the two can't overwrite each
+    /// other.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_reviews: Option<CodeReviewConfig>,
+}
+
+impl BranchProtectionParams {
+    const fn default_true() -> bool {
+        true
+    }
+}
+
+impl Default for BranchProtectionParams {
+    fn default() -> Self {
+        Self {
+            branch: None,
+            mode: BranchProtectionMode::default(),
+            required_status_checks: vec![],
+            require_signed_commits: false,
+            require_linear_history: true,
+            enforce_admins: true,
+            required_reviews: None,
+        }
+    }
+}
+
+/// How branch protection is applied to a repo.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum BranchProtectionMode {
+    /// Classic branch protection rules, which only protect a single branch.
+    #[default]
+    Classic,
+    /// A repository ruleset, which can protect branches matching a pattern.
+    Ruleset,
+}
+
+/// The configuration for forwarding a project's release SBOMs and SLSA attestations to GUAC.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct GUACForwardingConfig {
+    /// Where the documents are forwarded to.
+    pub endpoint: GUACEndpoint,
+}
+
+/// The GUAC endpoints release documents can be forwarded to.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum GUACEndpoint {
+    /// Ingest the documents with `guacone` against a GUAC deployment's collectsub service.
+    CollectSub {
+        /// The address of the collectsub service, e.g. `guac-collectsub.example.com:2782`.
+        csub_address: String,
+        /// The URL of GUAC's GraphQL API, e.g. `https://guac.example.com/query`.
+        graphql_address: String,
+    },
+    /// Upload the documents to an S3 compatible bucket that GUAC's blob collector watches.
+    Blob {
+        /// The bucket and prefix to upload to, e.g. `s3://guac-documents/my-project`.
+        bucket_url: String,
+        /// The endpoint of an S3 compatible service that isn't AWS, e.g. `https://minio.example.com`.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        endpoint_url: Option<String>,
+    },
+}
+
+/// The policy for writing a generated file when a file already exists at the same path in the source.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum WritePolicy {
+    /// Always overwrite the existing file.
+    #[default]
+    Overwrite,
+    /// Don't write the file and report a conflict.
+    FailIfExists,
+    /// Don't write the file and leave the existing file as is.
+    SkipIfExists,
+    /// Overwrite the existing file only if it's unchanged since it was last written, i.e. its hash matches
+    /// the recorded hash. Otherwise report a conflict.
+    OverwriteIfUnchanged,
+    /// Merge the changes made to the existing file since it was last written into the newly generated
+    /// content, using the previously generated content as the base. Report a conflict if the changes
+    /// can't be merged cleanly.
+    ThreeWayMerge,
+}
+
+/// The result of writing a generated file to the source.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum WriteOutcome {
+    /// The file was written with the generated content.
+    Written,
+    /// The existing file was left as is.
+    Skipped,
+    /// The changes to the existing file were merged into the generated content.
+    Merged,
+    /// The file wasn't written because of a conflict with the existing file.
+    Conflict(WriteConflictReason),
+}
+
+/// The reason a generated file conflicts with the existing file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum WriteConflictReason {
+    /// A file already exists at the path.
+    AlreadyExists,
+    /// The existing file was modified since it was last written.
+    ModifiedSinceRecorded,
+    /// The previously generated content to merge against can't be found.
+    MissingBase,
+    /// The changes to the existing file conflict with the generated content.
+    MergeConflict,
+}
+
+/// A file of a facet that couldn't be written because of a conflict with the existing file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct SourceFileConflict {
+    /// The path of the conflicting file, relative to the root of the source.
+    pub path: String,
+    /// Why the file conflicts.
+    pub reason: WriteConflictReason,
 }
 
 /// Represents the content of an API call. This just includes the
 /// name of the API call, the URL to the API call and the response.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct APIContent {
     /// The name of the API call.
@@ -208,7 +486,7 @@ pub struct APIContent {
 /// related API calls that represent a single facet. For example, a
 /// facet might expect multiple security flags on a GitHub project
 /// to be set that can't be included as one API call.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct APIBundleFacet {
     /// The API calls that make up the facet.
@@ -217,6 +495,10 @@ pub struct APIBundleFacet {
     pub facet_type: SupportedFacetType,
     /// The labels for the facet.
     pub labels: Vec<Label>,
+    /// The configuration the facet was created with, if it needs one that isn't recorded with a
+    /// source bundle facet.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<FacetConfig>,
 }
 
 /// Represents the parameters for creating an API bundle facet.
@@ -227,6 +509,9 @@ pub struct APIBundleFacetParams {
     pub common: CommonFacetCreateParams,
     /// The type of facet that is being created.
     pub facet_type: SupportedFacetType,
+    /// The configuration for the facet, for facets that need one.
+    #[serde(default)]
+    pub config: Option<FacetConfig>,
 }
 
 impl Labeled for SourceBundleFacet {
@@ -245,7 +530,17 @@ impl Labeled for APIBundleFacet {
 /// various supported facets like README, SECURITY.md, as well as
 /// API calls like enabling branch protection on GitHub.
 #[derive(
-    Serialize, Deserialize, Clone, Debug, PartialEq, Eq, VariantNames, EnumString, Hash, Default,
+    Serialize,
+    Deserialize,
+    Clone,
+    Debug,
+    PartialEq,
+    Eq,
+    VariantNames,
+    EnumString,
+    Hash,
+    Default,
+    JsonSchema,
 )]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub enum SupportedFacetType {
@@ -276,6 +571,9 @@ pub enum SupportedFacetType {
     /// A facet type showing that code review is enabled on the project.
     CodeReview,
 
+    /// A facet type for the project's CODEOWNERS file, which goes along with the `CodeReview` facet.
+    CodeOwners,
+
     /// A facet type showing that a tool for updating dependencies has been enabled on the project.
     DependencyUpdateTool,
 
@@ -20,6 +20,7 @@
 
 #![allow(clippy::module_name_repetitions)]
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString, VariantNames};
 use utoipa::ToSchema;
@@ -28,7 +29,7 @@ use utoipa::ToSchema;
 /// This is used to provide mechanism for mapping stuff like controls to elements
 /// of the project. This makes it easier to audit the project against some set of Security
 /// requirements.
-#[derive(Serialize, Deserialize, Clone, Debug, EnumString, VariantNames, Display)]
+#[derive(Serialize, Deserialize, Clone, Debug, EnumString, VariantNames, Display, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub enum Label {
     /// S2C2F Requirement SCA-1
@@ -43,6 +44,9 @@ pub enum Label {
     /// S2C2F Requirement AUD-3
     /// Validate SBOMs of OSS that you consume into your build
     S2C2FAUD4,
+    /// S2C2F Requirement INV-1
+    /// Maintain an automated inventory of all OSS used in development
+    S2C2FINV1,
 
     /// SLSA Build Level 1
     SLSABuildLevel1,
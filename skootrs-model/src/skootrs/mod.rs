@@ -15,17 +15,20 @@
 
 pub mod facet;
 pub mod label;
+pub mod state;
 
 use std::{collections::HashMap, error::Error, fmt, str::FromStr};
 
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use strum::{Display, EnumString, VariantNames};
 use url::Host;
 use utoipa::ToSchema;
 
 use self::{
-    facet::{InitializedFacet, SupportedFacetType},
+    facet::{FacetConfig, InitializedFacet, SupportedFacetType, WritePolicy},
     label::Label,
+    state::StateSigningConfig,
 };
 
 /// A helper type for the error type used throughout Skootrs. This is a `Box<dyn Error + Send + Sync>`.
@@ -60,7 +63,7 @@ pub enum SupportedEcosystems {
 
 /// Represents a project that has been initialized. This is the data and state of a project that has been
 /// created.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct InitializedProject {
     /// The metadata associated with an Skootrs initilialized source repository.
@@ -74,6 +77,25 @@ pub struct InitializedProject {
     // TODO: What to do if there are name collisions?
     /// The name of the project.
     pub name: String,
+    /// The default facets that are left out of the project, so they aren't applied again when it's updated.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_facets: Vec<SupportedFacetType>,
+}
+
+impl InitializedProject {
+    /// Returns the configurations the project's optional facets were created with.
+    #[must_use]
+    pub fn facet_configs(&self) -> Vec<FacetConfig> {
+        self.facets
+            .values()
+            .filter_map(|facet| match facet {
+                InitializedFacet::SourceBundle(source_bundle_facet) => {
+                    source_bundle_facet.config.clone()
+                }
+                InitializedFacet::APIBundle(api_bundle_facet) => api_bundle_facet.config.clone(),
+            })
+            .collect()
+    }
 }
 
 /// A helper enum for how a facet can be pulled from a `HashMap`
@@ -149,6 +171,84 @@ pub struct ProjectCreateParams {
     pub ecosystem_params: EcosystemInitializeParams,
     /// The parameters for initializing the source code for the project.
     pub source_params: SourceInitializeParams,
+    /// The configuration for optional facets. Facets that need a configuration, like forwarding to GUAC,
+    /// are only enabled when their configuration is provided.
+    #[serde(default)]
+    pub facet_configs: Vec<FacetConfig>,
+    /// The default facets to leave out of the project.
+    #[serde(default)]
+    pub excluded_facets: Vec<SupportedFacetType>,
+}
+
+/// The progress of creating a project. It's recorded after each step of the creation so a creation that
+/// failed part way through can be resumed from the step that failed, or rolled back.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+// Each of the bools records whether a part of the creation has happened yet.
+#[allow(clippy::struct_excessive_bools)]
+pub struct ProjectCreateProgress {
+    /// The parameters the project is being created with.
+    pub params: ProjectCreateParams,
+    /// The repository, once it's created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repo: Option<InitializedRepo>,
+    /// The local clone of the repository, once it's cloned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<InitializedSource>,
+    /// Whether the local clone was made by this creation rather than being a directory that was already
+    /// there. Only a clone made by this creation is removed when the creation is rolled back.
+    #[serde(default)]
+    pub source_created: bool,
+    /// The ecosystem, once it's initialized.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ecosystem: Option<InitializedEcosystem>,
+    /// The source bundle facets, once their files are written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_facets: Option<Vec<InitializedFacet>>,
+    /// Whether the initial commit has been pushed.
+    #[serde(default)]
+    pub pushed: bool,
+    /// The API bundle facets, once their API calls are made.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub api_facets: Option<Vec<InitializedFacet>>,
+    /// Whether the project state has been written to the repo.
+    #[serde(default)]
+    pub state_written: bool,
+    /// Whether the project has been added to the local project reference cache.
+    #[serde(default)]
+    pub cached: bool,
+}
+
+impl ProjectCreateProgress {
+    /// Returns the progress of a creation that hasn't started yet.
+    #[must_use]
+    pub const fn new(params: ProjectCreateParams) -> Self {
+        Self {
+            params,
+            repo: None,
+            source: None,
+            source_created: false,
+            ecosystem: None,
+            source_facets: None,
+            pushed: false,
+            api_facets: None,
+            state_written: false,
+            cached: false,
+        }
+    }
+}
+
+/// How a project creation that failed is rolled back.
+#[derive(
+    Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Display, EnumString, VariantNames,
+)]
+#[strum(ascii_case_insensitive)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum RollbackStrategy {
+    /// Delete the repository.
+    Delete,
+    /// Archive the repository, so it's kept read-only.
+    Archive,
 }
 
 /// The parameters for updating a project.
@@ -157,6 +257,24 @@ pub struct ProjectCreateParams {
 pub struct ProjectUpdateParams {
     /// The initialized project to update.
     pub initialized_project: InitializedProject,
+    /// The policy for writing facet files that already exist in the project. Defaults to a three-way merge
+    /// so changes made to the files since they were last written aren't lost.
+    #[serde(default = "ProjectUpdateParams::default_write_policy")]
+    pub write_policy: WritePolicy,
+    /// The configuration for the project's optional facets. Facets whose configuration is left out are
+    /// removed. Defaults to the configurations the facets were created with.
+    #[serde(default)]
+    pub facet_configs: Option<Vec<FacetConfig>>,
+    /// The default facets to leave out of the project. Facets that are left out are removed. Defaults to
+    /// the facets the project already leaves out.
+    #[serde(default)]
+    pub excluded_facets: Option<Vec<SupportedFacetType>>,
+}
+
+impl ProjectUpdateParams {
+    const fn default_write_policy() -> WritePolicy {
+        WritePolicy::ThreeWayMerge
+    }
 }
 
 /// The parameters for getting an existing Skootrs project.
@@ -167,6 +285,77 @@ pub struct ProjectGetParams {
     pub project_url: String,
 }
 
+/// The parameters for adopting an existing repository as a Skootrs project.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectAdoptParams {
+    /// The URL of the existing repository to adopt.
+    pub repo_url: String,
+    /// The parameters for cloning the source code of the repository.
+    pub source_params: SourceInitializeParams,
+    /// Whether facets whose files already exist in the repository should overwrite those files.
+    #[serde(default)]
+    pub overwrite_existing: bool,
+}
+
+/// The result of adopting an existing repository as a Skootrs project.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectAdoptResult {
+    /// The adopted project. It only includes the facets that were applied.
+    pub project: InitializedProject,
+    /// The branch the applied facets were committed to.
+    pub branch: String,
+    /// The facets that were applied to the repository.
+    pub applied_facets: Vec<FacetMapKey>,
+    /// The facets that weren't applied because their files already exist in the repository.
+    pub conflicting_facets: Vec<FacetConflict>,
+    /// The URL of the pull request proposing the applied facets, if one was opened.
+    pub pull_request_url: Option<String>,
+}
+
+/// A facet that conflicts with files that already exist in a repository.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct FacetConflict {
+    /// The conflicting facet.
+    pub facet: FacetMapKey,
+    /// The paths of the existing files the facet would have overwritten.
+    pub existing_files: Vec<String>,
+}
+
+/// The parameters for opening a pull request against a repository.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct PullRequestParams {
+    /// The branch with the proposed changes.
+    pub head: String,
+    /// The branch the changes should be merged into.
+    pub base: String,
+    /// The title of the pull request.
+    pub title: String,
+    /// The body of the pull request.
+    pub body: String,
+}
+
+/// The parameters for opening a pull request against a Skootrs project.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectPullRequestParams {
+    /// The initialized project to open the pull request against.
+    pub initialized_project: InitializedProject,
+    /// The parameters for the pull request.
+    pub pull_request: PullRequestParams,
+}
+
+/// The parameters for discovering the existing Skootrs projects that belong to a user or organization.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectDiscoverParams {
+    /// The user or organization whose repos should be scanned for Skootrs projects.
+    pub owner: GithubUser,
+}
+
 /// The parameters for listing all the outputs for a Skootrs project.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
@@ -268,9 +457,22 @@ pub struct FacetGetParams {
     pub facet_map_key: FacetMapKey,
 }
 
-/// Represents an initialized repository along with its host.
+/// The parameters for removing a facet from a project.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectFacetRemoveParams {
+    /// Parameters for first getting the project.
+    pub project_get_params: ProjectGetParams,
+    /// The key of the facet to remove from the project.
+    pub facet_map_key: FacetMapKey,
+    /// Whether the files of the facet that were modified since they were recorded are removed anyway.
+    #[serde(default)]
+    pub force: bool,
+}
+
+/// Represents an initialized repository along with its host.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub enum InitializedRepo {
     /// An initialized Github repository.
     Github(InitializedGithubRepo),
@@ -292,6 +494,14 @@ impl InitializedRepo {
             Self::Github(x) => x.full_url(),
         }
     }
+
+    /// Returns the name of the repo's default branch.
+    #[must_use]
+    pub fn default_branch(&self) -> &str {
+        match self {
+            Self::Github(x) => &x.default_branch,
+        }
+    }
 }
 
 impl TryFrom<String> for InitializedRepo {
@@ -312,12 +522,17 @@ impl TryFrom<String> for InitializedRepo {
         let name = *path_segments
             .get(1)
             .ok_or(format!("Invalid repo URL: {value}"))?;
+        // GitHub is the only supported repo host, so any domain is taken to be a GitHub instance, e.g. a
+        // GitHub Enterprise Server. The services check it's the instance they're configured for.
         match parts.host() {
-            Some(Host::Domain("github.com")) => {
+            Some(Host::Domain(host)) => {
                 Ok(Self::Github(InitializedGithubRepo {
                     name: name.to_string(),
                     // FIXME: This will have issues if this isn't a user repo and in fact an organization user.
                     organization: GithubUser::User(organization.into()),
+                    host: host.to_string(),
+                    default_branch: default_branch(),
+                    settings: None,
                 }))
             }
             _ => Err("Unsupported repo host".into()),
@@ -326,20 +541,37 @@ impl TryFrom<String> for InitializedRepo {
 }
 
 /// Represents an initialized Github repository.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct InitializedGithubRepo {
     /// The name of the Github repository.
     pub name: String,
     /// The organization the Github repository belongs to.
     pub organization: GithubUser,
+    /// The host of the GitHub instance the repository is on, e.g. `github.com` or the host of a GitHub
+    /// Enterprise Server.
+    #[serde(default = "default_github_host")]
+    pub host: String,
+    /// The name of the repository's default branch. It's detected when the repository is looked up, so a
+    /// repository parsed from its URL assumes `main`.
+    #[serde(default = "default_branch")]
+    pub default_branch: String,
+    /// The settings the repository was created with. This is only known for repositories Skootrs created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub settings: Option<GithubRepoSettings>,
 }
 
 impl InitializedGithubRepo {
     /// Returns the host URL of github.
     #[must_use]
     pub fn host_url(&self) -> String {
-        "https://github.com".into()
+        format!("https://{}", self.host)
+    }
+
+    /// Returns the base URL of the API of the GitHub instance the repository is on.
+    #[must_use]
+    pub fn api_url(&self) -> String {
+        github_api_url(&self.host)
     }
 
     /// Returns the full URL to the github repo.
@@ -356,7 +588,7 @@ impl InitializedGithubRepo {
 
 /// Represents an initialized ecosystem. The enum is used to represent the different types of ecosystems
 /// that are supported by Skootrs currently.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub enum InitializedEcosystem {
     /// An initialized Go ecosystem for `InitializedSource`.
@@ -365,6 +597,15 @@ pub enum InitializedEcosystem {
     Maven(InitializedMaven),
 }
 
+impl fmt::Display for InitializedEcosystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Go(_) => write!(f, "Go"),
+            Self::Maven(_) => write!(f, "Maven"),
+        }
+    }
+}
+
 /// The parameters for creating a repository.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
@@ -390,10 +631,27 @@ pub struct InitializedRepoGetParams {
     pub repo_url: String,
 }
 
+/// The host of the public GitHub instance.
+pub const GITHUB_HOST: &str = "github.com";
+
+fn default_github_host() -> String {
+    GITHUB_HOST.to_string()
+}
+
+/// Returns the base URL of the API of a GitHub instance. GitHub Enterprise Server serves it under `/api/v3`.
+#[must_use]
+pub fn github_api_url(host: &str) -> String {
+    if host == GITHUB_HOST {
+        "https://api.github.com".to_string()
+    } else {
+        format!("https://{host}/api/v3")
+    }
+}
+
 /// Represents a Github user which is really just whether or not a repo belongs to  a user or organization.
 /// This is used to create a repo in the Github API. The Github API has different calls for creating a repo
 /// that belongs to the current authorized user or an organization the user has access to.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub enum GithubUser {
     /// A Github user, i.e. not an organization.
@@ -422,13 +680,20 @@ pub struct GithubRepoParams {
     pub description: String,
     /// The organization the Github repository belongs to.
     pub organization: GithubUser,
+    /// The host of the GitHub instance to create the repository on, e.g. `github.com` or the host of a
+    /// GitHub Enterprise Server.
+    #[serde(default = "default_github_host")]
+    pub host: String,
+    /// The settings to create the Github repository with.
+    #[serde(default)]
+    pub settings: GithubRepoSettings,
 }
 
 impl GithubRepoParams {
     /// Helper for returning the github host.
     #[must_use]
     pub fn host_url(&self) -> String {
-        "https://github.com".into()
+        format!("https://{}", self.host)
     }
 
     /// Helper for returning the full URL to the github repo.
@@ -443,6 +708,99 @@ impl GithubRepoParams {
     }
 }
 
+/// The settings a Github repository is created with.
+#[allow(clippy::struct_excessive_bools)] // These mirror the Github repository settings.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct GithubRepoSettings {
+    /// Who can see the repository.
+    #[serde(default)]
+    pub visibility: RepoVisibility,
+    /// The repository to generate the new repository from, in `owner/name` form.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<String>,
+    /// The topics the repository is tagged with.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub topics: Vec<String>,
+    /// The name of the default branch.
+    #[serde(default = "default_branch")]
+    pub default_branch: String,
+    /// Whether pull requests can be merged with a merge commit.
+    #[serde(default = "default_true")]
+    pub allow_merge_commit: bool,
+    /// Whether pull requests can be squash merged.
+    #[serde(default = "default_true")]
+    pub allow_squash_merge: bool,
+    /// Whether pull requests can be rebase merged.
+    #[serde(default = "default_true")]
+    pub allow_rebase_merge: bool,
+    /// Whether head branches are deleted once their pull request is merged.
+    #[serde(default)]
+    pub delete_branch_on_merge: bool,
+    /// Whether secret scanning is enabled.
+    #[serde(default)]
+    pub secret_scanning: bool,
+    /// Whether pushes containing secrets are blocked. This needs secret scanning.
+    #[serde(default)]
+    pub secret_scanning_push_protection: bool,
+    /// Whether Dependabot alerts are enabled.
+    #[serde(default)]
+    pub dependabot_alerts: bool,
+}
+
+impl Default for GithubRepoSettings {
+    fn default() -> Self {
+        Self {
+            visibility: RepoVisibility::default(),
+            template: None,
+            topics: Vec::new(),
+            default_branch: default_branch(),
+            allow_merge_commit: true,
+            allow_squash_merge: true,
+            allow_rebase_merge: true,
+            delete_branch_on_merge: false,
+            secret_scanning: false,
+            secret_scanning_push_protection: false,
+            dependabot_alerts: false,
+        }
+    }
+}
+
+fn default_branch() -> String {
+    "main".to_string()
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+/// Who can see a Github repository.
+#[derive(
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    JsonSchema,
+    Display,
+    EnumString,
+    VariantNames,
+)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum RepoVisibility {
+    /// Anyone can see the repository.
+    #[default]
+    Public,
+    /// Only people given access can see the repository.
+    Private,
+    /// Members of the enterprise can see the repository. This is only available for organization
+    /// repositories on Github Enterprise.
+    Internal,
+}
+
 /// Represents the parameters for initializing a source code repository.
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
@@ -460,7 +818,7 @@ impl SourceInitializeParams {
 }
 
 /// Struct representing a working copy of source code.
-#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, JsonSchema)]
 pub struct InitializedSource {
     /// The path to the source code repository.
     pub path: String,
@@ -487,7 +845,7 @@ pub struct GoParams {
 }
 
 /// Represents an initialized go module.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct InitializedGo {
     /// The name of the Go module.
@@ -505,7 +863,7 @@ impl InitializedGo {
 }
 
 /// Represents an initialized Maven project.
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
 #[cfg_attr(feature = "openapi", derive(ToSchema))]
 pub struct InitializedMaven {
     /// The group ID of the Maven project.
@@ -528,13 +886,41 @@ impl GoParams {
 pub struct Config {
     /// The local path to cached projects. This is used by `LocalProjectService` for performing operations locally.
     pub local_project_path: String,
+    /// The configuration for signing and verifying the `.skootrs` state file.
+    #[serde(default)]
+    pub state_signing: StateSigningConfig,
+    /// The host of the GitHub instance projects are created on, e.g. `github.com` or the host of a GitHub
+    /// Enterprise Server.
+    #[serde(default = "default_github_host")]
+    pub github_host: String,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
             local_project_path: "/tmp".into(),
+            state_signing: StateSigningConfig::default(),
+            github_host: default_github_host(),
+        }
+    }
+}
+
+/// The environment variable that overrides the host of the GitHub instance projects are created on.
+pub const GITHUB_HOST_ENV_VAR: &str = "SKOOTRS_GITHUB_HOST";
+
+impl Config {
+    /// Returns the default config with the options set in the environment applied on top of it.
+    #[must_use]
+    pub fn from_env() -> Self {
+        Self::from_vars(|name| std::env::var(name).ok())
+    }
+
+    fn from_vars(var: impl Fn(&str) -> Option<String>) -> Self {
+        let mut config = Self::default();
+        if let Some(github_host) = var(GITHUB_HOST_ENV_VAR) {
+            config.github_host = github_host;
         }
+        config
     }
 }
 
@@ -549,4 +935,38 @@ mod tests {
         assert_eq!(repo.host_url(), "https://github.com");
         assert_eq!(repo.full_url(), "https://github.com/kusaridev/skootrs");
     }
+
+    #[test]
+    fn test_initialized_repo_try_from_github_enterprise() {
+        let InitializedRepo::Github(repo) =
+            InitializedRepo::try_from("https://github.example.com/myorg/myproject".to_string())
+                .unwrap();
+        assert_eq!(repo.host, "github.example.com");
+        assert_eq!(repo.host_url(), "https://github.example.com");
+        assert_eq!(repo.api_url(), "https://github.example.com/api/v3");
+        assert_eq!(
+            repo.full_url(),
+            "https://github.example.com/myorg/myproject"
+        );
+    }
+
+    #[test]
+    fn test_config_from_vars() {
+        let config = Config::from_vars(|_| None);
+        assert_eq!(config.github_host, GITHUB_HOST);
+
+        let config = Config::from_vars(|name| {
+            (name == GITHUB_HOST_ENV_VAR).then(|| "github.example.com".to_string())
+        });
+        assert_eq!(config.github_host, "github.example.com");
+    }
+
+    #[test]
+    fn test_initialized_github_repo_defaults_to_github() {
+        let repo: InitializedGithubRepo =
+            serde_json::from_str(r#"{"name": "skootrs", "organization": {"User": "kusaridev"}}"#)
+                .unwrap();
+        assert_eq!(repo.host, GITHUB_HOST);
+        assert_eq!(repo.api_url(), "https://api.github.com");
+    }
 }
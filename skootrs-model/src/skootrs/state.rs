@@ -0,0 +1,409 @@
+//
+// Copyright 2024 The Skootrs Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The `state` module provides the data model for the `.skootrs` state file
+//! that is kept in the root of every Skootrs managed repo.
+//!
+//! The state file wraps an `InitializedProject` in a versioned envelope so
+//! that older state files can be migrated forward and state files written
+//! by a newer version of Skootrs are rejected instead of misread.
+
+#![allow(clippy::module_name_repetitions)]
+
+use std::{error::Error, fmt};
+
+use chrono::{DateTime, Utc};
+use schemars::{schema::RootSchema, JsonSchema};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "openapi")]
+use utoipa::ToSchema;
+
+use super::{facet::SourceFile, FacetMapKey, InitializedProject};
+
+/// The name of the state file kept in the root of a Skootrs project's repo.
+pub const STATE_FILE_NAME: &str = ".skootrs";
+
+/// The name of the detached signature file for the state file, kept next to it in the repo.
+pub const STATE_SIGNATURE_FILE_NAME: &str = ".skootrs.sig";
+
+/// The schema version of the state file written by this version of Skootrs.
+pub const CURRENT_STATE_SCHEMA_VERSION: u32 = 1;
+
+/// A migration takes a state file at schema version `n` and returns it at schema version `n + 1`.
+type Migration = fn(serde_json::Value) -> Result<serde_json::Value, StateFileError>;
+
+/// The migrations between schema versions. The migration at index `n` migrates a state file from
+/// schema version `n` to `n + 1`, so there should always be `CURRENT_STATE_SCHEMA_VERSION` of them.
+const MIGRATIONS: [Migration; CURRENT_STATE_SCHEMA_VERSION as usize] = [migrate_v0_to_v1];
+
+/// Represents the content of the `.skootrs` state file. This is the `InitializedProject` along with
+/// metadata about the file itself like the schema version and who last applied it.
+#[derive(Serialize, Deserialize, Clone, Debug, JsonSchema)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectState {
+    /// The version of the state file schema. This is used to migrate older state files.
+    pub schema_version: u32,
+    /// The version of Skootrs that wrote the state file.
+    pub skootrs_version: String,
+    /// When the state file was written.
+    pub timestamp: DateTime<Utc>,
+    /// Who or what last applied the state to the project, e.g. a git user.
+    pub last_applied_by: String,
+    /// The state of the project itself.
+    pub project: InitializedProject,
+}
+
+impl ProjectState {
+    /// Wraps an `InitializedProject` in a state file envelope at the current schema version.
+    #[must_use]
+    pub fn new(project: InitializedProject, last_applied_by: String) -> Self {
+        Self {
+            schema_version: CURRENT_STATE_SCHEMA_VERSION,
+            skootrs_version: env!("CARGO_PKG_VERSION").to_string(),
+            timestamp: Utc::now(),
+            last_applied_by,
+            project,
+        }
+    }
+
+    /// Parses the content of a state file, migrating it to the current schema version if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the content isn't a valid state file or if it was written with a schema
+    /// version newer than the one supported by this version of Skootrs.
+    pub fn from_json(content: &str) -> Result<Self, StateFileError> {
+        let value: serde_json::Value =
+            serde_json::from_str(content).map_err(|e| StateFileError::Malformed(e.to_string()))?;
+        let migrated = migrate(value)?;
+        serde_json::from_value(migrated).map_err(|e| StateFileError::Malformed(e.to_string()))
+    }
+
+    /// Returns the JSON schema for the state file.
+    #[must_use]
+    pub fn schema() -> RootSchema {
+        schemars::schema_for!(Self)
+    }
+}
+
+/// A reference to a Skootrs project known to the local Skootrs along with some metadata about it.
+/// The metadata is `None` if the project was only ever referenced by its URL.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectReference {
+    /// The URL of the project's repo.
+    pub repo_url: String,
+    /// The name of the project.
+    pub name: Option<String>,
+    /// The ecosystem of the project, e.g. `Go`.
+    pub ecosystem: Option<String>,
+    /// When the reference was last updated.
+    pub last_updated: Option<DateTime<Utc>>,
+    /// The number of facets the project had when the reference was last updated.
+    pub facet_count: Option<usize>,
+}
+
+impl ProjectReference {
+    /// Creates a reference with metadata from an initialized project.
+    #[must_use]
+    pub fn from_project(project: &InitializedProject) -> Self {
+        Self {
+            repo_url: project.repo.full_url(),
+            name: Some(project.name.clone()),
+            ecosystem: Some(project.ecosystem.to_string()),
+            last_updated: Some(Utc::now()),
+            facet_count: Some(project.facets.len()),
+        }
+    }
+}
+
+/// A past version of the state file from the history of a project's repo.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectStateHistoryEntry {
+    /// The commit that changed the state file.
+    pub commit: String,
+    /// When the commit was authored.
+    pub timestamp: DateTime<Utc>,
+    /// The author of the commit, e.g. `Jane Doe <jane@example.com>`.
+    pub author: String,
+}
+
+/// The differences between two versions of a project's state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct ProjectStateDiff {
+    /// The commit of the state being compared from.
+    pub from: String,
+    /// The commit of the state being compared to.
+    pub to: String,
+    /// The facets that differ between the two states.
+    pub facets: Vec<FacetStateDiff>,
+}
+
+/// The difference in a single facet between two versions of a project's state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum FacetStateDiff {
+    /// The facet only exists in the newer state.
+    Added(FacetMapKey),
+    /// The facet only exists in the older state.
+    Removed(FacetMapKey),
+    /// The facet exists in both states but its files or API calls differ.
+    Changed {
+        /// The facet that changed.
+        facet: FacetMapKey,
+        /// The source files of the facet that changed. This is empty for API bundle facets.
+        files: Vec<SourceFileDiff>,
+    },
+}
+
+/// The difference in a single source file of a facet between two versions of a project's state.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum SourceFileDiff {
+    /// The file only exists in the newer state.
+    Added(SourceFile),
+    /// The file only exists in the older state.
+    Removed(SourceFile),
+    /// The file exists in both states with a different hash.
+    Changed {
+        /// The file in the older state.
+        from: SourceFile,
+        /// The file in the newer state.
+        to: SourceFile,
+    },
+}
+
+/// The key used to sign the state file. Keys are OpenSSH keys so both ed25519 and ECDSA keys
+/// are supported.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum StateSigningKey {
+    /// The path to an unencrypted OpenSSH private key file.
+    KeyFile(String),
+    /// The path to the OpenSSH public key of a key held by the running ssh-agent.
+    SshAgent(String),
+}
+
+/// What to do when the state file's signature is missing or doesn't verify.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub enum SignatureVerificationPolicy {
+    /// Fail to read the state file.
+    Enforce,
+    /// Log a warning and read the state file anyway. The warning is only logged when signing is configured,
+    /// since state files aren't signed otherwise.
+    #[default]
+    Warn,
+    /// Don't verify the signature at all.
+    Ignore,
+}
+
+/// The configuration for signing and verifying the state file.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[cfg_attr(feature = "openapi", derive(ToSchema))]
+pub struct StateSigningConfig {
+    /// The key to sign the state file with. The state file isn't signed if this is `None`.
+    pub signing_key: Option<StateSigningKey>,
+    /// The OpenSSH public keys, e.g. `ssh-ed25519 AAAA...`, trusted to sign the state file.
+    pub trusted_keys: Vec<String>,
+    /// What to do when the signature is missing or doesn't verify.
+    pub verification_policy: SignatureVerificationPolicy,
+}
+
+impl StateSigningConfig {
+    /// Whether signing is configured at all, i.e. there's a key to sign with or keys to trust.
+    #[must_use]
+    pub const fn is_configured(&self) -> bool {
+        self.signing_key.is_some() || !self.trusted_keys.is_empty()
+    }
+}
+
+/// The errors that can happen when reading a state file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StateFileError {
+    /// The state file isn't valid JSON or doesn't match the schema for its version.
+    Malformed(String),
+    /// The state file was written with a schema version this version of Skootrs doesn't support.
+    UnsupportedVersion {
+        /// The schema version found in the state file.
+        found: u64,
+        /// The newest schema version supported by this version of Skootrs.
+        supported: u32,
+    },
+}
+
+impl fmt::Display for StateFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(e) => write!(f, "Malformed state file: {e}"),
+            Self::UnsupportedVersion { found, supported } => write!(
+                f,
+                "State file schema version {found} is newer than the supported version {supported}, upgrade Skootrs to read it"
+            ),
+        }
+    }
+}
+
+impl Error for StateFileError {}
+
+/// Migrates a state file of any known schema version to the current schema version.
+fn migrate(mut value: serde_json::Value) -> Result<serde_json::Value, StateFileError> {
+    let version = match value.get("schema_version") {
+        // State files written before the envelope existed are just a bare `InitializedProject`.
+        None => 0,
+        Some(v) => v
+            .as_u64()
+            .ok_or_else(|| StateFileError::Malformed(format!("Invalid schema version: {v}")))?,
+    };
+
+    if version > u64::from(CURRENT_STATE_SCHEMA_VERSION) {
+        return Err(StateFileError::UnsupportedVersion {
+            found: version,
+            supported: CURRENT_STATE_SCHEMA_VERSION,
+        });
+    }
+
+    for migration in MIGRATIONS
+        .iter()
+        .skip(usize::try_from(version).unwrap_or(usize::MAX))
+    {
+        value = migration(value)?;
+    }
+
+    Ok(value)
+}
+
+/// Wraps a bare `InitializedProject` in the version 1 envelope.
+// All migrations share the `Migration` signature even if they can't fail.
+#[allow(clippy::unnecessary_wraps, clippy::needless_pass_by_value)]
+fn migrate_v0_to_v1(value: serde_json::Value) -> Result<serde_json::Value, StateFileError> {
+    Ok(serde_json::json!({
+        "schema_version": 1,
+        "skootrs_version": "unknown",
+        "timestamp": DateTime::<Utc>::default(),
+        "last_applied_by": "unknown",
+        "project": value,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    #![allow(clippy::unwrap_used)]
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::skootrs::{
+        GithubUser, InitializedEcosystem, InitializedGithubRepo, InitializedGo, InitializedRepo,
+        InitializedSource,
+    };
+
+    fn test_project() -> InitializedProject {
+        InitializedProject {
+            repo: InitializedRepo::Github(InitializedGithubRepo {
+                name: "skootrs".to_string(),
+                organization: GithubUser::Organization("kusaridev".to_string()),
+                host: "github.com".to_string(),
+                default_branch: "main".to_string(),
+                settings: None,
+            }),
+            ecosystem: InitializedEcosystem::Go(InitializedGo {
+                name: "skootrs".to_string(),
+                host: "github.com/kusaridev".to_string(),
+            }),
+            source: InitializedSource {
+                path: "/tmp/skootrs".to_string(),
+            },
+            facets: HashMap::new(),
+            name: "skootrs".to_string(),
+            excluded_facets: vec![],
+        }
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let state = ProjectState::new(test_project(), "tester".to_string());
+        let content = serde_json::to_string(&state).unwrap();
+        let parsed = ProjectState::from_json(&content).unwrap();
+        assert_eq!(parsed.schema_version, CURRENT_STATE_SCHEMA_VERSION);
+        assert_eq!(parsed.last_applied_by, "tester");
+        assert_eq!(parsed.project.name, "skootrs");
+    }
+
+    #[test]
+    fn test_migrate_legacy_state_file() {
+        let content = serde_json::to_string(&test_project()).unwrap();
+        let parsed = ProjectState::from_json(&content).unwrap();
+        assert_eq!(parsed.schema_version, CURRENT_STATE_SCHEMA_VERSION);
+        assert_eq!(parsed.skootrs_version, "unknown");
+        assert_eq!(
+            parsed.project.repo.full_url(),
+            "https://github.com/kusaridev/skootrs"
+        );
+    }
+
+    #[test]
+    fn test_newer_schema_version() {
+        let mut state =
+            serde_json::to_value(ProjectState::new(test_project(), "tester".to_string())).unwrap();
+        state["schema_version"] = serde_json::json!(CURRENT_STATE_SCHEMA_VERSION + 1);
+        let result = ProjectState::from_json(&state.to_string());
+        assert_eq!(
+            result.unwrap_err(),
+            StateFileError::UnsupportedVersion {
+                found: u64::from(CURRENT_STATE_SCHEMA_VERSION + 1),
+                supported: CURRENT_STATE_SCHEMA_VERSION,
+            }
+        );
+    }
+
+    #[test]
+    fn test_malformed_state_file() {
+        assert!(matches!(
+            ProjectState::from_json("not json"),
+            Err(StateFileError::Malformed(_))
+        ));
+        assert!(matches!(
+            ProjectState::from_json(r#"{"schema_version": 1, "project": {}}"#),
+            Err(StateFileError::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_signing_is_configured() {
+        assert!(!StateSigningConfig::default().is_configured());
+        assert!(StateSigningConfig {
+            trusted_keys: vec!["ssh-ed25519 AAAA".to_string()],
+            ..StateSigningConfig::default()
+        }
+        .is_configured());
+    }
+
+    #[test]
+    fn test_published_schema_is_up_to_date() {
+        // Regenerate with `SKOOTRS_UPDATE_SCHEMA=1 cargo test -p skootrs-model`
+        let schema_path = concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/schemas/project-state.schema.json"
+        );
+        let generated = serde_json::to_string_pretty(&ProjectState::schema()).unwrap() + "\n";
+        if std::env::var("SKOOTRS_UPDATE_SCHEMA").is_ok() {
+            std::fs::write(schema_path, &generated).unwrap();
+        }
+        let published = std::fs::read_to_string(schema_path).unwrap();
+        assert_eq!(published, generated);
+    }
+}
@@ -13,5 +13,5 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod project;
 pub mod rest;
-mod project;
\ No newline at end of file
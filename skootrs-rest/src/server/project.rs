@@ -15,12 +15,12 @@
 
 use actix_web::{Responder, web::{ServiceConfig, Data, Json, self}, HttpResponse};
 use serde::{Serialize, Deserialize};
-use skootrs_statestore::{InMemoryProjectReferenceCache, ProjectReferenceCache};
+use skootrs_statestore::{ProjectReferenceCache, sqlite::SqliteProjectReferenceCache};
 use tokio::sync::Mutex;
 use utoipa::ToSchema;
 
 use skootrs_model::skootrs::ProjectCreateParams;
-use skootrs_lib::service::{ecosystem::LocalEcosystemService, facet::LocalFacetService, output::LocalOutputService, project::{LocalProjectService, ProjectService}, repo::LocalRepoService, source::LocalSourceService};
+use skootrs_lib::service::{ecosystem::LocalEcosystemService, facet::LocalFacetService, github::GithubClient, output::LocalOutputService, project::{LocalProjectService, ProjectService}, repo::LocalRepoService, source::LocalSourceService};
 
 /// An Error response for the REST API
 #[derive(Serialize, Deserialize, Clone, ToSchema)]
@@ -34,10 +34,11 @@ pub(super) enum ErrorResponse {
 }
 
 /// Configures the services and routes for the Skootrs REST API
-pub(super) fn configure(store: Data<Mutex<InMemoryProjectReferenceCache>>) -> impl FnOnce(&mut ServiceConfig) {
+pub(super) fn configure(store: Data<Mutex<SqliteProjectReferenceCache>>, github: Data<GithubClient>) -> impl FnOnce(&mut ServiceConfig) {
     |config: &mut ServiceConfig| {
         config
             .app_data(store)
+            .app_data(github)
             .service(web::resource("/projects")
                 .route(web::post().to(create_project))
                 .route(web::get().to(list_projects))
@@ -70,21 +71,18 @@ pub(super) fn configure(store: Data<Mutex<InMemoryProjectReferenceCache>>) -> im
         (status = 409, description = "Project unable to be created", body = ErrorResponse, example = json!(ErrorResponse::InitializationError("Unable to create repo".into())))
     )
 )]
-pub(super) async fn create_project(params: Json<ProjectCreateParams>, project_store: Data<Mutex<InMemoryProjectReferenceCache>>) -> Result<impl Responder, actix_web::Error> {
-    // TODO: This should be initialized elsewhere
+pub(super) async fn create_project(params: Json<ProjectCreateParams>, project_store: Data<Mutex<SqliteProjectReferenceCache>>, github: Data<GithubClient>) -> Result<impl Responder, actix_web::Error> {
     let project_service = LocalProjectService {
-        repo_service: LocalRepoService {},
+        repo_service: LocalRepoService { github: github.get_ref().clone() },
         ecosystem_service: LocalEcosystemService {},
         source_service: LocalSourceService {},
-        facet_service: LocalFacetService {},
-        output_service: LocalOutputService {},
+        facet_service: LocalFacetService { github: github.get_ref().clone() },
+        output_service: LocalOutputService { github: github.get_ref().clone() },
     };
 
     let initialized_project = project_service.initialize(params.into_inner()).await
     .map_err(|err| actix_web::error::ErrorInternalServerError(err.to_string()))?;
-    project_store.lock().await.set(initialized_project.repo.full_url()).await.map_err(|err| actix_web::error::ErrorInternalServerError(err.to_string()))?;
-    // TODO: Should this return an internal server error if it can't save the cache?
-    project_store.lock().await.save().map_err(|err| actix_web::error::ErrorInternalServerError(err.to_string()))?;
+    project_store.lock().await.set_project(&initialized_project).await.map_err(|err| actix_web::error::ErrorInternalServerError(err.to_string()))?;
     Ok(HttpResponse::Ok().json(initialized_project))
 }
 
@@ -97,7 +95,7 @@ pub(super) async fn create_project(params: Json<ProjectCreateParams>, project_st
         (status = 500, description = "Internal server error", body = ErrorResponse, example = json!(ErrorResponse::InitializationError("Unable to list repos".into()))),
     )
 )]
-pub(super) async fn list_projects(project_store: Data<InMemoryProjectReferenceCache>) -> Result<impl Responder, actix_web::Error> {
-    let projects = project_store.list().await.map_err(|err| actix_web::error::ErrorInternalServerError(err.to_string()))?;
+pub(super) async fn list_projects(project_store: Data<Mutex<SqliteProjectReferenceCache>>) -> Result<impl Responder, actix_web::Error> {
+    let projects = project_store.lock().await.list().await.map_err(|err| actix_web::error::ErrorInternalServerError(err.to_string()))?;
     Ok(HttpResponse::Ok().json(projects))
 }
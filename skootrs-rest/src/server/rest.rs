@@ -16,7 +16,8 @@
 use std::net::Ipv4Addr;
 
 use actix_web::{App, HttpServer, web::Data};
-use skootrs_statestore::InMemoryProjectReferenceCache;
+use skootrs_lib::service::{github::GithubClient, repo::LocalRepoService};
+use skootrs_statestore::sqlite::SqliteProjectReferenceCache;
 use tokio::sync::Mutex;
 use tracing_actix_web::TracingLogger;
 use utoipa::{OpenApi, Modify, openapi::security::{SecurityScheme, ApiKey, ApiKeyValue}};
@@ -133,14 +134,20 @@ pub async fn run_server() -> std::io::Result<()> {
         }
     }
 
-    let store: Data<Mutex<InMemoryProjectReferenceCache>> = Data::new(Mutex::new(InMemoryProjectReferenceCache::new("/tmp/cache.json".into())));
+    let config = skootrs_model::skootrs::Config::from_env();
+    // The client is shared by every request so they all reuse the same credentials and installation tokens.
+    let github = GithubClient::for_host(&config.github_host);
+    let mut cache = SqliteProjectReferenceCache::open_default().map_err(std::io::Error::other)?;
+    cache.local_repo_service = LocalRepoService { github: github.clone() };
+    let store: Data<Mutex<SqliteProjectReferenceCache>> = Data::new(Mutex::new(cache));
     // Make instance variable of ApiDoc so all worker threads gets the same instance.
     let openapi = ApiDoc::openapi();
+    let github = Data::new(github);
 
     HttpServer::new(move || {
         App::new()
             .wrap(TracingLogger::default())
-            .configure(crate::server::project::configure(store.clone()))
+            .configure(crate::server::project::configure(store.clone(), github.clone()))
             .service(Redoc::with_url("/redoc", openapi.clone()))
             .service(
                 SwaggerUi::new("/swagger-ui/{_:.*}").url("/api-docs/openapi.json", openapi.clone()),
@@ -14,16 +14,34 @@
 // limitations under the License.
 
 //! This is the crate where the statestore where the management of `Skootrs` project state is defined.
-//! The statestore currently supports an in memory `SurrealDB` instance that writes to a file.
+//! The state of a project is kept in the project's repo, and the references to all the projects known
+//! to the local Skootrs are kept in a `SQLite` database under the user's data directory.
 
-use std::collections::HashSet;
+pub mod signing;
+pub mod sqlite;
+
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    process::Command,
+};
+
+use chrono::{DateTime, Utc};
+use tracing::{info, warn};
 
 use skootrs_lib::service::{
     repo::{LocalRepoService, RepoService},
-    source::{LocalSourceService, SourceService},
+    source::{resolve_source_path, LocalSourceService, SourceService},
 };
 
-use skootrs_model::skootrs::{InitializedProject, InitializedRepo, InitializedSource, SkootError};
+use skootrs_model::skootrs::{
+    facet::{InitializedFacet, SourceBundleFacet, SourceFile},
+    state::{
+        FacetStateDiff, ProjectReference, ProjectState, ProjectStateDiff, ProjectStateHistoryEntry,
+        SourceFileDiff, StateSigningConfig, STATE_FILE_NAME, STATE_SIGNATURE_FILE_NAME,
+    },
+    FacetMapKey, InitializedProject, InitializedRepo, InitializedSource, SkootError,
+};
 
 pub trait ProjectStateStore {
     fn create(
@@ -37,41 +55,372 @@ pub trait ProjectStateStore {
         &self,
         project: InitializedProject,
     ) -> impl std::future::Future<Output = Result<(), SkootError>> + Send;
+    /// Lists the past versions of the project state, newest first.
+    fn history(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Vec<ProjectStateHistoryEntry>, SkootError>> + Send;
+    /// Reads the project state as it was at a past commit.
+    fn read_at(
+        &self,
+        commit: String,
+    ) -> impl std::future::Future<Output = Result<InitializedProject, SkootError>> + Send;
+    /// Compares the project state at two commits at the facet and file hash level.
+    fn diff(
+        &self,
+        from: String,
+        to: String,
+    ) -> impl std::future::Future<Output = Result<ProjectStateDiff, SkootError>> + Send;
+    /// Restores the facets of the project state at a past commit as a new commit and returns the
+    /// restored project.
+    fn rollback(
+        &self,
+        commit: String,
+    ) -> impl std::future::Future<Output = Result<InitializedProject, SkootError>> + Send;
 }
 
 pub struct GitProjectStateStore<S: SourceService> {
     // TODO: This should be a git repo type of some sort
     pub source: InitializedSource,
     pub source_service: S,
+    pub signing: StateSigningConfig,
 }
 
 impl ProjectStateStore for GitProjectStateStore<LocalSourceService> {
     async fn create(&self, project: InitializedProject) -> Result<(), SkootError> {
+        // An existing state is only ever replaced by an update so a project can't be reset by accident.
+        if Path::new(&self.source.path).join(STATE_FILE_NAME).exists() {
+            return Err(format!("A project state already exists in {}", self.source.path).into());
+        }
+        self.write_state(project, "Updated skootrs project state".to_string())
+    }
+
+    async fn read(&self) -> Result<Option<InitializedProject>, SkootError> {
+        let project_state =
+            self.source_service
+                .read_file(&self.source, "./", STATE_FILE_NAME.to_string())?;
+        self.verify(&project_state)?;
+        Ok(Some(ProjectState::from_json(&project_state)?.project))
+    }
+
+    async fn update(&self, project: InitializedProject) -> Result<(), SkootError> {
+        self.write_state(project, "Updated skootrs project state".to_string())
+    }
+
+    async fn history(&self) -> Result<Vec<ProjectStateHistoryEntry>, SkootError> {
+        let log = self.git(&[
+            "log",
+            "--format=%H%x1f%aI%x1f%an <%ae>",
+            "--",
+            STATE_FILE_NAME,
+        ])?;
+        log.lines()
+            .map(|line| {
+                let fields: Vec<&str> = line.split('\u{1f}').collect();
+                let [commit, timestamp, author] = fields[..] else {
+                    return Err(SkootError::from(format!("Unexpected git log line: {line}")));
+                };
+                Ok(ProjectStateHistoryEntry {
+                    commit: commit.to_string(),
+                    timestamp: DateTime::parse_from_rfc3339(timestamp)?.with_timezone(&Utc),
+                    author: author.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    async fn read_at(&self, commit: String) -> Result<InitializedProject, SkootError> {
+        let commit = self.resolve_commit(&commit)?;
+        let content = self.git(&["show", &format!("{commit}:{STATE_FILE_NAME}")])?;
+        // The state at an older commit is what gets restored on rollback, so it's held to the same
+        // signature policy as the current state.
+        let signature = self
+            .git(&["show", &format!("{commit}:{STATE_SIGNATURE_FILE_NAME}")])
+            .ok();
+        signing::check(
+            &self.signing,
+            &content,
+            signature.as_deref(),
+            &format!("{} at {commit}", self.source.path),
+        )?;
+        Ok(ProjectState::from_json(&content)?.project)
+    }
+
+    async fn diff(&self, from: String, to: String) -> Result<ProjectStateDiff, SkootError> {
+        let from_project = self.read_at(from.clone()).await?;
+        let to_project = self.read_at(to.clone()).await?;
+        Ok(ProjectStateDiff {
+            facets: diff_facets(&from_project, &to_project),
+            from,
+            to,
+        })
+    }
+
+    async fn rollback(&self, commit: String) -> Result<InitializedProject, SkootError> {
+        let commit = self.resolve_commit(&commit)?;
+        let current = self.read().await?.ok_or("No current project state")?;
+        let mut restored = self.read_at(commit.clone()).await?;
+
+        // Files that belong to facets that didn't exist yet are removed, and every file of the
+        // restored facets is brought back to its content at the commit.
+        let current_files = source_file_paths(&current);
+        let restored_files = source_file_paths(&restored);
+        // The paths come from state files, so all of them are checked before touching anything to make
+        // sure a tampered state can't remove or overwrite files outside of the source.
+        for path in current_files.union(&restored_files) {
+            resolve_source_path(&self.source, path, "")?;
+        }
+        for path in current_files.difference(&restored_files) {
+            let full_path = resolve_source_path(&self.source, path, "")?;
+            if full_path.exists() {
+                info!("Removing {path} which is not part of the restored state");
+                std::fs::remove_file(full_path)?;
+            }
+        }
+        for path in &restored_files {
+            self.git(&["checkout", &commit, "--", path])?;
+        }
+        for facet in restored.facets.values() {
+            if let InitializedFacet::APIBundle(api_bundle) = facet {
+                warn!(
+                    "API bundle facet {} can't be rolled back and is left as is",
+                    api_bundle.facet_type
+                );
+            }
+        }
+
+        restored.source = self.source.clone();
+        self.write_state(
+            restored.clone(),
+            format!("Rolled back skootrs project state to {commit}"),
+        )?;
+        Ok(restored)
+    }
+}
+
+impl GitProjectStateStore<LocalSourceService> {
+    /// Writes the project state, signing it if configured, and commits and pushes it along with any other
+    /// changes in the source.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the state can't be signed, written, or committed.
+    pub fn write_state(
+        &self,
+        project: InitializedProject,
+        message: String,
+    ) -> Result<(), SkootError> {
+        let project_state = ProjectState::new(project, self.last_applied_by());
+        let content = serde_json::to_string_pretty(&project_state)?;
+        if let Some(signing_key) = &self.signing.signing_key {
+            let signature = signing::sign(signing_key, content.as_bytes())?;
+            self.source_service.write_file(
+                self.source.clone(),
+                "./",
+                STATE_SIGNATURE_FILE_NAME.to_string(),
+                signature,
+            )?;
+        }
         self.source_service.write_file(
             self.source.clone(),
             "./",
-            ".skootrs".to_string(),
-            serde_json::to_string(&project)?,
-        )?;
-        self.source_service.commit_and_push_changes(
-            self.source.clone(),
-            "Updated skootrs project state".to_string(),
+            STATE_FILE_NAME.to_string(),
+            content,
         )?;
+        self.source_service
+            .commit_and_push_changes(self.source.clone(), message)?;
         Ok(())
     }
 
-    async fn read(&self) -> Result<Option<InitializedProject>, SkootError> {
-        let project = self
+    /// Resolves a commit-ish like a commit hash or tag to the hash of the commit. The commit-ish comes from
+    /// the user, so anything that git could take as an option is rejected.
+    fn resolve_commit(&self, commit: &str) -> Result<String, SkootError> {
+        if commit.is_empty() || commit.starts_with('-') {
+            return Err(format!("Invalid commit {commit:?}").into());
+        }
+        let resolved = self.git(&[
+            "rev-parse",
+            "--verify",
+            "--end-of-options",
+            &format!("{commit}^{{commit}}"),
+        ])?;
+        Ok(resolved.trim().to_string())
+    }
+
+    /// Runs a git command in the project's source directory and returns its stdout.
+    fn git(&self, args: &[&str]) -> Result<String, SkootError> {
+        let output = Command::new("git")
+            .args(args)
+            .current_dir(&self.source.path)
+            .output()?;
+        if !output.status.success() {
+            return Err(format!(
+                "Failed to run git {}: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr)
+            )
+            .into());
+        }
+        Ok(String::from_utf8(output.stdout)?)
+    }
+}
+
+impl<S: SourceService> GitProjectStateStore<S> {
+    /// Verifies the signature of the state file content according to the verification policy.
+    fn verify(&self, content: &str) -> Result<(), SkootError> {
+        let signature = self
             .source_service
-            .read_file(&self.source, "./", ".skootrs".to_string())?;
-        Ok(Some(serde_json::from_str(&project).unwrap()))
+            .read_file(&self.source, "./", STATE_SIGNATURE_FILE_NAME.to_string())
+            .ok();
+        signing::check(
+            &self.signing,
+            content,
+            signature.as_deref(),
+            &self.source.path,
+        )?;
+        Ok(())
     }
 
-    async fn update(&self, project: InitializedProject) -> Result<(), SkootError> {
-        self.create(project).await
+    /// Returns who is applying the state, which is the git user configured for the source
+    /// or "unknown" if one isn't configured.
+    fn last_applied_by(&self) -> String {
+        Command::new("git")
+            .args(["config", "user.email"])
+            .current_dir(&self.source.path)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|user| user.trim().to_string())
+            .filter(|user| !user.is_empty())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+}
+
+/// Returns the differences between the facets of two versions of a project.
+#[must_use]
+pub fn diff_facets(from: &InitializedProject, to: &InitializedProject) -> Vec<FacetStateDiff> {
+    let mut diffs: Vec<FacetStateDiff> = from
+        .facets
+        .iter()
+        .filter_map(|(key, from_facet)| match to.facets.get(key) {
+            None => Some(FacetStateDiff::Removed(key.clone())),
+            Some(to_facet) => diff_facet(key, from_facet, to_facet),
+        })
+        .chain(
+            to.facets
+                .keys()
+                .filter(|key| !from.facets.contains_key(key))
+                .map(|key| FacetStateDiff::Added(key.clone())),
+        )
+        .collect();
+    diffs.sort_by_key(|diff| match diff {
+        FacetStateDiff::Added(key)
+        | FacetStateDiff::Removed(key)
+        | FacetStateDiff::Changed { facet: key, .. } => key.to_string(),
+    });
+    diffs
+}
+
+fn diff_facet(
+    key: &FacetMapKey,
+    from: &InitializedFacet,
+    to: &InitializedFacet,
+) -> Option<FacetStateDiff> {
+    match (from, to) {
+        (InitializedFacet::SourceBundle(from), InitializedFacet::SourceBundle(to)) => {
+            let by_path = |facet: &SourceBundleFacet| -> HashMap<String, SourceFile> {
+                facet
+                    .source_files
+                    .iter()
+                    .flatten()
+                    .map(|file| (source_file_path(file), file.clone()))
+                    .collect()
+            };
+            let from_files = by_path(from);
+            let to_files = by_path(to);
+            let mut files: Vec<SourceFileDiff> = from_files
+                .iter()
+                .filter_map(|(path, from_file)| match to_files.get(path) {
+                    None => Some(SourceFileDiff::Removed(from_file.clone())),
+                    Some(to_file) if to_file.hash != from_file.hash => {
+                        Some(SourceFileDiff::Changed {
+                            from: from_file.clone(),
+                            to: to_file.clone(),
+                        })
+                    }
+                    Some(_) => None,
+                })
+                .chain(
+                    to_files
+                        .iter()
+                        .filter(|(path, _)| !from_files.contains_key(*path))
+                        .map(|(_, to_file)| SourceFileDiff::Added(to_file.clone())),
+                )
+                .collect();
+            files.sort_by_key(|diff| match diff {
+                SourceFileDiff::Added(file)
+                | SourceFileDiff::Removed(file)
+                | SourceFileDiff::Changed { to: file, .. } => source_file_path(file),
+            });
+            (!files.is_empty()).then(|| FacetStateDiff::Changed {
+                facet: key.clone(),
+                files,
+            })
+        }
+        (from, to) => {
+            let changed = serde_json::to_value(from).ok() != serde_json::to_value(to).ok();
+            changed.then(|| FacetStateDiff::Changed {
+                facet: key.clone(),
+                files: vec![],
+            })
+        }
     }
 }
 
+/// Returns the path of a source file relative to the root of the repo.
+fn source_file_path(file: &SourceFile) -> String {
+    let path = Path::new(&file.path).join(&file.name);
+    path.strip_prefix("./")
+        .unwrap_or(&path)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Returns the paths of all the source files of a project's source bundle facets.
+fn source_file_paths(project: &InitializedProject) -> HashSet<String> {
+    project
+        .facets
+        .values()
+        .filter_map(|facet| match facet {
+            InitializedFacet::SourceBundle(s) => s.source_files.as_ref(),
+            InitializedFacet::APIBundle(_) => None,
+        })
+        .flatten()
+        .map(source_file_path)
+        .collect()
+}
+
+/// Fetches the state file of a project from its repo and checks its signature according to the
+/// verification policy.
+///
+/// # Errors
+///
+/// Returns an error if the state file can't be fetched or its signature doesn't pass the policy.
+pub async fn fetch_verified_state(
+    repo_service: &LocalRepoService,
+    signing: &StateSigningConfig,
+    repo: &InitializedRepo,
+) -> Result<String, SkootError> {
+    let content = repo_service
+        .fetch_file_content(repo, STATE_FILE_NAME)
+        .await?;
+    let signature = repo_service
+        .fetch_optional_file_content(repo, STATE_SIGNATURE_FILE_NAME)
+        .await?;
+    signing::check(signing, &content, signature.as_deref(), &repo.full_url())?;
+    Ok(content)
+}
+
 pub trait ProjectReferenceCache {
     fn list(&self)
         -> impl std::future::Future<Output = Result<HashSet<String>, SkootError>> + Send;
@@ -87,6 +436,15 @@ pub trait ProjectReferenceCache {
         &mut self,
         repo_url: String,
     ) -> impl std::future::Future<Output = Result<(), SkootError>> + Send;
+    /// Lists the references to all the known projects along with any metadata stored for them.
+    fn list_references(
+        &self,
+    ) -> impl std::future::Future<Output = Result<Vec<ProjectReference>, SkootError>> + Send;
+    /// Adds or updates the reference to a project along with its metadata.
+    fn set_project(
+        &mut self,
+        project: &InitializedProject,
+    ) -> impl std::future::Future<Output = Result<(), SkootError>> + Send;
 }
 
 pub struct InMemoryProjectReferenceCache {
@@ -95,6 +453,7 @@ pub struct InMemoryProjectReferenceCache {
     pub local_source_service: LocalSourceService,
     pub local_repo_service: LocalRepoService,
     pub clone_path: String,
+    pub signing: StateSigningConfig,
 }
 
 impl ProjectReferenceCache for InMemoryProjectReferenceCache {
@@ -104,12 +463,8 @@ impl ProjectReferenceCache for InMemoryProjectReferenceCache {
 
     async fn get(&mut self, repo_url: String) -> Result<InitializedProject, SkootError> {
         let repo = InitializedRepo::try_from(repo_url)?;
-        let project = self
-            .local_repo_service
-            .fetch_file_content(&repo, ".skootrs")
-            .await?;
-        let initialized_project: InitializedProject = serde_json::from_str(&project)?;
-        Ok(initialized_project)
+        let project = fetch_verified_state(&self.local_repo_service, &self.signing, &repo).await?;
+        Ok(ProjectState::from_json(&project)?.project)
     }
 
     async fn set(&mut self, repo_url: String) -> Result<(), SkootError> {
@@ -123,6 +478,24 @@ impl ProjectReferenceCache for InMemoryProjectReferenceCache {
         self.save()?;
         Ok(())
     }
+
+    async fn list_references(&self) -> Result<Vec<ProjectReference>, SkootError> {
+        Ok(self
+            .cache
+            .iter()
+            .map(|repo_url| ProjectReference {
+                repo_url: repo_url.clone(),
+                name: None,
+                ecosystem: None,
+                last_updated: None,
+                facet_count: None,
+            })
+            .collect())
+    }
+
+    async fn set_project(&mut self, project: &InitializedProject) -> Result<(), SkootError> {
+        self.set(project.repo.full_url()).await
+    }
 }
 
 impl InMemoryProjectReferenceCache {
@@ -133,8 +506,9 @@ impl InMemoryProjectReferenceCache {
             save_path,
             cache: HashSet::new(),
             local_source_service: LocalSourceService {},
-            local_repo_service: LocalRepoService {},
+            local_repo_service: LocalRepoService::default(),
             clone_path: "/tmp".to_string(),
+            signing: StateSigningConfig::default(),
         }
     }
 
@@ -176,3 +550,283 @@ impl InMemoryProjectReferenceCache {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use skootrs_model::skootrs::state::SignatureVerificationPolicy;
+    use skootrs_model::skootrs::{
+        facet::SupportedFacetType, label::Label, GithubUser, InitializedEcosystem,
+        InitializedGithubRepo, InitializedGo,
+    };
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn source_bundle(
+        facet_type: SupportedFacetType,
+        files: &[(&str, &str, &str)],
+    ) -> InitializedFacet {
+        InitializedFacet::SourceBundle(SourceBundleFacet {
+            source_files: Some(
+                files
+                    .iter()
+                    .map(|(path, name, hash)| SourceFile {
+                        name: (*name).to_string(),
+                        path: (*path).to_string(),
+                        hash: (*hash).to_string(),
+                    })
+                    .collect(),
+            ),
+            facet_type,
+            source_files_content: None,
+            labels: vec![Label::Custom("test".to_string())],
+            conflicts: vec![],
+            config: None,
+        })
+    }
+
+    fn project(source: &InitializedSource, facets: Vec<InitializedFacet>) -> InitializedProject {
+        InitializedProject {
+            repo: InitializedRepo::Github(InitializedGithubRepo {
+                name: "test".to_string(),
+                organization: GithubUser::User("testuser".to_string()),
+                host: "github.com".to_string(),
+                default_branch: "main".to_string(),
+                settings: None,
+            }),
+            ecosystem: InitializedEcosystem::Go(InitializedGo {
+                name: "test".to_string(),
+                host: "github.com/testuser".to_string(),
+            }),
+            source: source.clone(),
+            facets: facets
+                .into_iter()
+                .map(|f| (FacetMapKey::Type(f.facet_type()), f))
+                .collect(),
+            name: "test".to_string(),
+            excluded_facets: vec![],
+        }
+    }
+
+    fn write(source: &InitializedSource, path: &str, content: &str) {
+        let full_path = Path::new(&source.path).join(path);
+        std::fs::create_dir_all(full_path.parent().unwrap()).unwrap();
+        std::fs::write(full_path, content).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_history_diff_and_rollback() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let store = GitProjectStateStore {
+            source: source.clone(),
+            source_service: LocalSourceService {},
+            signing: StateSigningConfig::default(),
+        };
+        store.git(&["init"]).unwrap();
+        store
+            .git(&["config", "user.email", "test@example.com"])
+            .unwrap();
+        store.git(&["config", "user.name", "Test"]).unwrap();
+
+        write(&source, "README.md", "one");
+        let readme_v1 = source_bundle(SupportedFacetType::Readme, &[("./", "README.md", "one")]);
+        store
+            .create(project(&source, vec![readme_v1]))
+            .await
+            .unwrap();
+
+        write(&source, "README.md", "two");
+        write(&source, ".github/workflows/scorecard.yml", "scorecard");
+        let readme_v2 = source_bundle(SupportedFacetType::Readme, &[("./", "README.md", "two")]);
+        let scorecard = source_bundle(
+            SupportedFacetType::Scorecard,
+            &[("./.github/workflows", "scorecard.yml", "scorecard")],
+        );
+        store
+            .update(project(&source, vec![readme_v2, scorecard]))
+            .await
+            .unwrap();
+
+        let history = store.history().await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].author, "Test <test@example.com>");
+        let (newest, oldest) = (history[0].commit.clone(), history[1].commit.clone());
+
+        let diff = store.diff(oldest.clone(), newest).await.unwrap();
+        assert_eq!(diff.facets.len(), 2);
+        assert!(diff
+            .facets
+            .contains(&FacetStateDiff::Added(FacetMapKey::Type(
+                SupportedFacetType::Scorecard
+            ))));
+        assert!(diff.facets.iter().any(|d| matches!(
+            d,
+            FacetStateDiff::Changed { facet: FacetMapKey::Type(SupportedFacetType::Readme), files }
+                if matches!(files[..], [SourceFileDiff::Changed { .. }])
+        )));
+
+        let restored = store.rollback(oldest).await.unwrap();
+        assert_eq!(restored.facets.len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(temp_dir.path().join("README.md")).unwrap(),
+            "one"
+        );
+        assert!(!temp_dir
+            .path()
+            .join(".github/workflows/scorecard.yml")
+            .exists());
+        assert_eq!(store.read().await.unwrap().unwrap().facets.len(), 1);
+        assert_eq!(store.history().await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_rollback_rejects_paths_outside_source() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let outside = temp_dir.path().join("outside.txt");
+        std::fs::write(&outside, "keep me").unwrap();
+        let repo_dir = temp_dir.path().join("repo");
+        std::fs::create_dir(&repo_dir).unwrap();
+        let source = InitializedSource {
+            path: repo_dir.to_str().unwrap().to_string(),
+        };
+        let store = GitProjectStateStore {
+            source: source.clone(),
+            source_service: LocalSourceService {},
+            signing: StateSigningConfig::default(),
+        };
+        store.git(&["init"]).unwrap();
+        store
+            .git(&["config", "user.email", "test@example.com"])
+            .unwrap();
+        store.git(&["config", "user.name", "Test"]).unwrap();
+
+        store.create(project(&source, vec![])).await.unwrap();
+        let initial = store.history().await.unwrap()[0].commit.clone();
+        // A malicious state file that claims a file outside of the checkout belongs to a facet.
+        let malicious = source_bundle(SupportedFacetType::Readme, &[("../", "outside.txt", "x")]);
+        store
+            .update(project(&source, vec![malicious]))
+            .await
+            .unwrap();
+
+        let result = store.rollback(initial).await;
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read_to_string(outside).unwrap(), "keep me");
+    }
+
+    #[tokio::test]
+    async fn test_read_at_rejects_options() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let store = GitProjectStateStore {
+            source: source.clone(),
+            source_service: LocalSourceService {},
+            signing: StateSigningConfig::default(),
+        };
+        store.git(&["init"]).unwrap();
+        store
+            .git(&["config", "user.email", "test@example.com"])
+            .unwrap();
+        store.git(&["config", "user.name", "Test"]).unwrap();
+        store.create(project(&source, vec![])).await.unwrap();
+
+        let output = temp_dir.path().join("output.txt");
+        for commit in [
+            format!("--output={}", output.display()),
+            String::new(),
+            "does-not-exist".to_string(),
+        ] {
+            assert!(store.read_at(commit.clone()).await.is_err());
+            assert!(store.rollback(commit).await.is_err());
+        }
+        assert!(!output.exists());
+        assert!(store.read_at("HEAD".to_string()).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_write_state_commits_other_changes() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let store = GitProjectStateStore {
+            source: source.clone(),
+            source_service: LocalSourceService {},
+            signing: StateSigningConfig::default(),
+        };
+        store.git(&["init"]).unwrap();
+        store
+            .git(&["config", "user.email", "test@example.com"])
+            .unwrap();
+        store.git(&["config", "user.name", "Test"]).unwrap();
+        write(&source, "README.md", "one");
+        store.create(project(&source, vec![])).await.unwrap();
+
+        std::fs::remove_file(temp_dir.path().join("README.md")).unwrap();
+        store
+            .write_state(
+                project(&source, vec![]),
+                "Removed the Readme facet".to_string(),
+            )
+            .unwrap();
+
+        assert!(store.git(&["status", "--porcelain"]).unwrap().is_empty());
+        let changed = store
+            .git(&["show", "--name-only", "--format=", "HEAD"])
+            .unwrap();
+        assert!(changed.lines().any(|line| line == "README.md"));
+        assert!(changed.lines().any(|line| line == STATE_FILE_NAME));
+    }
+
+    #[tokio::test]
+    async fn test_create_refuses_existing_state() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let store = GitProjectStateStore {
+            source: source.clone(),
+            source_service: LocalSourceService {},
+            signing: StateSigningConfig::default(),
+        };
+        store.git(&["init"]).unwrap();
+        store
+            .git(&["config", "user.email", "test@example.com"])
+            .unwrap();
+        store.git(&["config", "user.name", "Test"]).unwrap();
+        store.create(project(&source, vec![])).await.unwrap();
+
+        assert!(store.create(project(&source, vec![])).await.is_err());
+        assert!(store.update(project(&source, vec![])).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_read_at_enforces_signatures() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let source = InitializedSource {
+            path: temp_dir.path().to_str().unwrap().to_string(),
+        };
+        let mut store = GitProjectStateStore {
+            source: source.clone(),
+            source_service: LocalSourceService {},
+            signing: StateSigningConfig::default(),
+        };
+        store.git(&["init"]).unwrap();
+        store
+            .git(&["config", "user.email", "test@example.com"])
+            .unwrap();
+        store.git(&["config", "user.name", "Test"]).unwrap();
+        store.create(project(&source, vec![])).await.unwrap();
+
+        store.signing.verification_policy = SignatureVerificationPolicy::Enforce;
+
+        assert!(store.read_at("HEAD".to_string()).await.is_err());
+        assert!(store.rollback("HEAD".to_string()).await.is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//
+// Copyright 2024 The Skootrs Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Signing and verification of the `.skootrs` state file.
+//!
+//! Signatures are detached `sshsig` signatures, i.e. the same format produced by
+//! `ssh-keygen -Y sign`, so they can also be checked outside of Skootrs with
+//! `ssh-keygen -Y verify -n skootrs-state`.
+
+use std::{error::Error, fmt, path::Path};
+
+use ssh_agent_client_rs::Client;
+use ssh_key::{HashAlg, LineEnding, PrivateKey, PublicKey, SshSig};
+use tracing::{debug, warn};
+
+use skootrs_model::skootrs::{
+    state::{SignatureVerificationPolicy, StateSigningConfig, StateSigningKey},
+    SkootError,
+};
+
+/// The `sshsig` namespace for state file signatures. This keeps a state file signature from being
+/// valid for anything else signed with the same key.
+pub const SIGNATURE_NAMESPACE: &str = "skootrs-state";
+
+/// Signs the content of a state file and returns the PEM encoded detached signature.
+///
+/// # Errors
+///
+/// Returns an error if the key can't be loaded or the content can't be signed.
+pub fn sign(key: &StateSigningKey, content: &[u8]) -> Result<String, SkootError> {
+    let signature =
+        match key {
+            StateSigningKey::KeyFile(path) => PrivateKey::read_openssh_file(Path::new(path))?
+                .sign(SIGNATURE_NAMESPACE, HashAlg::Sha512, content)?,
+            StateSigningKey::SshAgent(public_key_path) => {
+                let public_key = PublicKey::read_openssh_file(Path::new(public_key_path))?;
+                let socket = std::env::var("SSH_AUTH_SOCK").map_err(|_| {
+                    SkootError::from("SSH_AUTH_SOCK must be set to sign with ssh-agent")
+                })?;
+                let mut client = Client::connect(Path::new(&socket))?;
+                let signed_data =
+                    SshSig::signed_data(SIGNATURE_NAMESPACE, HashAlg::Sha512, content)?;
+                let agent_signature = client.sign(&public_key, &signed_data)?;
+                SshSig::new(
+                    public_key.key_data().clone(),
+                    SIGNATURE_NAMESPACE,
+                    HashAlg::Sha512,
+                    agent_signature,
+                )?
+            }
+        };
+
+    Ok(signature.to_pem(LineEnding::LF)?)
+}
+
+/// Verifies a detached signature over the content of a state file against a set of trusted OpenSSH
+/// public keys.
+///
+/// # Errors
+///
+/// Returns an error if the signature is malformed, wasn't made by a trusted key, or doesn't match the
+/// content.
+pub fn verify(
+    trusted_keys: &[String],
+    content: &[u8],
+    signature: &str,
+) -> Result<(), SignatureError> {
+    let signature =
+        SshSig::from_pem(signature).map_err(|e| SignatureError::Invalid(e.to_string()))?;
+    let signer = trusted_keys
+        .iter()
+        .filter_map(|key| PublicKey::from_openssh(key).ok())
+        .find(|key| key.key_data() == signature.public_key())
+        .ok_or(SignatureError::UntrustedKey)?;
+
+    signer
+        .verify(SIGNATURE_NAMESPACE, content, &signature)
+        .map_err(|e| SignatureError::Invalid(e.to_string()))
+}
+
+/// Checks the signature of a state file according to the verification policy of `config`. The
+/// `location` names the state file being checked in warnings, e.g. a path or repo URL.
+///
+/// # Errors
+///
+/// Returns an error if the signature is missing or doesn't verify and the policy is
+/// `SignatureVerificationPolicy::Enforce`.
+pub fn check(
+    config: &StateSigningConfig,
+    content: &str,
+    signature: Option<&str>,
+    location: &str,
+) -> Result<(), SignatureError> {
+    if config.verification_policy == SignatureVerificationPolicy::Ignore {
+        return Ok(());
+    }
+
+    let result = signature.map_or(Err(SignatureError::Missing), |signature| {
+        verify(&config.trusted_keys, content.as_bytes(), signature)
+    });
+
+    match (result, &config.verification_policy) {
+        (Ok(()), _) | (Err(_), SignatureVerificationPolicy::Ignore) => Ok(()),
+        // Without any signing configured the state file isn't expected to be signed, so there's
+        // nothing to warn about.
+        (Err(e), SignatureVerificationPolicy::Warn) if !config.is_configured() => {
+            debug!("{e} for {location}");
+            Ok(())
+        }
+        (Err(e), SignatureVerificationPolicy::Warn) => {
+            warn!("{e} for {location}");
+            Ok(())
+        }
+        (Err(e), SignatureVerificationPolicy::Enforce) => Err(e),
+    }
+}
+
+/// The reasons a state file can fail signature verification.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SignatureError {
+    /// The state file has no signature.
+    Missing,
+    /// The signature was made by a key that isn't trusted.
+    UntrustedKey,
+    /// The signature is malformed or doesn't match the state file.
+    Invalid(String),
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing => write!(f, "State file is not signed"),
+            Self::UntrustedKey => write!(f, "State file is signed by an untrusted key"),
+            Self::Invalid(e) => write!(f, "State file signature is invalid: {e}"),
+        }
+    }
+}
+
+impl Error for SignatureError {}
+
+#[cfg(test)]
+mod tests {
+    use ssh_key::{rand_core::OsRng, Algorithm, EcdsaCurve};
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn generate_key_file(dir: &TempDir, algorithm: Algorithm) -> (StateSigningKey, String) {
+        let private_key = PrivateKey::random(&mut OsRng, algorithm).unwrap();
+        let path = dir.path().join("key");
+        private_key
+            .write_openssh_file(&path, LineEnding::LF)
+            .unwrap();
+        (
+            StateSigningKey::KeyFile(path.to_str().unwrap().to_string()),
+            private_key.public_key().to_openssh().unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_sign_and_verify_ed25519() {
+        let dir = TempDir::new("test").unwrap();
+        let (key, public_key) = generate_key_file(&dir, Algorithm::Ed25519);
+        let signature = sign(&key, b"state").unwrap();
+        assert!(verify(&[public_key], b"state", &signature).is_ok());
+    }
+
+    #[test]
+    fn test_sign_and_verify_ecdsa() {
+        let dir = TempDir::new("test").unwrap();
+        let (key, public_key) = generate_key_file(
+            &dir,
+            Algorithm::Ecdsa {
+                curve: EcdsaCurve::NistP256,
+            },
+        );
+        let signature = sign(&key, b"state").unwrap();
+        assert!(verify(&[public_key], b"state", &signature).is_ok());
+    }
+
+    #[test]
+    fn test_verify_tampered_content() {
+        let dir = TempDir::new("test").unwrap();
+        let (key, public_key) = generate_key_file(&dir, Algorithm::Ed25519);
+        let signature = sign(&key, b"state").unwrap();
+        assert!(matches!(
+            verify(&[public_key], b"tampered state", &signature),
+            Err(SignatureError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn test_check_policies() {
+        let dir = TempDir::new("test").unwrap();
+        let (key, public_key) = generate_key_file(&dir, Algorithm::Ed25519);
+        let signature = sign(&key, b"state").unwrap();
+        let enforce = StateSigningConfig {
+            signing_key: None,
+            trusted_keys: vec![public_key],
+            verification_policy: SignatureVerificationPolicy::Enforce,
+        };
+        let warn = StateSigningConfig {
+            verification_policy: SignatureVerificationPolicy::Warn,
+            ..enforce.clone()
+        };
+
+        assert!(check(&enforce, "state", Some(&signature), "test").is_ok());
+        assert_eq!(
+            check(&enforce, "state", None, "test"),
+            Err(SignatureError::Missing)
+        );
+        assert!(matches!(
+            check(&enforce, "tampered state", Some(&signature), "test"),
+            Err(SignatureError::Invalid(_))
+        ));
+        assert!(check(&warn, "tampered state", Some(&signature), "test").is_ok());
+    }
+
+    #[test]
+    fn test_verify_untrusted_key() {
+        let dir = TempDir::new("test").unwrap();
+        let (key, _) = generate_key_file(&dir, Algorithm::Ed25519);
+        let other_dir = TempDir::new("test").unwrap();
+        let (_, other_public_key) = generate_key_file(&other_dir, Algorithm::Ed25519);
+        let signature = sign(&key, b"state").unwrap();
+        assert_eq!(
+            verify(&[other_public_key], b"state", &signature),
+            Err(SignatureError::UntrustedKey)
+        );
+    }
+}
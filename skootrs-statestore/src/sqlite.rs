@@ -0,0 +1,359 @@
+//
+// Copyright 2024 The Skootrs Authors.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A `ProjectReferenceCache` backed by a `SQLite` database.
+//!
+//! The database lives under the user's data directory (e.g. `$XDG_DATA_HOME/skootrs`) so the same
+//! projects are known no matter which directory Skootrs is run from. Every operation opens its own
+//! connection and writes happen in transactions, so concurrent Skootrs processes rely on `SQLite`'s
+//! file locking instead of clobbering each other.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, TransactionBehavior};
+use tracing::info;
+
+use skootrs_lib::service::repo::LocalRepoService;
+use skootrs_model::skootrs::{
+    state::{ProjectReference, ProjectState, StateSigningConfig},
+    InitializedProject, InitializedRepo, SkootError,
+};
+
+use crate::{fetch_verified_state, ProjectReferenceCache};
+
+/// How long to wait for another Skootrs process to release its lock on the database.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The metadata key recording that the legacy JSON cache has already been imported.
+const LEGACY_IMPORTED_KEY: &str = "legacy_json_imported";
+
+/// A `ProjectReferenceCache` that stores project references and their metadata in a `SQLite` database.
+pub struct SqliteProjectReferenceCache {
+    pub db_path: PathBuf,
+    pub local_repo_service: LocalRepoService,
+    pub signing: StateSigningConfig,
+}
+
+impl SqliteProjectReferenceCache {
+    /// Opens the cache at `db_path`, creating the database and its parent directories if they don't exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the database can't be created or opened.
+    pub fn open(db_path: PathBuf) -> Result<Self, SkootError> {
+        if let Some(parent) = db_path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let cache = Self {
+            db_path,
+            local_repo_service: LocalRepoService::default(),
+            signing: StateSigningConfig::default(),
+        };
+        let connection = cache.connection()?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS projects (
+                repo_url TEXT PRIMARY KEY NOT NULL,
+                name TEXT,
+                ecosystem TEXT,
+                last_updated TEXT,
+                facet_count INTEGER
+            );
+            CREATE TABLE IF NOT EXISTS metadata (
+                key TEXT PRIMARY KEY NOT NULL,
+                value TEXT NOT NULL
+            );",
+        )?;
+        Ok(cache)
+    }
+
+    /// Opens the cache at the default location under the user's data directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data directory can't be determined or the database can't be opened.
+    pub fn open_default() -> Result<Self, SkootError> {
+        Self::open(Self::default_path()?)
+    }
+
+    /// Returns the default location of the cache, e.g. `~/.local/share/skootrs/projects.db` on Linux.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user's data directory can't be determined.
+    pub fn default_path() -> Result<PathBuf, SkootError> {
+        let data_dir = dirs::data_dir().ok_or("Failed to find the user's data directory")?;
+        Ok(data_dir.join("skootrs").join("projects.db"))
+    }
+
+    /// Imports the references from a legacy JSON cache file, i.e. the `./skootcache` file written by
+    /// `InMemoryProjectReferenceCache`. The import only happens once per database, so later calls are
+    /// a no-op even if the legacy file changes. Returns the number of imported references.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the legacy file exists but can't be read or the references can't be stored.
+    pub fn import_legacy_json(&self, legacy_path: &Path) -> Result<usize, SkootError> {
+        let mut connection = self.connection()?;
+        // The write lock is taken up front so two processes opening the cache for the first time can't
+        // both see that the import hasn't happened yet.
+        let transaction = connection.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        let already_imported: Option<String> = transaction
+            .query_row(
+                "SELECT value FROM metadata WHERE key = ?1",
+                params![LEGACY_IMPORTED_KEY],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if already_imported.is_some() || !legacy_path.exists() {
+            return Ok(0);
+        }
+
+        let legacy_cache: HashSet<String> =
+            serde_json::from_str(&std::fs::read_to_string(legacy_path)?)?;
+        let mut imported = 0;
+        for repo_url in &legacy_cache {
+            imported += transaction.execute(
+                "INSERT OR IGNORE INTO projects (repo_url) VALUES (?1)",
+                params![repo_url],
+            )?;
+        }
+        transaction.execute(
+            "INSERT INTO metadata (key, value) VALUES (?1, ?2)",
+            params![LEGACY_IMPORTED_KEY, Utc::now().to_rfc3339()],
+        )?;
+        transaction.commit()?;
+        info!(
+            "Imported {imported} project references from {}",
+            legacy_path.display()
+        );
+        Ok(imported)
+    }
+
+    fn connection(&self) -> Result<Connection, SkootError> {
+        let connection = Connection::open(&self.db_path)?;
+        connection.busy_timeout(BUSY_TIMEOUT)?;
+        Ok(connection)
+    }
+
+    fn upsert(&self, reference: &ProjectReference) -> Result<(), SkootError> {
+        let facet_count = reference.facet_count.map(i64::try_from).transpose()?;
+        self.connection()?.execute(
+            "INSERT INTO projects (repo_url, name, ecosystem, last_updated, facet_count)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                ON CONFLICT (repo_url) DO UPDATE SET
+                    name = COALESCE(excluded.name, name),
+                    ecosystem = COALESCE(excluded.ecosystem, ecosystem),
+                    last_updated = COALESCE(excluded.last_updated, last_updated),
+                    facet_count = COALESCE(excluded.facet_count, facet_count)",
+            params![
+                reference.repo_url,
+                reference.name,
+                reference.ecosystem,
+                reference.last_updated,
+                facet_count,
+            ],
+        )?;
+        Ok(())
+    }
+}
+
+impl ProjectReferenceCache for SqliteProjectReferenceCache {
+    async fn list(&self) -> Result<HashSet<String>, SkootError> {
+        Ok(self
+            .list_references()
+            .await?
+            .into_iter()
+            .map(|reference| reference.repo_url)
+            .collect())
+    }
+
+    async fn get(&mut self, repo_url: String) -> Result<InitializedProject, SkootError> {
+        let repo = InitializedRepo::try_from(repo_url)?;
+        let project = fetch_verified_state(&self.local_repo_service, &self.signing, &repo).await?;
+        let initialized_project = ProjectState::from_json(&project)?.project;
+        // Keep the metadata fresh since we already have the latest state in hand.
+        self.upsert(&ProjectReference::from_project(&initialized_project))?;
+        Ok(initialized_project)
+    }
+
+    async fn set(&mut self, repo_url: String) -> Result<(), SkootError> {
+        self.upsert(&ProjectReference {
+            repo_url,
+            name: None,
+            ecosystem: None,
+            last_updated: None,
+            facet_count: None,
+        })
+    }
+
+    async fn delete(&mut self, repo_url: String) -> Result<(), SkootError> {
+        self.connection()?.execute(
+            "DELETE FROM projects WHERE repo_url = ?1",
+            params![repo_url],
+        )?;
+        Ok(())
+    }
+
+    async fn list_references(&self) -> Result<Vec<ProjectReference>, SkootError> {
+        let connection = self.connection()?;
+        let mut statement = connection.prepare(
+            "SELECT repo_url, name, ecosystem, last_updated, facet_count
+                FROM projects ORDER BY repo_url",
+        )?;
+        let references = statement
+            .query_map([], |row| {
+                let facet_count: Option<i64> = row.get(4)?;
+                Ok(ProjectReference {
+                    repo_url: row.get(0)?,
+                    name: row.get(1)?,
+                    ecosystem: row.get(2)?,
+                    last_updated: row.get::<_, Option<DateTime<Utc>>>(3)?,
+                    facet_count: facet_count.and_then(|count| usize::try_from(count).ok()),
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(references)
+    }
+
+    async fn set_project(&mut self, project: &InitializedProject) -> Result<(), SkootError> {
+        self.upsert(&ProjectReference::from_project(project))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use skootrs_model::skootrs::{
+        GithubUser, InitializedEcosystem, InitializedGithubRepo, InitializedGo, InitializedSource,
+    };
+    use tempdir::TempDir;
+
+    use super::*;
+
+    fn project() -> InitializedProject {
+        InitializedProject {
+            repo: InitializedRepo::Github(InitializedGithubRepo {
+                name: "test".to_string(),
+                organization: GithubUser::User("testuser".to_string()),
+                host: "github.com".to_string(),
+                default_branch: "main".to_string(),
+                settings: None,
+            }),
+            ecosystem: InitializedEcosystem::Go(InitializedGo {
+                name: "test".to_string(),
+                host: "github.com/testuser".to_string(),
+            }),
+            source: InitializedSource {
+                path: "/tmp/test".to_string(),
+            },
+            facets: HashMap::new(),
+            name: "test".to_string(),
+            excluded_facets: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_list_and_delete() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let mut cache =
+            SqliteProjectReferenceCache::open(temp_dir.path().join("nested/projects.db")).unwrap();
+
+        cache.set_project(&project()).await.unwrap();
+        cache
+            .set("https://github.com/testuser/other".to_string())
+            .await
+            .unwrap();
+        // Setting just the URL again shouldn't wipe the stored metadata.
+        cache
+            .set("https://github.com/testuser/test".to_string())
+            .await
+            .unwrap();
+
+        let references = cache.list_references().await.unwrap();
+        assert_eq!(references.len(), 2);
+        assert_eq!(references[0].repo_url, "https://github.com/testuser/other");
+        assert_eq!(references[0].name, None);
+        assert_eq!(references[1].name, Some("test".to_string()));
+        assert_eq!(references[1].ecosystem, Some("Go".to_string()));
+        assert_eq!(references[1].facet_count, Some(0));
+        assert!(references[1].last_updated.is_some());
+
+        cache
+            .delete("https://github.com/testuser/other".to_string())
+            .await
+            .unwrap();
+        assert_eq!(
+            cache.list().await.unwrap(),
+            HashSet::from(["https://github.com/testuser/test".to_string()])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_import_legacy_json_once() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let legacy_path = temp_dir.path().join("skootcache");
+        std::fs::write(
+            &legacy_path,
+            r#"["https://github.com/testuser/a", "https://github.com/testuser/b"]"#,
+        )
+        .unwrap();
+        let cache = SqliteProjectReferenceCache::open(temp_dir.path().join("projects.db")).unwrap();
+
+        assert_eq!(cache.import_legacy_json(&legacy_path).unwrap(), 2);
+        std::fs::write(&legacy_path, r#"["https://github.com/testuser/c"]"#).unwrap();
+        assert_eq!(cache.import_legacy_json(&legacy_path).unwrap(), 0);
+        assert_eq!(cache.list().await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_import_legacy_json_concurrently() {
+        let temp_dir = TempDir::new("test").unwrap();
+        let legacy_path = temp_dir.path().join("skootcache");
+        std::fs::write(
+            &legacy_path,
+            r#"["https://github.com/testuser/a", "https://github.com/testuser/b"]"#,
+        )
+        .unwrap();
+        let db_path = temp_dir.path().join("projects.db");
+        SqliteProjectReferenceCache::open(db_path.clone()).unwrap();
+
+        let imports = (0..4)
+            .map(|_| {
+                let db_path = db_path.clone();
+                let legacy_path = legacy_path.clone();
+                std::thread::spawn(move || {
+                    SqliteProjectReferenceCache::open(db_path)
+                        .unwrap()
+                        .import_legacy_json(&legacy_path)
+                        .unwrap()
+                })
+            })
+            .collect::<Vec<_>>();
+        let imported = imports
+            .into_iter()
+            .map(|import| import.join().unwrap())
+            .collect::<Vec<_>>();
+
+        // Only one of the processes imports the references.
+        assert_eq!(imported.iter().sum::<usize>(), 2);
+        assert_eq!(imported.iter().filter(|count| **count == 2).count(), 1);
+    }
+}